@@ -1,16 +1,64 @@
+use std::borrow::Cow;
+
 use chrono::{Duration, NaiveDateTime, TimeDelta};
-use openwhoop_codec::{Activity, ParsedHistoryReading};
+use openwhoop_codec::{Activity, BodyLocation, ParsedHistoryReading};
+
+use crate::helpers::gravity::gravity_delta;
 
 const ACTIVITY_CHANGE_THRESHOLD: Duration = Duration::minutes(15);
 const MIN_SLEEP_DURATION: Duration = Duration::minutes(60);
 pub const MAX_SLEEP_PAUSE: Duration = Duration::minutes(60);
 
 // Gravity-based detection thresholds (from notebook analysis)
-const GRAVITY_STILL_THRESHOLD: f32 = 0.01; // g - max delta to be considered "still"
 const GRAVITY_WINDOW_MINUTES: i64 = 15; // rolling window size in minutes
-const GRAVITY_STILL_FRACTION: f32 = 0.70; // fraction of still readings to classify as sleep
 const GRAVITY_MAX_GAP: Duration = Duration::minutes(20); // break runs on data gaps larger than this
 
+/// Gravity stillness thresholds for where the strap is worn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct GravityThresholds {
+    /// Max delta in g between readings to be considered "still"
+    still: f32,
+    /// Fraction of still readings in the window to classify as sleep
+    still_fraction: f32,
+}
+
+impl GravityThresholds {
+    /// The values come from the notebook analysis of wrist-worn captures.
+    /// There are no bicep captures to tune on yet, so every placement uses
+    /// the wrist values for now.
+    fn for_location(_location: BodyLocation) -> Self {
+        Self {
+            still: 0.01,
+            still_fraction: 0.70,
+        }
+    }
+}
+
+/// Where the strap was worn over time, from the wear windows stored with
+/// downloaded readings. Each placement holds from its start until the next
+/// one starts; readings before the first are taken as wrist-worn, the
+/// strap's default placement.
+#[derive(Clone, Debug, Default)]
+pub struct WearTimeline {
+    windows: Vec<(NaiveDateTime, BodyLocation)>,
+}
+
+impl WearTimeline {
+    pub fn new(mut windows: Vec<(NaiveDateTime, BodyLocation)>) -> Self {
+        windows.sort_by_key(|(start, _)| *start);
+        Self { windows }
+    }
+
+    /// Placement in effect at `time`.
+    pub fn at(&self, time: NaiveDateTime) -> BodyLocation {
+        let index = self.windows.partition_point(|(start, _)| *start <= time);
+        index
+            .checked_sub(1)
+            .map(|i| self.windows[i].1)
+            .unwrap_or(BodyLocation::Wrist)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct ActivityPeriod {
     pub activity: Activity,
@@ -49,8 +97,63 @@ impl ActivityPeriod {
         None
     }
 
-    /// Detect sleep/active periods using the gravity vector.
-    pub fn detect_from_gravity(history: &[ParsedHistoryReading]) -> Vec<ActivityPeriod> {
+    /// Whether the strap was on the wrist: readings without heart rate or
+    /// without skin contact are off-wrist.
+    pub fn is_worn(reading: &ParsedHistoryReading) -> bool {
+        reading.bpm > 0
+            && reading
+                .sensor_data
+                .as_ref()
+                .is_none_or(|sensor| sensor.skin_contact > 0)
+    }
+
+    /// Sleeps in `history`, oldest first. Off-wrist readings are treated as
+    /// missing data, so a strap lying still on a table isn't taken for
+    /// sleep, and sleep segments split by gaps shorter than
+    /// [`MAX_SLEEP_PAUSE`] are merged into one sleep. `wear` is where the
+    /// strap was worn, see [`Self::detect_from_gravity`].
+    pub fn detect_sleeps(
+        history: &[ParsedHistoryReading],
+        wear: &WearTimeline,
+    ) -> Vec<ActivityPeriod> {
+        let history = if history.iter().all(Self::is_worn) {
+            Cow::Borrowed(history)
+        } else {
+            Cow::Owned(
+                history
+                    .iter()
+                    .filter(|reading| Self::is_worn(reading))
+                    .cloned()
+                    .collect(),
+            )
+        };
+
+        let mut sleeps = Vec::<ActivityPeriod>::new();
+        for period in Self::detect_from_gravity(&history, wear) {
+            if !matches!(period.activity, Activity::Sleep) {
+                continue;
+            }
+
+            match sleeps.last_mut() {
+                Some(last) if period.start - last.end < MAX_SLEEP_PAUSE => {
+                    last.end = period.end;
+                    last.duration = last.end - last.start;
+                }
+                _ => sleeps.push(period),
+            }
+        }
+
+        sleeps.retain(|sleep| sleep.duration > MIN_SLEEP_DURATION);
+        sleeps
+    }
+
+    /// Detect sleep/active periods using the gravity vector. Each reading's
+    /// window is judged with the stillness thresholds for where `wear` says
+    /// the strap was worn at that reading.
+    pub fn detect_from_gravity(
+        history: &[ParsedHistoryReading],
+        wear: &WearTimeline,
+    ) -> Vec<ActivityPeriod> {
         if history.len() < 2 {
             return Vec::new();
         }
@@ -60,12 +163,7 @@ impl ActivityPeriod {
                 history
                     .windows(2)
                     .map(|w| match (w[0].gravity, w[1].gravity) {
-                        (Some(a), Some(b)) => {
-                            let dx = a[0] - b[0];
-                            let dy = a[1] - b[1];
-                            let dz = a[2] - b[2];
-                            (dx * dx + dy * dy + dz * dz).sqrt()
-                        }
+                        (Some(a), Some(b)) => gravity_delta(a, b),
                         _ => f32::MAX, // no gravity data -> treat as active (moving)
                     }),
             )
@@ -84,26 +182,19 @@ impl ActivityPeriod {
         let window_size = window_size.max(3);
         let n = deltas.len();
 
-        let still_frac = (0..n)
+        let is_sleep = (0..n)
             .map(|i| {
+                let thresholds = GravityThresholds::for_location(wear.at(history[i].time));
                 let half = window_size / 2;
                 let start = i.saturating_sub(half);
                 let end = (i + half + 1).min(n);
                 let window = &deltas[start..end];
-                let still = window
-                    .iter()
-                    .filter(|&&d| d < GRAVITY_STILL_THRESHOLD)
-                    .count();
+                let still = window.iter().filter(|&&d| d < thresholds.still).count();
 
-                still as f32 / window.len() as f32
+                still as f32 / window.len() as f32 >= thresholds.still_fraction
             })
             .collect::<Vec<_>>();
 
-        let is_sleep = still_frac
-            .iter()
-            .map(|&f| f >= GRAVITY_STILL_FRACTION)
-            .collect::<Vec<_>>();
-
         let mut periods = Vec::new();
         let mut run_start = 0_usize;
 
@@ -213,6 +304,7 @@ mod tests {
             rr: vec![],
             imu_data: None,
             gravity,
+            sensor_data: None,
         }
     }
 
@@ -220,13 +312,13 @@ mod tests {
 
     #[test]
     fn detect_from_gravity_empty() {
-        assert!(ActivityPeriod::detect_from_gravity(&[]).is_empty());
+        assert!(ActivityPeriod::detect_from_gravity(&[], &WearTimeline::default()).is_empty());
     }
 
     #[test]
     fn detect_from_gravity_single_reading() {
         let history = vec![make_reading(0, Some([0.0, 0.0, 1.0]))];
-        assert!(ActivityPeriod::detect_from_gravity(&history).is_empty());
+        assert!(ActivityPeriod::detect_from_gravity(&history, &WearTimeline::default()).is_empty());
     }
 
     #[test]
@@ -235,7 +327,7 @@ mod tests {
         let history: Vec<_> = (0..120)
             .map(|m| make_reading(m, Some([0.0, 0.0, 1.0])))
             .collect();
-        let periods = ActivityPeriod::detect_from_gravity(&history);
+        let periods = ActivityPeriod::detect_from_gravity(&history, &WearTimeline::default());
         assert!(!periods.is_empty());
         assert!(matches!(periods[0].activity, Activity::Sleep));
     }
@@ -249,7 +341,7 @@ mod tests {
                 make_reading(m, Some([v, 0.0, 0.0]))
             })
             .collect();
-        let periods = ActivityPeriod::detect_from_gravity(&history);
+        let periods = ActivityPeriod::detect_from_gravity(&history, &WearTimeline::default());
         assert!(!periods.is_empty());
         assert!(matches!(periods[0].activity, Activity::Active));
     }
@@ -258,7 +350,7 @@ mod tests {
     fn detect_from_gravity_no_gravity_data_is_active() {
         // gravity: None -> delta = MAX -> classified as active
         let history: Vec<_> = (0..120).map(|m| make_reading(m, None)).collect();
-        let periods = ActivityPeriod::detect_from_gravity(&history);
+        let periods = ActivityPeriod::detect_from_gravity(&history, &WearTimeline::default());
         assert!(!periods.is_empty());
         assert!(matches!(periods[0].activity, Activity::Active));
     }
@@ -271,12 +363,103 @@ mod tests {
             .collect();
         // Jump 60 minutes forward (> GRAVITY_MAX_GAP = 20 min)
         history.extend((120..180).map(|m| make_reading(m, Some([0.0, 0.0, 1.0]))));
-        let periods = ActivityPeriod::detect_from_gravity(&history);
+        let periods = ActivityPeriod::detect_from_gravity(&history, &WearTimeline::default());
         // Both blocks are sleep (still), gap forces a break -> at least 2 periods
         // (though filter_merge may re-merge short ones; both are 60 min so they survive)
         assert!(periods.len() >= 2);
     }
 
+    #[test]
+    fn wear_timeline_holds_each_placement_until_the_next() {
+        let wear = WearTimeline::new(vec![
+            (base() + Duration::hours(8), BodyLocation::Bicep),
+            (base() + Duration::hours(2), BodyLocation::Wrist),
+        ]);
+
+        assert_eq!(wear.at(base()), BodyLocation::Wrist);
+        assert_eq!(wear.at(base() + Duration::hours(2)), BodyLocation::Wrist);
+        assert_eq!(wear.at(base() + Duration::hours(8)), BodyLocation::Bicep);
+        assert_eq!(wear.at(base() + Duration::days(2)), BodyLocation::Bicep);
+        assert_eq!(WearTimeline::default().at(base()), BodyLocation::Wrist);
+    }
+
+    // -- detect_sleeps -----------------------------------------------------
+
+    fn still(minutes: std::ops::Range<i64>) -> impl Iterator<Item = ParsedHistoryReading> {
+        minutes.map(|m| make_reading(m, Some([0.0, 0.0, 1.0])))
+    }
+
+    fn moving(minutes: std::ops::Range<i64>) -> impl Iterator<Item = ParsedHistoryReading> {
+        minutes.map(|m| {
+            let v = if m % 2 == 0 { 1.0_f32 } else { -1.0_f32 };
+            make_reading(m, Some([v, 0.0, 0.0]))
+        })
+    }
+
+    #[test]
+    fn detect_sleeps_merges_segments_split_by_missing_data() {
+        // Two hours of sleep, 40 minutes without data, two more hours
+        let history = still(0..120).chain(still(160..280)).collect::<Vec<_>>();
+        let sleeps = ActivityPeriod::detect_sleeps(&history, &WearTimeline::default());
+
+        assert_eq!(sleeps.len(), 1);
+        assert_eq!(sleeps[0].start, base());
+        assert_eq!(sleeps[0].end, base() + Duration::minutes(279));
+    }
+
+    #[test]
+    fn detect_sleeps_keeps_separate_nights_apart() {
+        let history = still(0..120)
+            .chain(moving(120..240))
+            .chain(still(240..360))
+            .collect::<Vec<_>>();
+        let sleeps = ActivityPeriod::detect_sleeps(&history, &WearTimeline::default());
+
+        assert_eq!(sleeps.len(), 2);
+        assert!(sleeps[0].end < sleeps[1].start);
+    }
+
+    #[test]
+    fn detect_sleeps_ignores_off_wrist_stillness() {
+        let mut history = moving(0..60)
+            .chain(still(60..240))
+            .chain(moving(240..300))
+            .collect::<Vec<_>>();
+        for reading in &mut history[60..240] {
+            reading.bpm = 0;
+        }
+        assert!(ActivityPeriod::detect_sleeps(&history, &WearTimeline::default()).is_empty());
+
+        for reading in &mut history[60..240] {
+            reading.bpm = 70;
+            reading.sensor_data = Some(openwhoop_codec::SensorData {
+                ppg_green: 0,
+                ppg_red_ir: 0,
+                spo2_red: 0,
+                spo2_ir: 0,
+                skin_temp_raw: 0,
+                ambient_light: 0,
+                led_drive_1: 0,
+                led_drive_2: 0,
+                resp_rate_raw: 0,
+                signal_quality: 0,
+                skin_contact: 0,
+                accel_gravity: [0.0, 0.0, 1.0],
+                spo2_pct: None,
+            });
+        }
+        assert!(ActivityPeriod::detect_sleeps(&history, &WearTimeline::default()).is_empty());
+    }
+
+    #[test]
+    fn detect_sleeps_drops_short_sleeps() {
+        let history = moving(0..60)
+            .chain(still(60..100))
+            .chain(moving(100..160))
+            .collect::<Vec<_>>();
+        assert!(ActivityPeriod::detect_sleeps(&history, &WearTimeline::default()).is_empty());
+    }
+
     // -- find_sleep ---------------------------------------------------------
 
     #[test]
@@ -0,0 +1,294 @@
+use openwhoop_codec::{ImuSample, ParsedHistoryReading};
+use openwhoop_types::activities::ActivityType;
+
+use crate::helpers::time_math::{mean, std_dev};
+
+/// Label for a detected workout and the share of its IMU windows that
+/// agree with it, 0-1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActivityClassification {
+    pub activity: ActivityType,
+    pub confidence: f64,
+}
+
+/// Motion of one window of IMU data.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct WindowFeatures {
+    /// Standard deviation of the acceleration magnitude (g)
+    intensity: f64,
+    /// Oscillations of the acceleration magnitude per minute
+    cadence: f64,
+    /// Mean angular velocity (deg/s)
+    rotation: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WindowLabel {
+    Still,
+    Cyclic(ActivityType),
+    Other,
+}
+
+/// Labels workouts from wrist IMU data with rules over windowed motion
+/// features: intensity, cadence and rotation.
+pub struct ActivityClassifier;
+
+impl ActivityClassifier {
+    /// Readings (seconds) per window.
+    const WINDOW: usize = 10;
+    const MIN_WINDOW: usize = 5;
+    /// Below this confidence the workout stays a generic `Activity`.
+    pub const MIN_CONFIDENCE: f64 = 0.6;
+
+    const STILL_INTENSITY: f64 = 0.03;
+    const STILL_ROTATION: f64 = 10.0;
+
+    const RUNNING_CADENCE: (f64, f64) = (130.0, 210.0);
+    const RUNNING_INTENSITY: f64 = 0.3;
+    const WALKING_CADENCE: (f64, f64) = (80.0, 130.0);
+    const WALKING_INTENSITY: (f64, f64) = (0.06, 0.3);
+    const ROWING_CADENCE: (f64, f64) = (16.0, 40.0);
+    const ROWING_INTENSITY: f64 = 0.15;
+    const ROWING_ROTATION: f64 = 40.0;
+    const CYCLING_INTENSITY: f64 = 0.06;
+    const CYCLING_ROTATION: f64 = 25.0;
+
+    /// Share of still windows expected between sets of strength training.
+    const STRENGTH_REST: (f64, f64) = (0.25, 0.75);
+
+    /// Classify the readings of a workout. Returns `None` when they carry
+    /// no IMU data.
+    pub fn classify(history: &[ParsedHistoryReading]) -> Option<ActivityClassification> {
+        let labels = Self::windows(history)
+            .iter()
+            .map(|window| Self::label(&Self::features(window)))
+            .collect::<Vec<_>>();
+        if labels.is_empty() {
+            return None;
+        }
+
+        let total = labels.len() as f64;
+        let count = |label: WindowLabel| labels.iter().filter(|&&l| l == label).count();
+        let still = count(WindowLabel::Still);
+        let other = count(WindowLabel::Other);
+        let active = labels.len() - still;
+
+        let rest_share = still as f64 / total;
+        let (activity, confidence) = if (Self::STRENGTH_REST.0..=Self::STRENGTH_REST.1)
+            .contains(&rest_share)
+            && other * 2 >= active
+        {
+            (ActivityType::Weightlifting, (still + other) as f64 / total)
+        } else {
+            [
+                ActivityType::Running,
+                ActivityType::Walking,
+                ActivityType::Rowing,
+                ActivityType::Cycling,
+            ]
+            .into_iter()
+            .map(|activity| {
+                (
+                    activity,
+                    count(WindowLabel::Cyclic(activity)) as f64 / total,
+                )
+            })
+            .fold((ActivityType::Activity, 0.0), |best, candidate| {
+                if candidate.1 > best.1 {
+                    candidate
+                } else {
+                    best
+                }
+            })
+        };
+
+        Some(ActivityClassification {
+            activity: if confidence >= Self::MIN_CONFIDENCE {
+                activity
+            } else {
+                ActivityType::Activity
+            },
+            confidence,
+        })
+    }
+
+    /// IMU samples grouped into windows of consecutive readings.
+    fn windows(history: &[ParsedHistoryReading]) -> Vec<Vec<&[ImuSample]>> {
+        let readings = history
+            .iter()
+            .filter_map(|r| r.imu_data.as_deref().filter(|imu| !imu.is_empty()))
+            .collect::<Vec<_>>();
+
+        readings
+            .chunks(Self::WINDOW)
+            .filter(|window| window.len() >= Self::MIN_WINDOW)
+            .map(|window| window.to_vec())
+            .collect()
+    }
+
+    fn features(window: &[&[ImuSample]]) -> WindowFeatures {
+        let samples = window.iter().flat_map(|imu| imu.iter()).collect::<Vec<_>>();
+        let magnitude = samples
+            .iter()
+            .map(|s| f64::from(s.acceleration()))
+            .collect::<Vec<_>>();
+        let rotation =
+            samples.iter().map(|s| f64::from(s.rotation())).sum::<f64>() / samples.len() as f64;
+
+        let mean = mean(&magnitude);
+        let intensity = std_dev(&magnitude);
+
+        // Light smoothing, then count oscillations with hysteresis so
+        // sensor noise is not mistaken for cadence.
+        let smoothed = magnitude
+            .windows(5)
+            .map(|w| w.iter().sum::<f64>() / 5.0 - mean)
+            .collect::<Vec<_>>();
+        let hysteresis = (intensity * 0.5).max(0.01);
+        let mut below = false;
+        let mut cycles = 0;
+        for value in smoothed {
+            if value < -hysteresis {
+                below = true;
+            } else if below && value > hysteresis {
+                below = false;
+                cycles += 1;
+            }
+        }
+
+        WindowFeatures {
+            intensity,
+            cadence: f64::from(cycles) * 60.0 / window.len() as f64,
+            rotation,
+        }
+    }
+
+    fn label(features: &WindowFeatures) -> WindowLabel {
+        let WindowFeatures {
+            intensity,
+            cadence,
+            rotation,
+        } = *features;
+        let within = |(low, high): (f64, f64), value: f64| (low..high).contains(&value);
+
+        if intensity < Self::STILL_INTENSITY && rotation < Self::STILL_ROTATION {
+            WindowLabel::Still
+        } else if within(Self::RUNNING_CADENCE, cadence) && intensity >= Self::RUNNING_INTENSITY {
+            WindowLabel::Cyclic(ActivityType::Running)
+        } else if within(Self::WALKING_CADENCE, cadence)
+            && within(Self::WALKING_INTENSITY, intensity)
+        {
+            WindowLabel::Cyclic(ActivityType::Walking)
+        } else if within(Self::ROWING_CADENCE, cadence)
+            && intensity >= Self::ROWING_INTENSITY
+            && rotation >= Self::ROWING_ROTATION
+        {
+            WindowLabel::Cyclic(ActivityType::Rowing)
+        } else if intensity < Self::CYCLING_INTENSITY && rotation < Self::CYCLING_ROTATION {
+            WindowLabel::Cyclic(ActivityType::Cycling)
+        } else {
+            WindowLabel::Other
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+
+    fn base() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(17, 0, 0)
+            .unwrap()
+    }
+
+    /// One reading per second with 100 IMU samples; `segments` are
+    /// (seconds, cadence per minute, amplitude in g, rotation in deg/s).
+    fn history(segments: &[(i64, f64, f32, f32)]) -> Vec<ParsedHistoryReading> {
+        let mut readings = Vec::new();
+        let mut second = 0;
+        for &(seconds, cadence, amplitude, rotation) in segments {
+            for _ in 0..seconds {
+                let imu = (0..100)
+                    .map(|i| {
+                        let t = second as f64 + f64::from(i) / 100.0;
+                        let phase = (2.0 * std::f64::consts::PI * cadence / 60.0 * t).sin();
+                        ImuSample {
+                            acc_x_g: 0.0,
+                            acc_y_g: 0.0,
+                            acc_z_g: 1.0 + amplitude * phase as f32,
+                            gyr_x_dps: rotation,
+                            gyr_y_dps: 0.0,
+                            gyr_z_dps: 0.0,
+                        }
+                    })
+                    .collect();
+                readings.push(ParsedHistoryReading {
+                    time: base() + TimeDelta::seconds(second),
+                    bpm: 140,
+                    rr: Vec::new(),
+                    imu_data: Some(imu),
+                    gravity: None,
+                    sensor_data: None,
+                });
+                second += 1;
+            }
+        }
+        readings
+    }
+
+    fn classify(segments: &[(i64, f64, f32, f32)]) -> ActivityClassification {
+        ActivityClassifier::classify(&history(segments)).unwrap()
+    }
+
+    #[test]
+    fn classifies_cyclic_sports() {
+        let running = classify(&[(600, 170.0, 0.8, 120.0)]);
+        assert_eq!(running.activity, ActivityType::Running);
+        assert_eq!(running.confidence, 1.0);
+
+        assert_eq!(
+            classify(&[(600, 110.0, 0.15, 60.0)]).activity,
+            ActivityType::Walking
+        );
+        assert_eq!(
+            classify(&[(600, 28.0, 0.4, 80.0)]).activity,
+            ActivityType::Rowing
+        );
+        assert_eq!(
+            classify(&[(600, 80.0, 0.02, 15.0)]).activity,
+            ActivityType::Cycling
+        );
+    }
+
+    #[test]
+    fn classifies_sets_with_rest_as_strength() {
+        let sets = (0..6)
+            .flat_map(|_| [(30, 60.0, 0.5, 60.0), (60, 0.0, 0.0, 0.0)])
+            .collect::<Vec<_>>();
+        let strength = classify(&sets);
+        assert_eq!(strength.activity, ActivityType::Weightlifting);
+        assert_eq!(strength.confidence, 1.0);
+    }
+
+    #[test]
+    fn low_confidence_stays_generic() {
+        let mixed = classify(&[
+            (200, 170.0, 0.8, 120.0),
+            (200, 110.0, 0.15, 60.0),
+            (200, 28.0, 0.4, 80.0),
+        ]);
+        assert_eq!(mixed.activity, ActivityType::Activity);
+        assert!(mixed.confidence < ActivityClassifier::MIN_CONFIDENCE);
+        assert!(mixed.confidence > 0.0);
+    }
+
+    #[test]
+    fn no_classification_without_imu() {
+        let mut readings = history(&[(600, 170.0, 0.8, 120.0)]);
+        readings.iter_mut().for_each(|r| r.imu_data = None);
+        assert!(ActivityClassifier::classify(&readings).is_none());
+    }
+}
@@ -0,0 +1,195 @@
+use chrono::{NaiveDate, TimeDelta};
+use openwhoop_codec::ParsedHistoryReading;
+
+use crate::{Sex, UserProfile};
+
+/// Energy spent over a period, in kcal. `active` is what was spent above
+/// the resting metabolic rate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Calories {
+    pub total: f64,
+    pub active: f64,
+}
+
+/// Estimates energy expenditure from heart rate: the resting metabolic rate
+/// (Mifflin-St Jeor) at rest and the heart rate equations of Keytel et al.
+/// (2005) once heart rate shows activity.
+pub struct CalorieCalculator {
+    pub sex: Sex,
+    pub age: u32,
+    pub weight_kg: f64,
+    pub height_cm: f64,
+    pub resting_hr: u8,
+    pub max_hr: u8,
+}
+
+impl CalorieCalculator {
+    /// Fraction of the heart rate reserve above which the activity
+    /// equations are used.
+    const ACTIVE_HRR: f64 = 0.3;
+    /// A reading stands for the time until the next one, up to this.
+    const MAX_READING_GAP: TimeDelta = TimeDelta::seconds(5);
+    const KCAL_PER_KJ: f64 = 1.0 / 4.184;
+    const MINUTES_PER_DAY: f64 = 1440.0;
+
+    /// `None` unless the profile has sex, birth date, weight and height.
+    pub fn new(profile: &UserProfile, date: NaiveDate, resting_hr: u8, max_hr: u8) -> Option<Self> {
+        Some(Self {
+            sex: profile.sex?,
+            age: profile.age_on(date)?,
+            weight_kg: profile.weight_kg?,
+            height_cm: profile.height_cm?,
+            resting_hr,
+            max_hr,
+        })
+    }
+
+    /// Resting metabolic rate, kcal/day.
+    pub fn bmr(&self) -> f64 {
+        let base = 10.0 * self.weight_kg + 6.25 * self.height_cm - 5.0 * f64::from(self.age);
+        match self.sex {
+            Sex::Male => base + 5.0,
+            Sex::Female => base - 161.0,
+        }
+    }
+
+    /// Heart rate above which energy is estimated from heart rate.
+    pub fn active_threshold(&self) -> f64 {
+        let reserve = f64::from(self.max_hr.saturating_sub(self.resting_hr));
+        f64::from(self.resting_hr) + reserve * Self::ACTIVE_HRR
+    }
+
+    /// Energy expenditure at `bpm`, kcal/min.
+    pub fn rate(&self, bpm: u8) -> f64 {
+        let resting = self.bmr() / Self::MINUTES_PER_DAY;
+        if f64::from(bpm) < self.active_threshold() {
+            return resting;
+        }
+
+        let (hr, weight, age) = (f64::from(bpm), self.weight_kg, f64::from(self.age));
+        let kj = match self.sex {
+            Sex::Male => -55.0969 + 0.6309 * hr + 0.1988 * weight + 0.2017 * age,
+            Sex::Female => -20.4022 + 0.4472 * hr - 0.1263 * weight + 0.074 * age,
+        };
+        (kj * Self::KCAL_PER_KJ).max(resting)
+    }
+
+    /// Energy spent over the readings.
+    pub fn calculate(&self, history: &[ParsedHistoryReading]) -> Calories {
+        let resting = self.bmr() / Self::MINUTES_PER_DAY;
+        let (total, minutes) = history
+            .iter()
+            .enumerate()
+            .map(|(i, reading)| {
+                let duration = history.get(i + 1).map_or(TimeDelta::seconds(1), |next| {
+                    (next.time - reading.time).min(Self::MAX_READING_GAP)
+                });
+                let minutes = duration.num_milliseconds() as f64 / 60_000.0;
+                (self.rate(reading.bpm) * minutes, minutes)
+            })
+            .fold((0.0, 0.0), |(total, all), (kcal, minutes)| {
+                (total + kcal, all + minutes)
+            });
+
+        Calories {
+            total,
+            active: total - resting * minutes,
+        }
+    }
+
+    /// Energy spent over a whole day; time without readings counts at the
+    /// resting metabolic rate.
+    pub fn calculate_day(&self, history: &[ParsedHistoryReading]) -> Calories {
+        let measured = self.calculate(history);
+        let resting = self.bmr() / Self::MINUTES_PER_DAY;
+        let measured_minutes = (measured.total - measured.active) / resting;
+        let unmeasured = (Self::MINUTES_PER_DAY - measured_minutes).max(0.0);
+
+        Calories {
+            total: measured.total + unmeasured * resting,
+            active: measured.active,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    fn base() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 6, 15)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    }
+
+    fn calculator(sex: Sex) -> CalorieCalculator {
+        let profile = UserProfile {
+            birth_date: NaiveDate::from_ymd_opt(1990, 6, 15),
+            sex: Some(sex),
+            weight_kg: Some(80.0),
+            height_cm: Some(180.0),
+            max_hr: None,
+            zone_basis: None,
+        };
+        CalorieCalculator::new(&profile, base().date(), 60, 190).unwrap()
+    }
+
+    fn history(minutes: i64, bpm: u8) -> Vec<ParsedHistoryReading> {
+        (0..minutes * 60)
+            .map(|second| ParsedHistoryReading {
+                time: base() + TimeDelta::hours(10) + TimeDelta::seconds(second),
+                bpm,
+                rr: Vec::new(),
+                imu_data: None,
+                gravity: None,
+                sensor_data: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn needs_complete_profile() {
+        let profile = UserProfile {
+            sex: Some(Sex::Male),
+            weight_kg: Some(80.0),
+            ..Default::default()
+        };
+        assert!(CalorieCalculator::new(&profile, base().date(), 60, 190).is_none());
+    }
+
+    #[test]
+    fn bmr_follows_mifflin_st_jeor() {
+        // 800 + 1125 - 175
+        assert_eq!(calculator(Sex::Male).bmr(), 1755.0);
+        assert_eq!(calculator(Sex::Female).bmr(), 1589.0);
+    }
+
+    #[test]
+    fn resting_heart_rate_burns_bmr() {
+        let calculator = calculator(Sex::Male);
+        let calories = calculator.calculate(&history(60, 65));
+        assert!((calories.total - 1755.0 / 24.0).abs() < 0.1);
+        assert!(calories.active.abs() < 1e-9);
+
+        let day = calculator.calculate_day(&[]);
+        assert_eq!(day.total, 1755.0);
+        assert_eq!(day.active, 0.0);
+    }
+
+    #[test]
+    fn exercise_uses_heart_rate_equations() {
+        let calculator = calculator(Sex::Male);
+        // (-55.0969 + 0.6309 * 150 + 0.1988 * 80 + 0.2017 * 35) / 4.184
+        assert!((calculator.rate(150) - 14.938).abs() < 0.001);
+
+        let workout = calculator.calculate(&history(30, 150));
+        assert!((workout.total - 448.1).abs() < 0.5, "{workout:?}");
+        assert!(workout.active > 400.0);
+
+        let day = calculator.calculate_day(&history(30, 150));
+        assert!((day.total - (workout.total + 1755.0 * 1410.0 / 1440.0)).abs() < 0.5);
+        assert_eq!(day.active, workout.active);
+    }
+}
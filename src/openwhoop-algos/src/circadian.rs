@@ -0,0 +1,533 @@
+use std::{f64::consts::TAU, fmt};
+
+use chrono::{Datelike, NaiveDateTime, TimeDelta, Timelike, Weekday};
+use openwhoop_codec::ParsedHistoryReading;
+
+use crate::{
+    SleepCycle, SleepEpoch, SleepStage, SleepStager,
+    helpers::{format_hm::FormatHM, time_math::mean},
+};
+
+/// Chronotype from the sleep-corrected mid-sleep on free days (MSFsc),
+/// following the Munich Chronotype Questionnaire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chronotype {
+    DefiniteMorning,
+    ModerateMorning,
+    Intermediate,
+    ModerateEvening,
+    DefiniteEvening,
+}
+
+impl Chronotype {
+    /// Upper MSFsc bound of each type, in hours after midnight.
+    const BOUNDS: [(f64, Self); 4] = [
+        (2.5, Self::DefiniteMorning),
+        (3.5, Self::ModerateMorning),
+        (5.0, Self::Intermediate),
+        (6.0, Self::ModerateEvening),
+    ];
+
+    pub fn from_mid_sleep(hours: f64) -> Self {
+        Self::BOUNDS
+            .iter()
+            .find(|(bound, _)| hours < *bound)
+            .map_or(Self::DefiniteEvening, |(_, chronotype)| *chronotype)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DefiniteMorning => "definite morning",
+            Self::ModerateMorning => "moderate morning",
+            Self::Intermediate => "intermediate",
+            Self::ModerateEvening => "moderate evening",
+            Self::DefiniteEvening => "definite evening",
+        }
+    }
+}
+
+impl fmt::Display for Chronotype {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Timing and regularity of sleep. Each value is `None` when there are not
+/// enough nights of the kind it needs.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CircadianMetrics {
+    /// Mid-sleep on free days corrected for catch-up sleep, hours after
+    /// midnight (negative before midnight).
+    pub mid_sleep_free: Option<f64>,
+    pub chronotype: Option<Chronotype>,
+    /// Difference between mid-sleep on free days and on workdays.
+    pub social_jetlag: Option<TimeDelta>,
+    /// Sleep regularity index: -100 (reversed every day) to 100 (same
+    /// sleep/wake state at the same time every day).
+    pub sleep_regularity: Option<f64>,
+}
+
+impl fmt::Display for CircadianMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.chronotype.zip(self.mid_sleep_free) {
+            Some((chronotype, hours)) => writeln!(
+                f,
+                "Chronotype: {} (free day mid-sleep {})",
+                chronotype,
+                (hours * 60.0).rem_euclid(1440.0).format_hm()
+            )?,
+            None => writeln!(f, "Chronotype: -")?,
+        }
+        match self.social_jetlag {
+            Some(jetlag) => writeln!(f, "Social jetlag: {}", jetlag.format_hm())?,
+            None => writeln!(f, "Social jetlag: -")?,
+        }
+        match self.sleep_regularity {
+            Some(sri) => write!(f, "Sleep regularity index: {:.0}", sri),
+            None => write!(f, "Sleep regularity index: -"),
+        }
+    }
+}
+
+pub struct CircadianAnalyzer;
+
+impl CircadianAnalyzer {
+    /// Nights of each kind needed for chronotype and social jetlag.
+    pub const MIN_NIGHTS: usize = 2;
+    /// Days of consecutive sleep/wake state needed for the regularity index.
+    pub const MIN_REGULARITY_DAYS: i64 = 2;
+
+    /// Metrics of `sleeps` with the stored hypnogram `epochs` of any of
+    /// them, which let in-bed wake count as awake for the regularity index,
+    /// and the `recorded` minutes the strap has readings for.
+    pub fn analyze(
+        sleeps: &[SleepCycle],
+        epochs: &[SleepEpoch],
+        recorded: &[NaiveDateTime],
+    ) -> CircadianMetrics {
+        let (free, work): (Vec<_>, Vec<_>) = sleeps.iter().partition(|sleep| is_free_day(sleep));
+
+        let mid_sleep_free = Self::mid_sleep_free_corrected(&free, &work);
+        let social_jetlag = (free.len() >= Self::MIN_NIGHTS && work.len() >= Self::MIN_NIGHTS)
+            .then(|| {
+                let jetlag = mean_mid_sleep(&free) - mean_mid_sleep(&work);
+                TimeDelta::seconds((jetlag.abs() * 3600.0).round() as i64)
+            });
+
+        CircadianMetrics {
+            mid_sleep_free,
+            chronotype: mid_sleep_free.map(Chronotype::from_mid_sleep),
+            social_jetlag,
+            sleep_regularity: Self::sleep_regularity_index(sleeps, epochs, recorded),
+        }
+    }
+
+    /// MSFsc: mid-sleep on free days, moved earlier by half the sleep
+    /// caught up on them when free day sleep is longer than the weekly
+    /// average.
+    fn mid_sleep_free_corrected(free: &[&SleepCycle], work: &[&SleepCycle]) -> Option<f64> {
+        if free.len() < Self::MIN_NIGHTS {
+            return None;
+        }
+
+        let mid_sleep = mean_mid_sleep(free);
+        let free_duration = mean_duration_hours(free);
+        if work.is_empty() {
+            return Some(mid_sleep);
+        }
+
+        let week_duration = (5.0 * mean_duration_hours(work) + 2.0 * free_duration) / 7.0;
+        Some(mid_sleep - (free_duration - week_duration).max(0.0) / 2.0)
+    }
+
+    /// Probability of being in the same sleep/wake state at any two minutes
+    /// 24 hours apart, scaled to -100..100 (Phillips et al., 2017). Minutes
+    /// inside a sleep count as asleep, except those of wake `epochs`; sleeps
+    /// without staged epochs count as asleep throughout. Pairs where either
+    /// minute is missing from `recorded` are left out, as the strap was not
+    /// worn to tell.
+    pub fn sleep_regularity_index(
+        sleeps: &[SleepCycle],
+        epochs: &[SleepEpoch],
+        recorded: &[NaiveDateTime],
+    ) -> Option<f64> {
+        let first = sleeps.iter().map(|sleep| sleep.start).min()?;
+        let last = sleeps.iter().map(|sleep| sleep.end).max()?;
+        let origin = first.date().and_hms_opt(0, 0, 0)?;
+        let minutes = usize::try_from((last - origin).num_minutes()).ok()?;
+        if ((last - first).num_days()) < Self::MIN_REGULARITY_DAYS {
+            return None;
+        }
+
+        let mut asleep = vec![false; minutes];
+        let mut worn = vec![false; minutes];
+        let minute_of = |time: NaiveDateTime| {
+            usize::try_from((time - origin).num_minutes())
+                .unwrap_or(0)
+                .min(minutes)
+        };
+        for &time in recorded.iter().filter(|&&time| time >= origin) {
+            if let Some(worn) = worn.get_mut(minute_of(time)) {
+                *worn = true;
+            }
+        }
+        for sleep in sleeps {
+            asleep[minute_of(sleep.start)..minute_of(sleep.end)].fill(true);
+        }
+        for epoch in epochs.iter().filter(|e| e.stage == SleepStage::Wake) {
+            let minute = minute_of(epoch.start);
+            let end = minute_of(epoch.start + SleepStager::EPOCH).max(minute + 1);
+            asleep[minute..end.min(minutes)].fill(false);
+        }
+
+        // Only compare minutes from the first sleep on
+        let start = minute_of(first);
+        let day = 24 * 60;
+        let pairs = (start..minutes.saturating_sub(day))
+            .filter(|&minute| worn[minute] && worn[minute + day])
+            .map(|minute| asleep[minute] == asleep[minute + day])
+            .collect::<Vec<_>>();
+        if pairs.is_empty() {
+            return None;
+        }
+
+        let same = pairs.iter().filter(|same| **same).count() as f64 / pairs.len() as f64;
+        Some(-100.0 + 200.0 * same)
+    }
+}
+
+/// Free day nights are those before a Saturday or Sunday.
+fn is_free_day(sleep: &SleepCycle) -> bool {
+    matches!(sleep.end.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+/// Mid-sleep in hours after midnight of the wake-up day, so nights that
+/// straddle midnight average without wrapping.
+fn mid_sleep_hours(sleep: &SleepCycle) -> f64 {
+    let midpoint = sleep.start + (sleep.end - sleep.start) / 2;
+    let midnight = sleep.end.date().and_hms_opt(0, 0, 0).unwrap_or(sleep.end);
+    (midpoint - midnight).num_seconds() as f64 / 3600.0
+}
+
+fn mean_mid_sleep(sleeps: &[&SleepCycle]) -> f64 {
+    mean(
+        &sleeps
+            .iter()
+            .map(|s| mid_sleep_hours(s))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn mean_duration_hours(sleeps: &[&SleepCycle]) -> f64 {
+    mean(
+        &sleeps
+            .iter()
+            .map(|s| (s.end - s.start).num_seconds() as f64 / 3600.0)
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// 24-hour cosinor fit of the heart rate:
+/// `bpm = mesor + amplitude * cos(2π (t - acrophase) / 24h)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeartRateRhythm {
+    /// Rhythm-adjusted mean, bpm.
+    pub mesor: f64,
+    /// Half the peak to trough difference, bpm.
+    pub amplitude: f64,
+    /// Time of day of the peak, hours after midnight.
+    pub acrophase: f64,
+    /// Share of the heart rate variance the rhythm explains.
+    pub r_squared: f64,
+}
+
+impl HeartRateRhythm {
+    /// Hours of readings needed to fit a daily rhythm.
+    pub const MIN_HOURS: i64 = 24;
+
+    /// Least squares fit over the readings, `None` when they span less than
+    /// [`Self::MIN_HOURS`].
+    pub fn fit(hr: &[ParsedHistoryReading]) -> Option<Self> {
+        let (first, last) = (hr.first()?, hr.last()?);
+        if (last.time - first.time).num_hours() < Self::MIN_HOURS {
+            return None;
+        }
+
+        // Normal equations of bpm = m + b cos(ωt) + g sin(ωt)
+        let mut ata = [[0.0; 3]; 3];
+        let mut aty = [0.0; 3];
+        for reading in hr {
+            let time = reading.time.time();
+            let hours = f64::from(time.num_seconds_from_midnight()) / 3600.0;
+            let (sin, cos) = (TAU * hours / 24.0).sin_cos();
+            let row = [1.0, cos, sin];
+            let bpm = f64::from(reading.bpm);
+            for ((ata, aty), x) in ata.iter_mut().zip(&mut aty).zip(row) {
+                for (ata, y) in ata.iter_mut().zip(row) {
+                    *ata += x * y;
+                }
+                *aty += x * bpm;
+            }
+        }
+        let [mesor, beta, gamma] = solve_3x3(ata, aty)?;
+
+        let mean_bpm = aty[0] / ata[0][0];
+        let (residual, total) = hr.iter().fold((0.0, 0.0), |(residual, total), reading| {
+            let hours = f64::from(reading.time.time().num_seconds_from_midnight()) / 3600.0;
+            let (sin, cos) = (TAU * hours / 24.0).sin_cos();
+            let bpm = f64::from(reading.bpm);
+            let fitted = mesor + beta * cos + gamma * sin;
+            (
+                residual + (bpm - fitted).powi(2),
+                total + (bpm - mean_bpm).powi(2),
+            )
+        });
+
+        Some(Self {
+            mesor,
+            amplitude: beta.hypot(gamma),
+            acrophase: (gamma.atan2(beta) / TAU * 24.0).rem_euclid(24.0),
+            r_squared: if total > 0.0 {
+                1.0 - residual / total
+            } else {
+                0.0
+            },
+        })
+    }
+}
+
+impl fmt::Display for HeartRateRhythm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "HR rhythm: mesor {:.0} bpm, amplitude {:.1} bpm, peak at {} (R² {:.2})",
+            self.mesor,
+            self.amplitude,
+            (self.acrophase * 60.0).format_hm(),
+            self.r_squared
+        )
+    }
+}
+
+/// Cramer's rule, `None` for a singular system.
+fn solve_3x3(a: [[f64; 3]; 3], b: [f64; 3]) -> Option<[f64; 3]> {
+    let det = |m: [[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let d = det(a);
+    if d.abs() < 1e-9 {
+        return None;
+    }
+
+    let mut solution = [0.0; 3];
+    for (column, value) in solution.iter_mut().enumerate() {
+        let mut m = a;
+        for (row, b) in m.iter_mut().zip(b) {
+            row[column] = b;
+        }
+        *value = det(m) / d;
+    }
+    Some(solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    /// Night ending on `end_day` of March 2025 (the 1st is a Saturday).
+    fn night(end_day: u32, start: (u32, u32), end: (u32, u32)) -> SleepCycle {
+        let end_date = NaiveDate::from_ymd_opt(2025, 3, end_day).unwrap();
+        let start_date = if start.0 >= 12 {
+            end_date.pred_opt().unwrap()
+        } else {
+            end_date
+        };
+        SleepCycle {
+            id: end_date,
+            start: start_date.and_hms_opt(start.0, start.1, 0).unwrap(),
+            end: end_date.and_hms_opt(end.0, end.1, 0).unwrap(),
+            min_bpm: 50,
+            max_bpm: 70,
+            avg_bpm: 58,
+            min_hrv: 30,
+            max_hrv: 80,
+            avg_hrv: 55,
+            score: 100.0,
+            ..Default::default()
+        }
+    }
+
+    /// Every minute from midnight before the first sleep to the end of the
+    /// last, as if the strap was never taken off.
+    fn every_minute(sleeps: &[SleepCycle]) -> Vec<NaiveDateTime> {
+        let (Some(first), Some(last)) = (sleeps.first(), sleeps.last()) else {
+            return Vec::new();
+        };
+        let origin = first.start.date().and_hms_opt(0, 0, 0).unwrap();
+        (0..(last.end - origin).num_minutes())
+            .map(|minute| origin + TimeDelta::minutes(minute))
+            .collect()
+    }
+
+    #[test]
+    fn weekend_shift_gives_social_jetlag_and_chronotype() {
+        // Workdays 23:00-07:00 (mid-sleep 03:00), weekends 01:00-10:00
+        // (mid-sleep 05:30, one hour longer than workdays)
+        let sleeps = (3..=16)
+            .map(|day| {
+                let weekday = NaiveDate::from_ymd_opt(2025, 3, day).unwrap().weekday();
+                if matches!(weekday, Weekday::Sat | Weekday::Sun) {
+                    night(day, (1, 0), (10, 0))
+                } else {
+                    night(day, (23, 0), (7, 0))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let metrics = CircadianAnalyzer::analyze(&sleeps, &[], &every_minute(&sleeps));
+        assert_eq!(metrics.social_jetlag, Some(TimeDelta::minutes(150)));
+        // Week average 8h 17m: 5.5h - (9h - 8h 17m) / 2 = 5h 09m
+        let msf = metrics.mid_sleep_free.unwrap();
+        assert!(
+            (msf - (5.5 - (9.0 - 58.0 / 7.0) / 2.0)).abs() < 1e-9,
+            "{msf}"
+        );
+        assert_eq!(metrics.chronotype, Some(Chronotype::ModerateEvening));
+        // Weekend nights shift the state at 23:00-01:00 and 07:00-10:00
+        let sri = metrics.sleep_regularity.unwrap();
+        assert!(sri > 50.0 && sri < 90.0, "{sri}");
+    }
+
+    #[test]
+    fn identical_nights_are_fully_regular() {
+        // Monday to Friday: no free days
+        let sleeps = (3..=7)
+            .map(|day| night(day, (22, 30), (6, 30)))
+            .collect::<Vec<_>>();
+
+        let metrics = CircadianAnalyzer::analyze(&sleeps, &[], &every_minute(&sleeps));
+        assert_eq!(metrics.sleep_regularity, Some(100.0));
+        assert_eq!(metrics.social_jetlag, None);
+        assert_eq!(metrics.chronotype, None);
+
+        assert_eq!(
+            CircadianAnalyzer::sleep_regularity_index(&sleeps[..1], &[], &every_minute(&sleeps)),
+            None
+        );
+        assert_eq!(
+            CircadianAnalyzer::analyze(&[], &[], &[]),
+            CircadianMetrics::default()
+        );
+    }
+
+    #[test]
+    fn wake_epochs_count_as_awake() {
+        let sleeps = (3..=7)
+            .map(|day| night(day, (22, 30), (6, 30)))
+            .collect::<Vec<_>>();
+        // An hour awake in bed at 02:00 on one night only
+        let wake_start = NaiveDate::from_ymd_opt(2025, 3, 5)
+            .unwrap()
+            .and_hms_opt(2, 0, 0)
+            .unwrap();
+        let epochs = (0..120)
+            .map(|i| SleepEpoch {
+                start: wake_start + SleepStager::EPOCH * i,
+                stage: SleepStage::Wake,
+            })
+            .collect::<Vec<_>>();
+
+        let recorded = every_minute(&sleeps);
+        let regular = CircadianAnalyzer::sleep_regularity_index(&sleeps, &[], &recorded).unwrap();
+        let wake = CircadianAnalyzer::sleep_regularity_index(&sleeps, &epochs, &recorded).unwrap();
+        assert_eq!(regular, 100.0);
+        assert!(wake < regular, "{wake}");
+
+        // Non-wake epochs change nothing
+        let light = epochs
+            .iter()
+            .map(|epoch| SleepEpoch {
+                stage: SleepStage::Light,
+                ..*epoch
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            CircadianAnalyzer::sleep_regularity_index(&sleeps, &light, &recorded),
+            Some(100.0)
+        );
+    }
+
+    #[test]
+    fn minutes_without_data_are_left_out() {
+        let mut sleeps = (3..=7)
+            .map(|day| night(day, (22, 30), (6, 30)))
+            .collect::<Vec<_>>();
+        // No sleep detected on the night to the 5th
+        let missing = sleeps.remove(2);
+        let recorded = every_minute(&sleeps);
+        let unworn = recorded
+            .iter()
+            .copied()
+            .filter(|&minute| minute < missing.start || minute >= missing.end)
+            .collect::<Vec<_>>();
+
+        // Worn all night, the strap saw the user awake
+        let awake = CircadianAnalyzer::sleep_regularity_index(&sleeps, &[], &recorded).unwrap();
+        assert!(awake < 100.0, "{awake}");
+        // Off the wrist, the night can't count either way
+        assert_eq!(
+            CircadianAnalyzer::sleep_regularity_index(&sleeps, &[], &unworn),
+            Some(100.0)
+        );
+        assert_eq!(
+            CircadianAnalyzer::sleep_regularity_index(&sleeps, &[], &[]),
+            None
+        );
+    }
+
+    #[test]
+    fn chronotype_bounds() {
+        assert_eq!(
+            Chronotype::from_mid_sleep(-0.5),
+            Chronotype::DefiniteMorning
+        );
+        assert_eq!(Chronotype::from_mid_sleep(3.0), Chronotype::ModerateMorning);
+        assert_eq!(Chronotype::from_mid_sleep(4.0), Chronotype::Intermediate);
+        assert_eq!(Chronotype::from_mid_sleep(7.0), Chronotype::DefiniteEvening);
+    }
+
+    #[test]
+    fn cosinor_recovers_the_rhythm() {
+        let start = NaiveDate::from_ymd_opt(2025, 3, 3)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        // Peak at 16:00, mesor 70, amplitude 12, one reading a minute
+        let hr = (0..3 * 24 * 60)
+            .map(|minute| {
+                let time = start + TimeDelta::minutes(minute);
+                let hours = f64::from(time.time().num_seconds_from_midnight()) / 3600.0;
+                let bpm = 70.0 + 12.0 * (TAU * (hours - 16.0) / 24.0).cos();
+                ParsedHistoryReading {
+                    time,
+                    bpm: bpm.round() as u8,
+                    rr: vec![],
+                    imu_data: None,
+                    gravity: None,
+                    sensor_data: None,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let rhythm = HeartRateRhythm::fit(&hr).unwrap();
+        assert!((rhythm.mesor - 70.0).abs() < 0.1, "{rhythm:?}");
+        assert!((rhythm.amplitude - 12.0).abs() < 0.1, "{rhythm:?}");
+        assert!((rhythm.acrophase - 16.0).abs() < 0.05, "{rhythm:?}");
+        assert!(rhythm.r_squared > 0.99, "{rhythm:?}");
+
+        assert!(HeartRateRhythm::fit(&hr[..600]).is_none());
+    }
+}
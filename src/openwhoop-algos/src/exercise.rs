@@ -15,6 +15,8 @@ pub struct ExerciseMetrics {
     pub count: u64,
     pub mean_duration: TimeDelta,
     pub duration_std: TimeDelta,
+    /// Sum over the activities with a calorie estimate, in kcal
+    pub calories: Option<f64>,
 }
 
 impl ExerciseMetrics {
@@ -23,6 +25,11 @@ impl ExerciseMetrics {
             return Ok(Self::default());
         }
 
+        let calories = exercises
+            .iter()
+            .filter_map(|e| e.calories)
+            .reduce(|sum, kcal| sum + kcal);
+
         let durations = exercises
             .into_iter()
             .filter_map(|e| e.to.map(|to| to - e.from))
@@ -41,15 +48,20 @@ impl ExerciseMetrics {
             mean_duration,
             duration_std: std_dev_delta(durations.as_slice(), mean_duration)?,
             total_duration: durations.into_iter().sum(),
+            calories,
         })
     }
 }
 
 impl Display for ExerciseMetrics {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let calories = self
+            .calories
+            .map_or_else(|| "-".to_owned(), |kcal| format!("{:.0} kcal", kcal));
         f.write_fmt(format_args!(
-            "Duration: {:?}h\nCount: {}\nMean duration: {}\nDuration std: {}",
+            "Duration: {:?}h\nCalories: {}\nCount: {}\nMean duration: {}\nDuration std: {}",
             self.total_duration.num_hours(),
+            calories,
             self.count,
             self.mean_duration.format_hm(),
             self.duration_std.format_hm()
@@ -70,6 +82,7 @@ mod tests {
         assert_eq!(metrics.duration_std, TimeDelta::default());
         assert_eq!(metrics.mean_duration, TimeDelta::default());
         assert_eq!(metrics.total_duration, TimeDelta::default());
+        assert_eq!(metrics.calories, None);
     }
 
     #[test]
@@ -89,6 +102,7 @@ mod tests {
                 to: Some(base + TimeDelta::hours(1)),
                 activity: ActivityType::Running,
                 strain: None,
+                calories: Some(600.0),
             },
             ActivityPeriod {
                 period_id: base.date(),
@@ -96,6 +110,7 @@ mod tests {
                 to: Some(base + TimeDelta::hours(5)),
                 activity: ActivityType::Cycling,
                 strain: None,
+                calories: Some(450.5),
             },
         ];
 
@@ -104,5 +119,7 @@ mod tests {
         assert_eq!(metrics.total_duration, TimeDelta::hours(2));
         assert_eq!(metrics.mean_duration, TimeDelta::hours(1));
         assert_eq!(metrics.duration_std, TimeDelta::seconds(0)); // identical durations
+        assert_eq!(metrics.calories, Some(1050.5));
+        assert!(metrics.to_string().contains("Calories: 1050 kcal"));
     }
 }
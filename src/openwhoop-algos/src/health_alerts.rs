@@ -0,0 +1,345 @@
+use std::{fmt, str::FromStr};
+
+use chrono::NaiveDate;
+use openwhoop_codec::WhoopError;
+use openwhoop_entities::health_alerts;
+
+use crate::helpers::time_math::{mean, sample_std_dev};
+
+/// Nightly metric watched for deviations from the personal baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthMetric {
+    RestingHr,
+    Hrv,
+    SkinTemp,
+    RespRate,
+    Spo2,
+}
+
+impl HealthMetric {
+    pub const ALL: [Self; 5] = [
+        Self::RestingHr,
+        Self::Hrv,
+        Self::SkinTemp,
+        Self::RespRate,
+        Self::Spo2,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::RestingHr => "rhr",
+            Self::Hrv => "hrv",
+            Self::SkinTemp => "skin_temp",
+            Self::RespRate => "resp_rate",
+            Self::Spo2 => "spo2",
+        }
+    }
+
+    /// +1 when a rise is the warning sign, -1 when a drop is.
+    fn direction(&self) -> f64 {
+        match self {
+            Self::RestingHr | Self::SkinTemp | Self::RespRate => 1.0,
+            Self::Hrv | Self::Spo2 => -1.0,
+        }
+    }
+
+    /// Smallest standard deviation a baseline is given, so a very steady
+    /// baseline doesn't turn measurement noise into large z-scores.
+    fn min_std_dev(&self) -> f64 {
+        match self {
+            Self::RestingHr => 1.0,
+            Self::Hrv => 3.0,
+            Self::SkinTemp => 0.1,
+            Self::RespRate => 0.3,
+            Self::Spo2 => 0.5,
+        }
+    }
+}
+
+impl fmt::Display for HealthMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for HealthMetric {
+    type Err = WhoopError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|metric| metric.as_str().eq_ignore_ascii_case(s))
+            .ok_or(WhoopError::InvalidData)
+    }
+}
+
+/// One night's values of the watched metrics, `None` where the night has
+/// no value.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct NightlyMetrics {
+    pub date: NaiveDate,
+    /// Resting heart rate: the night's minimum, bpm.
+    pub rhr: Option<f64>,
+    /// Average sleeping RMSSD, ms.
+    pub hrv: Option<f64>,
+    /// Average skin temperature, °C.
+    pub skin_temp: Option<f64>,
+    /// Average respiratory rate, breaths/min.
+    pub resp_rate: Option<f64>,
+    /// Average SpO2, %.
+    pub spo2: Option<f64>,
+}
+
+impl NightlyMetrics {
+    pub fn value(&self, metric: HealthMetric) -> Option<f64> {
+        match metric {
+            HealthMetric::RestingHr => self.rhr,
+            HealthMetric::Hrv => self.hrv,
+            HealthMetric::SkinTemp => self.skin_temp,
+            HealthMetric::RespRate => self.resp_rate,
+            HealthMetric::Spo2 => self.spo2,
+        }
+    }
+}
+
+/// When nights count as deviating and when that raises an alert.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlertThresholds {
+    /// Smallest z-score, in the warning direction, for a metric to count.
+    pub z_score: f64,
+    /// Metrics that have to deviate on the same night.
+    pub min_metrics: usize,
+    /// Consecutive deviating nights before an alert is raised.
+    pub min_nights: usize,
+}
+
+impl Default for AlertThresholds {
+    fn default() -> Self {
+        Self {
+            z_score: 2.0,
+            min_metrics: 2,
+            min_nights: 2,
+        }
+    }
+}
+
+/// Alert raised for the night `date`, the `nights`-th deviating night in a
+/// row, with the z-scores of the metrics that deviated on it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthAlert {
+    pub date: NaiveDate,
+    pub nights: usize,
+    pub deviations: Vec<(HealthMetric, f64)>,
+}
+
+impl HealthAlert {
+    pub fn z_score(&self, metric: HealthMetric) -> Option<f64> {
+        self.deviations
+            .iter()
+            .find(|(m, _)| *m == metric)
+            .map(|(_, z)| *z)
+    }
+}
+
+impl From<health_alerts::Model> for HealthAlert {
+    fn from(value: health_alerts::Model) -> Self {
+        let deviations = HealthMetric::ALL
+            .into_iter()
+            .zip([
+                value.rhr_z,
+                value.hrv_z,
+                value.skin_temp_z,
+                value.resp_rate_z,
+                value.spo2_z,
+            ])
+            .filter_map(|(metric, z)| z.map(|z| (metric, z)))
+            .collect();
+
+        Self {
+            date: value.date,
+            nights: usize::try_from(value.nights).unwrap_or(0),
+            deviations,
+        }
+    }
+}
+
+impl fmt::Display for HealthAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: night {} off baseline (", self.date, self.nights)?;
+        for (i, (metric, z)) in self.deviations.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{} z {:+.1}", metric, z)?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// Flags runs of nights where several metrics move away from the rolling
+/// personal baseline in the direction seen with illness or overreaching:
+/// resting HR, skin temperature and respiratory rate up, HRV and SpO2 down.
+/// The baseline is taken from the nights before a run starts, so a long
+/// run doesn't become its own baseline.
+pub struct HealthAlertDetector {
+    pub thresholds: AlertThresholds,
+}
+
+impl HealthAlertDetector {
+    /// Nights averaged into the baseline.
+    pub const BASELINE_NIGHTS: usize = 28;
+    /// Nights with a value a metric needs before it is compared.
+    pub const MIN_BASELINE_NIGHTS: usize = 7;
+
+    pub fn new(thresholds: AlertThresholds) -> Self {
+        Self { thresholds }
+    }
+
+    /// Alerts for `nights`, oldest first.
+    pub fn detect(&self, nights: &[NightlyMetrics]) -> Vec<HealthAlert> {
+        let mut alerts = Vec::new();
+        let mut run = 0;
+
+        for (i, night) in nights.iter().enumerate() {
+            let run_start = i - run;
+            let baseline = &nights[run_start.saturating_sub(Self::BASELINE_NIGHTS)..run_start];
+            let deviations = self.deviations(night, baseline);
+
+            if deviations.len() < self.thresholds.min_metrics.max(1) {
+                run = 0;
+                continue;
+            }
+
+            run += 1;
+            if run >= self.thresholds.min_nights {
+                alerts.push(HealthAlert {
+                    date: night.date,
+                    nights: run,
+                    deviations,
+                });
+            }
+        }
+
+        alerts
+    }
+
+    /// Metrics of `night` past the z-score threshold in their warning
+    /// direction, with their z-scores.
+    pub fn deviations(
+        &self,
+        night: &NightlyMetrics,
+        baseline: &[NightlyMetrics],
+    ) -> Vec<(HealthMetric, f64)> {
+        HealthMetric::ALL
+            .into_iter()
+            .filter_map(|metric| {
+                let value = night.value(metric)?;
+                let z = z_score(metric, value, baseline)?;
+                (z * metric.direction() >= self.thresholds.z_score).then_some((metric, z))
+            })
+            .collect()
+    }
+}
+
+fn z_score(metric: HealthMetric, value: f64, baseline: &[NightlyMetrics]) -> Option<f64> {
+    let values = baseline
+        .iter()
+        .filter_map(|night| night.value(metric))
+        .collect::<Vec<_>>();
+    if values.len() < HealthAlertDetector::MIN_BASELINE_NIGHTS {
+        return None;
+    }
+
+    let mean = mean(&values);
+    let std_dev = sample_std_dev(&values).max(metric.min_std_dev());
+    Some((value - mean) / std_dev)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SyntheticScenario;
+
+    fn nights(scenario: SyntheticScenario, count: usize) -> Vec<NightlyMetrics> {
+        let first = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        (0..count)
+            .map(|night| {
+                let date = first + chrono::Days::new(night as u64);
+                scenario.vitals(night, count, 55).metrics(date)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn healthy_nights_raise_no_alerts() {
+        let detector = HealthAlertDetector::new(AlertThresholds::default());
+        assert!(
+            detector
+                .detect(&nights(SyntheticScenario::Healthy, 40))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn illness_is_flagged_with_its_metrics() {
+        let nights = nights(SyntheticScenario::Illness, 30);
+        let alerts = HealthAlertDetector::new(AlertThresholds::default()).detect(&nights);
+
+        // Last three nights are ill; the alert starts on the second one
+        let dates = alerts.iter().map(|alert| alert.date).collect::<Vec<_>>();
+        assert_eq!(dates, [nights[28].date, nights[29].date]);
+        assert_eq!(alerts[1].nights, 3);
+
+        let last = &alerts[1];
+        assert!(last.z_score(HealthMetric::RestingHr).unwrap() >= 2.0);
+        assert!(last.z_score(HealthMetric::Hrv).unwrap() <= -2.0);
+        assert!(last.z_score(HealthMetric::SkinTemp).unwrap() >= 2.0);
+        assert!(last.z_score(HealthMetric::RespRate).unwrap() >= 2.0);
+        assert!(last.z_score(HealthMetric::Spo2).unwrap() <= -2.0);
+    }
+
+    #[test]
+    fn overreaching_moves_heart_metrics_only() {
+        let nights = nights(SyntheticScenario::Overreaching, 30);
+        let alerts = HealthAlertDetector::new(AlertThresholds::default()).detect(&nights);
+
+        assert_eq!(alerts.len(), 2);
+        for alert in &alerts {
+            let metrics = alert
+                .deviations
+                .iter()
+                .map(|(metric, _)| *metric)
+                .collect::<Vec<_>>();
+            assert_eq!(metrics, [HealthMetric::RestingHr, HealthMetric::Hrv]);
+        }
+    }
+
+    #[test]
+    fn thresholds_are_configurable() {
+        let nights = nights(SyntheticScenario::Overreaching, 30);
+
+        let strict = HealthAlertDetector::new(AlertThresholds {
+            min_metrics: 3,
+            ..Default::default()
+        });
+        assert!(strict.detect(&nights).is_empty());
+
+        let eager = HealthAlertDetector::new(AlertThresholds {
+            min_nights: 1,
+            ..Default::default()
+        });
+        assert_eq!(eager.detect(&nights).len(), 3);
+
+        // Too few nights for a baseline
+        let detector = HealthAlertDetector::new(AlertThresholds::default());
+        assert!(detector.detect(&nights[22..]).is_empty());
+    }
+
+    #[test]
+    fn metric_names_round_trip() {
+        for metric in HealthMetric::ALL {
+            assert_eq!(metric.to_string().parse::<HealthMetric>().unwrap(), metric);
+        }
+        assert!("vo2max".parse::<HealthMetric>().is_err());
+    }
+}
@@ -0,0 +1,15 @@
+/// Distance between two gravity vectors, in g.
+pub fn gravity_delta(a: [f32; 3], b: [f32; 3]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gravity_delta_is_euclidean_distance() {
+        assert_eq!(gravity_delta([0.0, 0.0, 1.0], [0.0, 0.0, 1.0]), 0.0);
+        assert!((gravity_delta([0.0, 0.0, 1.0], [0.0, 0.3, 0.6]) - 0.5).abs() < 1e-6);
+    }
+}
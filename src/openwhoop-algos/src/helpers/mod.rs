@@ -1,2 +1,3 @@
 pub mod format_hm;
+pub mod gravity;
 pub mod time_math;
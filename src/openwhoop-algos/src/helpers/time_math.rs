@@ -1,4 +1,4 @@
-use chrono::{NaiveTime, TimeDelta, Timelike as _};
+use chrono::{NaiveDateTime, NaiveTime, TimeDelta, Timelike as _};
 
 use openwhoop_codec::WhoopError;
 
@@ -74,6 +74,71 @@ pub fn mean(values: &[f64]) -> f64 {
     }
 }
 
+/// Population variance, 0 for an empty slice.
+pub fn variance(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mean = mean(values);
+    values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64
+}
+
+/// Sample variance, 0 with fewer than two values.
+pub fn sample_variance(values: &[f64]) -> f64 {
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean = mean(values);
+    values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (values.len() - 1) as f64
+}
+
+pub fn std_dev(values: &[f64]) -> f64 {
+    variance(values).sqrt()
+}
+
+pub fn sample_std_dev(values: &[f64]) -> f64 {
+    sample_variance(values).sqrt()
+}
+
+/// Median, 0 for an empty slice.
+pub fn median(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+/// Least squares fit `y = slope * x + intercept`. `None` with fewer than
+/// two points or when all `x` are equal.
+pub fn linear_fit(points: &[(f64, f64)]) -> Option<(f64, f64)> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let sxx = points
+        .iter()
+        .map(|(x, _)| (x - mean_x).powi(2))
+        .sum::<f64>();
+    if sxx == 0.0 {
+        return None;
+    }
+    let sxy = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum::<f64>();
+    let slope = sxy / sxx;
+    Some((slope, mean_y - slope * mean_x))
+}
+
 pub fn std_dev_delta(durations: &[TimeDelta], mean: TimeDelta) -> Result<TimeDelta, WhoopError> {
     if durations.is_empty() {
         return Ok(TimeDelta::default());
@@ -92,6 +157,13 @@ pub fn round_float(v: f64) -> f64 {
     (v * 100.0).round() / 100.0
 }
 
+/// Start of the minute `time` falls in.
+pub fn truncate_to_minute(time: NaiveDateTime) -> NaiveDateTime {
+    time.with_second(0)
+        .and_then(|t| t.with_nanosecond(0))
+        .unwrap_or(time)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -188,6 +260,31 @@ mod tests {
         assert_eq!(mean(&[2.0, 4.0, 6.0]), 4.0);
     }
 
+    #[test]
+    fn variance_population_and_sample() {
+        let values = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        assert_eq!(variance(&values), 4.0);
+        assert_eq!(std_dev(&values), 2.0);
+        assert!((sample_variance(&values) - 32.0 / 7.0).abs() < 1e-12);
+        assert_eq!(sample_variance(&[1.0]), 0.0);
+        assert_eq!(variance(&[]), 0.0);
+    }
+
+    #[test]
+    fn median_odd_even_and_empty() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), 2.5);
+        assert_eq!(median(&[]), 0.0);
+    }
+
+    #[test]
+    fn linear_fit_recovers_line() {
+        let points = [(0.0, 1.0), (1.0, 3.0), (2.0, 5.0)];
+        assert_eq!(linear_fit(&points), Some((2.0, 1.0)));
+        assert_eq!(linear_fit(&[(1.0, 1.0), (1.0, 2.0)]), None);
+        assert_eq!(linear_fit(&[(1.0, 1.0)]), None);
+    }
+
     #[test]
     fn std_dev_delta_empty() -> Result<(), WhoopError> {
         assert_eq!(
@@ -205,6 +302,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn round_float_basic() {
         assert_eq!(round_float(3.14159), 3.14);
         assert_eq!(round_float(1.999), 2.0);
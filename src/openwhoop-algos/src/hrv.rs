@@ -0,0 +1,476 @@
+use std::fmt;
+
+use chrono::{NaiveDateTime, Timelike};
+use openwhoop_codec::ParsedHistoryReading;
+use openwhoop_entities::hrv_samples;
+
+use crate::{
+    CorrectedRr, RrArtifactCorrector,
+    helpers::time_math::{linear_fit, mean, sample_std_dev, sample_variance},
+};
+
+/// Heart rate variability of a series of RR intervals. Durations are in ms,
+/// band powers in ms².
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HrvMetrics {
+    /// Number of RR intervals used.
+    pub count: usize,
+    /// Share of intervals that were corrected as artifacts, in percent.
+    pub artifact_rate: f64,
+    pub mean_nn: f64,
+    pub sdnn: f64,
+    pub rmssd: f64,
+    /// Share of successive differences above 50 ms, in percent.
+    pub pnn50: f64,
+    /// Low frequency power (0.04-0.15 Hz), `None` for series under two
+    /// minutes.
+    pub lf: Option<f64>,
+    /// High frequency power (0.15-0.4 Hz), `None` for series under two
+    /// minutes.
+    pub hf: Option<f64>,
+    pub lf_hf: Option<f64>,
+    /// Poincaré plot width: short-term variability.
+    pub sd1: f64,
+    /// Poincaré plot length: long-term variability.
+    pub sd2: f64,
+    /// Short-term (4-16 beats) detrended fluctuation analysis exponent,
+    /// `None` under [`HrvCalculator::MIN_DFA_INTERVALS`].
+    pub dfa_alpha1: Option<f64>,
+}
+
+impl fmt::Display for HrvMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = |value: Option<f64>, unit: &str| {
+            value.map_or_else(|| "-".to_owned(), |value| format!("{:.0}{}", value, unit))
+        };
+        writeln!(
+            f,
+            "Beats: {} ({:.1}% artifacts)",
+            self.count, self.artifact_rate
+        )?;
+        writeln!(f, "Mean NN: {:.0} ms", self.mean_nn)?;
+        writeln!(f, "SDNN: {:.1} ms", self.sdnn)?;
+        writeln!(f, "RMSSD: {:.1} ms", self.rmssd)?;
+        writeln!(f, "pNN50: {:.1}%", self.pnn50)?;
+        writeln!(
+            f,
+            "LF: {}  HF: {}  LF/HF: {}",
+            value(self.lf, " ms²"),
+            value(self.hf, " ms²"),
+            self.lf_hf
+                .map_or_else(|| "-".to_owned(), |ratio| format!("{:.2}", ratio))
+        )?;
+        writeln!(f, "SD1: {:.1} ms  SD2: {:.1} ms", self.sd1, self.sd2)?;
+        write!(
+            f,
+            "DFA α1: {}",
+            self.dfa_alpha1
+                .map_or_else(|| "-".to_owned(), |alpha| format!("{:.2}", alpha))
+        )
+    }
+}
+
+/// Time-domain HRV of one window of the continuous series.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HrvSample {
+    /// Start of the window.
+    pub start: NaiveDateTime,
+    /// Number of RR intervals used.
+    pub beats: usize,
+    pub mean_nn: f64,
+    pub sdnn: f64,
+    pub rmssd: f64,
+    /// Share of intervals that were corrected as artifacts, in percent.
+    pub artifact_rate: f64,
+    /// Share of the window covered by RR intervals, in percent. Low when
+    /// the strap was off or lost the signal for part of the window.
+    pub coverage: f64,
+}
+
+impl HrvSample {
+    /// Most artifacts in a window that is still trusted, in percent.
+    pub const MAX_ARTIFACT_RATE: f64 = 5.0;
+    /// Least coverage of a window that is still trusted, in percent.
+    pub const MIN_COVERAGE: f64 = 80.0;
+
+    /// Whether the window had a clean enough signal to compare with others.
+    pub fn is_reliable(&self) -> bool {
+        self.artifact_rate <= Self::MAX_ARTIFACT_RATE && self.coverage >= Self::MIN_COVERAGE
+    }
+}
+
+impl From<hrv_samples::Model> for HrvSample {
+    fn from(value: hrv_samples::Model) -> Self {
+        Self {
+            start: value.start,
+            beats: usize::try_from(value.beats).unwrap_or(0),
+            mean_nn: value.mean_nn,
+            sdnn: value.sdnn,
+            rmssd: value.rmssd,
+            artifact_rate: value.artifact_rate,
+            coverage: value.coverage,
+        }
+    }
+}
+
+/// Time-domain, frequency-domain (Lomb-Scargle, as RR intervals are not
+/// evenly sampled) and non-linear HRV metrics.
+pub struct HrvCalculator;
+
+impl HrvCalculator {
+    /// Fewest RR intervals to calculate metrics from.
+    pub const MIN_INTERVALS: usize = 30;
+    /// Fewest RR intervals for DFA α1: four boxes of the largest scale.
+    pub const MIN_DFA_INTERVALS: usize = 4 * Self::DFA_MAX_SCALE;
+    /// Shortest series, in seconds, for frequency-domain metrics.
+    const MIN_SPECTRUM_SECONDS: f64 = 120.0;
+
+    const LF_BAND: (f64, f64) = (0.04, 0.15);
+    const HF_BAND: (f64, f64) = (0.15, 0.4);
+    /// Frequency resolution of the periodogram, Hz.
+    const FREQUENCY_STEP: f64 = 0.001;
+
+    const DFA_MIN_SCALE: usize = 4;
+    const DFA_MAX_SCALE: usize = 16;
+
+    /// Length of the windows of the continuous series, in minutes.
+    pub const SAMPLE_MINUTES: u32 = 5;
+
+    /// Metrics of the artifact corrected RR intervals of the readings.
+    pub fn from_history(history: &[ParsedHistoryReading]) -> Option<HrvMetrics> {
+        Self::from_corrected(&RrArtifactCorrector::from_readings(history))
+    }
+
+    /// Metrics of consecutive RR intervals, in ms, after artifact correction.
+    pub fn calculate(rr: &[f64]) -> Option<HrvMetrics> {
+        Self::from_corrected(&RrArtifactCorrector::correct(rr))
+    }
+
+    fn from_corrected(corrected: &CorrectedRr) -> Option<HrvMetrics> {
+        let rr = corrected.rr.as_slice();
+        if rr.len() < Self::MIN_INTERVALS {
+            return None;
+        }
+
+        let mean_nn = mean(rr);
+        let sdnn = sample_std_dev(rr);
+        let rmssd = Self::rmssd(rr)?;
+        let differences = successive_differences(rr);
+        let pnn50 = differences.iter().filter(|d| d.abs() > 50.0).count() as f64
+            / differences.len() as f64
+            * 100.0;
+
+        let (lf, hf) = match Self::spectrum(rr) {
+            Some(spectrum) => (
+                Some(band_power(&spectrum, Self::LF_BAND)),
+                Some(band_power(&spectrum, Self::HF_BAND)),
+            ),
+            None => (None, None),
+        };
+        let lf_hf = lf.zip(hf).and_then(|(lf, hf)| (hf > 0.0).then(|| lf / hf));
+
+        // SD1² = var(ΔRR) / 2, SD2² = 2 SDNN² - SD1²
+        let sd1 = (sample_variance(&differences) / 2.0).sqrt();
+        let sd2 = (2.0 * sdnn.powi(2) - sd1.powi(2)).max(0.0).sqrt();
+
+        Some(HrvMetrics {
+            count: rr.len(),
+            artifact_rate: corrected.artifact_rate(),
+            mean_nn,
+            sdnn,
+            rmssd,
+            pnn50,
+            lf,
+            hf,
+            lf_hf,
+            sd1,
+            sd2,
+            dfa_alpha1: Self::dfa_alpha1(rr),
+        })
+    }
+
+    /// HRV of every wall-clock aligned window of [`Self::SAMPLE_MINUTES`]
+    /// in the readings, which must be sorted by time. Windows with fewer
+    /// than [`Self::MIN_INTERVALS`] usable intervals are left out.
+    pub fn samples(history: &[ParsedHistoryReading]) -> Vec<HrvSample> {
+        let window_ms = f64::from(Self::SAMPLE_MINUTES * 60 * 1000);
+        history
+            .chunk_by(|a, b| Self::window_start(a.time) == Self::window_start(b.time))
+            .filter_map(|window| {
+                let corrected = RrArtifactCorrector::from_readings(window);
+                let rr = corrected.rr.as_slice();
+                if rr.len() < Self::MIN_INTERVALS {
+                    return None;
+                }
+
+                Some(HrvSample {
+                    start: Self::window_start(window[0].time),
+                    beats: rr.len(),
+                    mean_nn: mean(rr),
+                    sdnn: sample_std_dev(rr),
+                    rmssd: Self::rmssd(rr)?,
+                    artifact_rate: corrected.artifact_rate(),
+                    coverage: (rr.iter().sum::<f64>() / window_ms * 100.0).min(100.0),
+                })
+            })
+            .collect()
+    }
+
+    /// Start of the sample window containing `time`.
+    pub fn window_start(time: NaiveDateTime) -> NaiveDateTime {
+        let minute = time.minute() - time.minute() % Self::SAMPLE_MINUTES;
+        time.with_minute(minute)
+            .and_then(|t| t.with_second(0))
+            .and_then(|t| t.with_nanosecond(0))
+            .unwrap_or(time)
+    }
+
+    /// Root mean square of successive differences.
+    pub fn rmssd(rr: &[f64]) -> Option<f64> {
+        if rr.len() < 2 {
+            return None;
+        }
+        let differences = successive_differences(rr);
+        Some((differences.iter().map(|d| d * d).sum::<f64>() / differences.len() as f64).sqrt())
+    }
+
+    /// Power spectral density in ms²/Hz on a grid up to the top of the HF
+    /// band, from the Lomb-Scargle periodogram of the beat times.
+    fn spectrum(rr: &[f64]) -> Option<Vec<(f64, f64)>> {
+        let times = rr
+            .iter()
+            .scan(0.0, |time, rr| {
+                *time += rr / 1000.0;
+                Some(*time)
+            })
+            .collect::<Vec<_>>();
+        let duration = times.last()? - times.first()?;
+        if duration < Self::MIN_SPECTRUM_SECONDS {
+            return None;
+        }
+
+        let mean = mean(rr);
+        let values = rr.iter().map(|rr| rr - mean).collect::<Vec<_>>();
+        // A sinusoid of amplitude A peaks at N·A²/4 over a 1/T wide lobe,
+        // so this scale makes the spectrum integrate to the variance.
+        let scale = 2.0 * duration / rr.len() as f64;
+
+        let steps = (Self::HF_BAND.1 / Self::FREQUENCY_STEP).round() as usize;
+        Some(
+            (1..=steps)
+                .map(|step| {
+                    let frequency = step as f64 * Self::FREQUENCY_STEP;
+                    let power = lomb_scargle(&times, &values, frequency);
+                    (frequency, power * scale)
+                })
+                .collect(),
+        )
+    }
+
+    /// Detrended fluctuation analysis over scales of 4-16 beats: the slope
+    /// of log F(n) against log n.
+    fn dfa_alpha1(rr: &[f64]) -> Option<f64> {
+        if rr.len() < Self::MIN_DFA_INTERVALS {
+            return None;
+        }
+
+        let mean = mean(rr);
+        let profile = rr
+            .iter()
+            .scan(0.0, |sum, rr| {
+                *sum += rr - mean;
+                Some(*sum)
+            })
+            .collect::<Vec<_>>();
+
+        let points = (Self::DFA_MIN_SCALE..=Self::DFA_MAX_SCALE)
+            .filter_map(|scale| {
+                let residuals = profile
+                    .chunks_exact(scale)
+                    .flat_map(detrend)
+                    .collect::<Vec<_>>();
+                let fluctuation =
+                    (residuals.iter().map(|r| r * r).sum::<f64>() / residuals.len() as f64).sqrt();
+                (fluctuation > 0.0).then(|| ((scale as f64).ln(), fluctuation.ln()))
+            })
+            .collect::<Vec<_>>();
+
+        linear_fit(&points).map(|(slope, _)| slope)
+    }
+}
+
+fn successive_differences(rr: &[f64]) -> Vec<f64> {
+    rr.windows(2).map(|w| w[1] - w[0]).collect()
+}
+
+/// Unnormalised Lomb-Scargle periodogram of zero-mean `values` sampled at
+/// `times` (s) at `frequency` (Hz).
+fn lomb_scargle(times: &[f64], values: &[f64], frequency: f64) -> f64 {
+    let omega = 2.0 * std::f64::consts::PI * frequency;
+    let (sin_sum, cos_sum) = times.iter().fold((0.0, 0.0), |(s, c), t| {
+        (s + (2.0 * omega * t).sin(), c + (2.0 * omega * t).cos())
+    });
+    let tau = sin_sum.atan2(cos_sum) / (2.0 * omega);
+
+    let (mut yc, mut ys, mut cc, mut ss) = (0.0, 0.0, 0.0, 0.0);
+    for (t, y) in times.iter().zip(values) {
+        let (sin, cos) = (omega * (t - tau)).sin_cos();
+        yc += y * cos;
+        ys += y * sin;
+        cc += cos * cos;
+        ss += sin * sin;
+    }
+
+    let cos_term = if cc > 0.0 { yc * yc / cc } else { 0.0 };
+    let sin_term = if ss > 0.0 { ys * ys / ss } else { 0.0 };
+    (cos_term + sin_term) / 2.0
+}
+
+/// Power in `[low, high)` Hz of a spectrum on an even grid.
+fn band_power(spectrum: &[(f64, f64)], (low, high): (f64, f64)) -> f64 {
+    let step = match spectrum {
+        [(first, _), (second, _), ..] => second - first,
+        _ => return 0.0,
+    };
+    // Grid points are multiples of the step; compare with half a step of
+    // slack so rounding doesn't move a point across a band edge.
+    spectrum
+        .iter()
+        .filter(|(frequency, _)| *frequency >= low - step / 2.0 && *frequency < high - step / 2.0)
+        .map(|(_, density)| density * step)
+        .sum()
+}
+
+/// Residuals of `values` around their least squares line.
+fn detrend(values: &[f64]) -> Vec<f64> {
+    let points = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (i as f64, *v))
+        .collect::<Vec<_>>();
+    let (slope, intercept) = linear_fit(&points).unwrap_or((0.0, mean(values)));
+    points
+        .iter()
+        .map(|(x, y)| y - (slope * x + intercept))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    fn alternating(count: usize, low: f64, high: f64) -> Vec<f64> {
+        (0..count)
+            .map(|i| if i % 2 == 0 { low } else { high })
+            .collect()
+    }
+
+    #[test]
+    fn time_domain_metrics() {
+        let metrics = HrvCalculator::calculate(&alternating(40, 800.0, 860.0)).unwrap();
+        assert_eq!(metrics.count, 40);
+        assert_eq!(metrics.artifact_rate, 0.0);
+        assert_eq!(metrics.mean_nn, 830.0);
+        assert_eq!(metrics.rmssd, 60.0);
+        assert_eq!(metrics.pnn50, 100.0);
+        assert!((metrics.sdnn - 30.38).abs() < 0.01, "{metrics:?}");
+        // Alternating beats only vary beat to beat.
+        assert!(metrics.sd1 > metrics.sd2);
+
+        let metrics = HrvCalculator::calculate(&alternating(40, 800.0, 840.0)).unwrap();
+        assert_eq!(metrics.pnn50, 0.0);
+        // Under two minutes: no spectrum, under 64 beats: no DFA
+        assert_eq!(metrics.lf, None);
+        assert_eq!(metrics.dfa_alpha1, None);
+
+        assert!(HrvCalculator::calculate(&[800.0; 10]).is_none());
+    }
+
+    #[test]
+    fn samples_cover_aligned_windows() {
+        let base = chrono::NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(12, 3, 0)
+            .unwrap();
+        // 12:03-12:15 at one reading per second with one RR interval each,
+        // with a gap from 12:06 to 12:08 and a missed beat at 12:11:00
+        let history = (0..720)
+            .filter(|i| !(180..300).contains(i))
+            .map(|i| ParsedHistoryReading {
+                time: base + chrono::TimeDelta::seconds(i),
+                bpm: 60,
+                rr: if i == 480 {
+                    vec![2000]
+                } else {
+                    vec![if i % 2 == 0 { 980 } else { 1020 }]
+                },
+                imu_data: None,
+                gravity: None,
+                sensor_data: None,
+            })
+            .collect::<Vec<_>>();
+
+        let samples = HrvCalculator::samples(&history);
+        let starts = samples
+            .iter()
+            .map(|sample| sample.start.format("%H:%M").to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(starts, ["12:00", "12:05", "12:10"]);
+
+        let full = samples[2];
+        assert_eq!(full.beats, 300);
+        assert!((full.coverage - 100.0).abs() < 0.5, "{full:?}");
+        assert!((full.artifact_rate - 1.0 / 3.0).abs() < 1e-9, "{full:?}");
+        assert!((full.rmssd - 40.0).abs() < 1.0, "{full:?}");
+        assert!(full.is_reliable());
+
+        // Two of five minutes at 12:00, three at 12:05
+        assert!((samples[0].coverage - 40.0).abs() < 0.5);
+        assert!((samples[1].coverage - 60.0).abs() < 0.5);
+        assert!(!samples[1].is_reliable());
+    }
+
+    #[test]
+    fn breathing_shows_up_as_high_frequency_power() {
+        // RR modulated by 50 ms at 0.25 Hz (15 breaths/min) for ~5 minutes
+        let mut time = 0.0;
+        let rr = (0..300)
+            .map(|_| {
+                let rr = 1000.0 + 50.0 * (2.0 * std::f64::consts::PI * 0.25 * time).sin();
+                time += rr / 1000.0;
+                rr
+            })
+            .collect::<Vec<_>>();
+
+        let metrics = HrvCalculator::calculate(&rr).unwrap();
+        let (lf, hf) = (metrics.lf.unwrap(), metrics.hf.unwrap());
+        // Variance of the sinusoid is 50² / 2 = 1250 ms²
+        assert!((hf - 1250.0).abs() < 150.0, "{metrics:?}");
+        assert!(lf < hf * 0.1, "{metrics:?}");
+        assert!(metrics.lf_hf.unwrap() < 0.1);
+    }
+
+    #[test]
+    fn dfa_separates_noise_from_random_walk() {
+        let mut rng = rand::rng();
+        let noise = (0..2000)
+            .map(|_| 900.0 + rng.random_range(-40.0..40.0))
+            .collect::<Vec<_>>();
+        let alpha = HrvCalculator::calculate(&noise)
+            .unwrap()
+            .dfa_alpha1
+            .unwrap();
+        assert!((alpha - 0.5).abs() < 0.2, "white noise alpha1 {alpha}");
+
+        let walk = noise
+            .iter()
+            .scan(900.0, |rr, v| {
+                *rr += (v - 900.0) / 10.0;
+                Some(*rr)
+            })
+            .collect::<Vec<_>>();
+        let alpha = HrvCalculator::calculate(&walk).unwrap().dfa_alpha1.unwrap();
+        assert!(alpha > 1.2, "random walk alpha1 {alpha}");
+    }
+}
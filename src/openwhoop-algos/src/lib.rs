@@ -1,19 +1,78 @@
 pub(crate) mod activity;
-pub use activity::{ActivityPeriod, MAX_SLEEP_PAUSE};
+pub use activity::{ActivityPeriod, MAX_SLEEP_PAUSE, WearTimeline};
+
+pub(crate) mod activity_classifier;
+pub use activity_classifier::{ActivityClassification, ActivityClassifier};
 
 pub(crate) mod sleep;
 pub use sleep::SleepCycle;
 
+pub(crate) mod sleep_stages;
+pub use sleep_stages::{SleepEpoch, SleepStage, SleepStageTotals, SleepStager};
+
+pub(crate) mod synthetic;
+pub use synthetic::{SyntheticNight, SyntheticScenario, SyntheticVitals};
+
+pub(crate) mod sleep_need;
+pub use sleep_need::{SleepNeed, SleepNeedCalculator};
+
+pub(crate) mod sleep_score;
+pub use sleep_score::{SleepScore, SleepScoreCalculator};
+
 pub(crate) mod sleep_consistency;
 pub use sleep_consistency::SleepConsistencyAnalyzer;
 
+pub(crate) mod circadian;
+pub use circadian::{Chronotype, CircadianAnalyzer, CircadianMetrics, HeartRateRhythm};
+
+pub(crate) mod rr_artifacts;
+pub use rr_artifacts::{CorrectedRr, RrArtifactCorrector};
+
+pub(crate) mod hrv;
+pub use hrv::{HrvCalculator, HrvMetrics, HrvSample};
+
 pub(crate) mod stress;
 pub use stress::{StressCalculator, StressScore};
 
+pub(crate) mod calories;
+pub use calories::{CalorieCalculator, Calories};
+
 pub(crate) mod exercise;
 pub use exercise::ExerciseMetrics;
 
+pub(crate) mod profile;
+pub use profile::{Sex, UserProfile};
+
+pub(crate) mod recovery;
+pub use recovery::{RecoveryBaseline, RecoveryCalculator, RecoveryScore};
+
+pub(crate) mod health_alerts;
+pub use health_alerts::{
+    AlertThresholds, HealthAlert, HealthAlertDetector, HealthMetric, NightlyMetrics,
+};
+
+pub(crate) mod resp_rate;
+pub use resp_rate::{RespiratoryRate, RespiratoryRateCalculator};
+
+pub(crate) mod skin_temp;
+pub use skin_temp::{SkinTempCalibration, SkinTempDeviation, SkinTempReading};
+
+pub(crate) mod spo2;
+pub use spo2::{SpO2Calculator, SpO2Score};
+
 pub(crate) mod strain;
 pub use strain::{StrainCalculator, StrainScore};
 
+pub(crate) mod workout;
+pub use workout::{Workout, WorkoutDetector};
+
+pub(crate) mod zones;
+pub use zones::{HeartRateZones, ZoneBasis, ZoneBreakdown};
+
+pub(crate) mod steps;
+pub use steps::{StepCounter, StepMinute};
+
+pub(crate) mod vo2max;
+pub use vo2max::{Vo2MaxEstimate, Vo2MaxEstimator};
+
 pub mod helpers;
@@ -0,0 +1,130 @@
+use std::{fmt, str::FromStr};
+
+use chrono::NaiveDate;
+use openwhoop_codec::WhoopError;
+use openwhoop_entities::profile;
+
+use crate::ZoneBasis;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sex {
+    Male,
+    Female,
+}
+
+impl Sex {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Male => "male",
+            Self::Female => "female",
+        }
+    }
+}
+
+impl fmt::Display for Sex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Sex {
+    type Err = WhoopError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "male" | "m" => Ok(Self::Male),
+            "female" | "f" => Ok(Self::Female),
+            _ => Err(WhoopError::InvalidData),
+        }
+    }
+}
+
+/// What the user told us about themselves. Every field is optional;
+/// calculations fall back to what the data shows when one is missing.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct UserProfile {
+    pub birth_date: Option<NaiveDate>,
+    pub sex: Option<Sex>,
+    pub weight_kg: Option<f64>,
+    pub height_cm: Option<f64>,
+    /// Known max heart rate, e.g. from a lab test. Takes precedence over
+    /// the age prediction.
+    pub max_hr: Option<u8>,
+    /// Basis of the heart rate zones, heart rate reserve when not set.
+    pub zone_basis: Option<ZoneBasis>,
+}
+
+impl UserProfile {
+    pub fn age_on(&self, date: NaiveDate) -> Option<u32> {
+        self.birth_date.and_then(|birth| date.years_since(birth))
+    }
+
+    /// Max heart rate predicted from age (Tanaka et al., 2001).
+    pub fn predicted_max_hr(&self, date: NaiveDate) -> Option<u8> {
+        self.age_on(date)
+            .map(|age| (208.0 - 0.7 * f64::from(age)).round().clamp(0.0, 255.0) as u8)
+    }
+
+    /// Configured max heart rate, else the age prediction. `None` means the
+    /// caller has to fall back to the highest heart rate observed.
+    pub fn max_hr_on(&self, date: NaiveDate) -> Option<u8> {
+        self.max_hr.or_else(|| self.predicted_max_hr(date))
+    }
+}
+
+impl From<profile::Model> for UserProfile {
+    fn from(value: profile::Model) -> Self {
+        Self {
+            birth_date: value.birth_date,
+            sex: value.sex.and_then(|sex| sex.parse().ok()),
+            weight_kg: value.weight_kg,
+            height_cm: value.height_cm,
+            max_hr: value.max_hr.and_then(|hr| u8::try_from(hr).ok()),
+            zone_basis: value.zone_basis.and_then(|basis| basis.parse().ok()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn age_counts_full_years() {
+        let profile = UserProfile {
+            birth_date: Some(date(1990, 6, 15)),
+            ..Default::default()
+        };
+        assert_eq!(profile.age_on(date(2025, 6, 14)), Some(34));
+        assert_eq!(profile.age_on(date(2025, 6, 15)), Some(35));
+        assert_eq!(UserProfile::default().age_on(date(2025, 1, 1)), None);
+    }
+
+    #[test]
+    fn max_hr_prefers_override_then_age() {
+        let today = date(2025, 6, 15);
+        let mut profile = UserProfile {
+            birth_date: Some(date(1990, 6, 15)),
+            ..Default::default()
+        };
+        // 208 - 0.7 * 35 = 183.5
+        assert_eq!(profile.predicted_max_hr(today), Some(184));
+        assert_eq!(profile.max_hr_on(today), Some(184));
+
+        profile.max_hr = Some(191);
+        assert_eq!(profile.max_hr_on(today), Some(191));
+
+        assert_eq!(UserProfile::default().max_hr_on(today), None);
+    }
+
+    #[test]
+    fn sex_round_trips() {
+        assert_eq!("F".parse::<Sex>().unwrap(), Sex::Female);
+        assert_eq!(Sex::Male.to_string().parse::<Sex>().unwrap(), Sex::Male);
+        assert!("other".parse::<Sex>().is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use std::fmt;
+
+use chrono::NaiveDate;
+use openwhoop_entities::recovery;
+
+use crate::SleepCycle;
+
+/// Daily recovery, 0-100, with the component scores it was built from.
+/// Respiratory rate and skin temperature are `None` when the night has no
+/// value for them and are left out of the total.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecoveryScore {
+    pub date: NaiveDate,
+    pub score: f64,
+    pub hrv: f64,
+    pub rhr: f64,
+    pub sleep: f64,
+    pub resp_rate: Option<f64>,
+    pub skin_temp: Option<f64>,
+}
+
+/// Averages of the nights before the scored one. Respiratory rate is `None`
+/// when none of those nights has a value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecoveryBaseline {
+    pub hrv: f64,
+    pub rhr: f64,
+    pub resp_rate: Option<f64>,
+}
+
+pub struct RecoveryCalculator;
+
+impl RecoveryCalculator {
+    const HRV_WEIGHT: f64 = 0.4;
+    const RHR_WEIGHT: f64 = 0.2;
+    const SLEEP_WEIGHT: f64 = 0.25;
+    const RESP_RATE_WEIGHT: f64 = 0.075;
+    const SKIN_TEMP_WEIGHT: f64 = 0.075;
+
+    /// Points per unit of relative HRV change: +/-33% spans the full range.
+    const HRV_SCALE: f64 = 150.0;
+    /// Points per unit of relative RHR change: +/-20% spans the full range.
+    const RHR_SCALE: f64 = 250.0;
+    /// Points lost per breath/min above the baseline.
+    const RESP_RATE_PENALTY: f64 = 25.0;
+    /// Points lost per degree Celsius away from the baseline.
+    const SKIN_TEMP_PENALTY: f64 = 100.0;
+
+    /// Score `night` against the `baseline` of the nights before it.
+    pub fn calculate(
+        night: &SleepCycle,
+        skin_temp_deviation: Option<f64>,
+        baseline: &RecoveryBaseline,
+    ) -> RecoveryScore {
+        let hrv = relative_score(f64::from(night.avg_hrv), baseline.hrv, Self::HRV_SCALE);
+        let rhr = relative_score(baseline.rhr, f64::from(night.avg_bpm), Self::RHR_SCALE);
+        let sleep = night.score.clamp(0.0, 100.0);
+        let resp_rate = night
+            .resp_rate
+            .zip(baseline.resp_rate)
+            .map(|(rate, baseline)| {
+                let elevation = (rate.avg - baseline).max(0.0);
+                (100.0 - elevation * Self::RESP_RATE_PENALTY).max(0.0)
+            });
+        let skin_temp = skin_temp_deviation
+            .map(|deviation| (100.0 - deviation.abs() * Self::SKIN_TEMP_PENALTY).max(0.0));
+
+        let components = [
+            (Some(hrv), Self::HRV_WEIGHT),
+            (Some(rhr), Self::RHR_WEIGHT),
+            (Some(sleep), Self::SLEEP_WEIGHT),
+            (resp_rate, Self::RESP_RATE_WEIGHT),
+            (skin_temp, Self::SKIN_TEMP_WEIGHT),
+        ];
+        let (sum, weights) = components
+            .into_iter()
+            .filter_map(|(value, weight)| value.map(|value| (value * weight, weight)))
+            .fold((0.0, 0.0), |(sum, weights), (value, weight)| {
+                (sum + value, weights + weight)
+            });
+
+        RecoveryScore {
+            date: night.id,
+            score: (sum / weights).clamp(0.0, 100.0),
+            hrv,
+            rhr,
+            sleep,
+            resp_rate,
+            skin_temp,
+        }
+    }
+}
+
+/// 50 at the baseline, higher when `value` is above `baseline`.
+fn relative_score(value: f64, baseline: f64, scale: f64) -> f64 {
+    if baseline <= 0.0 {
+        return 50.0;
+    }
+    (50.0 + (value - baseline) / baseline * scale).clamp(0.0, 100.0)
+}
+
+impl From<recovery::Model> for RecoveryScore {
+    fn from(value: recovery::Model) -> Self {
+        Self {
+            date: value.date,
+            score: value.score,
+            hrv: value.hrv_score,
+            rhr: value.rhr_score,
+            sleep: value.sleep_score,
+            resp_rate: value.resp_rate_score,
+            skin_temp: value.skin_temp_score,
+        }
+    }
+}
+
+impl fmt::Display for RecoveryScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let component = |value: Option<f64>| {
+            value.map_or_else(|| "-".to_owned(), |value| format!("{:.0}", value))
+        };
+        write!(
+            f,
+            "{}: {:.0}% (HRV {:.0}, RHR {:.0}, sleep {:.0}, respiratory rate {}, skin temp {})",
+            self.date,
+            self.score,
+            self.hrv,
+            self.rhr,
+            self.sleep,
+            component(self.resp_rate),
+            component(self.skin_temp)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RespiratoryRate;
+    use chrono::TimeDelta;
+
+    fn night(day: u32, avg_hrv: u16, avg_bpm: u8) -> SleepCycle {
+        let start = NaiveDate::from_ymd_opt(2025, 1, day)
+            .unwrap()
+            .and_hms_opt(23, 0, 0)
+            .unwrap();
+        let end = start + TimeDelta::hours(8);
+        SleepCycle {
+            id: end.date(),
+            start,
+            end,
+            min_bpm: avg_bpm - 5,
+            max_bpm: avg_bpm + 10,
+            avg_bpm,
+            min_hrv: avg_hrv / 2,
+            max_hrv: avg_hrv * 2,
+            avg_hrv,
+            score: 80.0,
+            ..Default::default()
+        }
+    }
+
+    fn baseline() -> RecoveryBaseline {
+        RecoveryBaseline {
+            hrv: 60.0,
+            rhr: 55.0,
+            resp_rate: None,
+        }
+    }
+
+    #[test]
+    fn baseline_night_scores_midrange() {
+        let recovery = RecoveryCalculator::calculate(&night(8, 60, 55), None, &baseline());
+        assert_eq!(recovery.date, NaiveDate::from_ymd_opt(2025, 1, 9).unwrap());
+        assert_eq!(recovery.hrv, 50.0);
+        assert_eq!(recovery.rhr, 50.0);
+        assert_eq!(recovery.resp_rate, None);
+        let expected = (50.0 * 0.4 + 50.0 * 0.2 + 80.0 * 0.25) / 0.85;
+        assert!((recovery.score - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn higher_hrv_and_lower_rhr_improve_recovery() {
+        let normal = RecoveryCalculator::calculate(&night(8, 60, 55), None, &baseline());
+        let good = RecoveryCalculator::calculate(&night(8, 75, 50), None, &baseline());
+        let poor = RecoveryCalculator::calculate(&night(8, 45, 62), None, &baseline());
+        assert!(good.score > normal.score && normal.score > poor.score);
+        assert!(good.hrv > 50.0 && good.rhr > 50.0);
+    }
+
+    #[test]
+    fn elevated_resp_rate_and_skin_temp_lower_recovery() {
+        let rate = |avg| RespiratoryRate {
+            min: avg - 1.0,
+            avg,
+            max: avg + 1.0,
+        };
+        let previous = RecoveryBaseline {
+            resp_rate: Some(14.0),
+            ..baseline()
+        };
+
+        let mut calm = night(8, 60, 55);
+        calm.resp_rate = Some(rate(14.0));
+        let mut sick = calm;
+        sick.resp_rate = Some(rate(16.0));
+
+        let calm = RecoveryCalculator::calculate(&calm, Some(0.0), &previous);
+        let sick = RecoveryCalculator::calculate(&sick, Some(0.8), &previous);
+        assert_eq!(calm.resp_rate, Some(100.0));
+        assert_eq!(sick.resp_rate, Some(50.0));
+        assert!((sick.skin_temp.unwrap() - 20.0).abs() < 1e-9);
+        assert!(sick.score < calm.score);
+    }
+}
@@ -0,0 +1,291 @@
+use std::fmt;
+
+use openwhoop_codec::ParsedHistoryReading;
+
+use crate::{
+    RrArtifactCorrector,
+    helpers::time_math::{mean, std_dev},
+};
+
+/// Nightly respiratory rate in breaths per minute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RespiratoryRate {
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64,
+}
+
+pub struct RespiratoryRateCalculator;
+
+impl RespiratoryRateCalculator {
+    /// Readings (~1 Hz) per estimate.
+    pub const WINDOW: usize = 60;
+    /// Window estimates needed for a nightly value.
+    pub const MIN_WINDOWS: usize = 5;
+    /// Beats needed for an RSA estimate.
+    const MIN_BEATS: usize = 30;
+    /// Beats on each side of the moving average used to detrend RR.
+    const DETREND_HALF_WIDTH: usize = 4;
+    /// Fraction of the detrended RR deviation a swing must exceed to count.
+    const HYSTERESIS: f64 = 0.3;
+    /// `resp_rate_raw` is reported in hundredths of a breath per minute.
+    const RAW_SCALE: f64 = 0.01;
+    const MIN_BRPM: f64 = 6.0;
+    const MAX_BRPM: f64 = 30.0;
+
+    /// Estimate respiratory rate over `history`, which should cover one
+    /// sleep. Each window combines the strap's `resp_rate_raw` with the
+    /// respiratory sinus arrhythmia in its RR intervals.
+    pub fn calculate(history: &[ParsedHistoryReading]) -> Option<RespiratoryRate> {
+        let rates = history
+            .chunks(Self::WINDOW)
+            .filter_map(Self::window_rate)
+            .collect::<Vec<_>>();
+
+        if rates.len() < Self::MIN_WINDOWS {
+            return None;
+        }
+
+        Some(RespiratoryRate {
+            min: rates.iter().copied().fold(f64::INFINITY, f64::min),
+            avg: mean(&rates),
+            max: rates.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        })
+    }
+
+    fn window_rate(window: &[ParsedHistoryReading]) -> Option<f64> {
+        let estimates = [Self::sensor_rate(window), Self::rsa_rate(window)]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        (!estimates.is_empty()).then(|| mean(&estimates))
+    }
+
+    /// Mean of the plausible strap-reported values in the window. Firmware
+    /// builds that report a constant outside the physiological range are
+    /// ignored this way.
+    fn sensor_rate(window: &[ParsedHistoryReading]) -> Option<f64> {
+        let rates = window
+            .iter()
+            .filter_map(|reading| reading.sensor_data.as_ref())
+            .filter(|sensor| sensor.skin_contact > 0)
+            .map(|sensor| f64::from(sensor.resp_rate_raw) * Self::RAW_SCALE)
+            .filter(|rate| Self::is_plausible(*rate))
+            .collect::<Vec<_>>();
+
+        (rates.len() >= window.len() / 2 && !rates.is_empty()).then(|| mean(&rates))
+    }
+
+    /// Breathing frequency from the oscillation of detrended RR intervals.
+    fn rsa_rate(window: &[ParsedHistoryReading]) -> Option<f64> {
+        let rr = RrArtifactCorrector::from_readings(window).rr;
+        if rr.len() < Self::MIN_BEATS {
+            return None;
+        }
+
+        let minutes = rr.iter().sum::<f64>() / 60_000.0;
+        let rate = Self::swings(&rr).len() as f64 / 2.0 / minutes;
+        Self::is_plausible(rate).then_some(rate)
+    }
+
+    /// Durations (ms) of individual breaths, measured between RR swings in
+    /// the same direction.
+    pub(crate) fn breath_intervals(rr: &[f64]) -> Vec<f64> {
+        let elapsed = rr
+            .iter()
+            .scan(0.0, |total, rr| {
+                *total += rr;
+                Some(*total)
+            })
+            .collect::<Vec<_>>();
+
+        Self::swings(rr)
+            .iter()
+            .step_by(2)
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|w| elapsed[*w[1]] - elapsed[*w[0]])
+            .collect()
+    }
+
+    /// Beat indices where the detrended RR series changes direction.
+    fn swings(rr: &[f64]) -> Vec<usize> {
+        let detrended = (0..rr.len())
+            .map(|i| {
+                let start = i.saturating_sub(Self::DETREND_HALF_WIDTH);
+                let end = (i + Self::DETREND_HALF_WIDTH + 1).min(rr.len());
+                rr[i] - mean(&rr[start..end])
+            })
+            .collect::<Vec<_>>();
+
+        let spread = std_dev(&detrended);
+        if spread == 0.0 {
+            return Vec::new();
+        }
+        let threshold = spread * Self::HYSTERESIS;
+
+        let mut rising = None;
+        let mut swings = Vec::new();
+        for (i, d) in detrended.into_iter().enumerate() {
+            let state = if d > threshold {
+                Some(true)
+            } else if d < -threshold {
+                Some(false)
+            } else {
+                continue;
+            };
+            if rising.is_some_and(|r| Some(r) != state) {
+                swings.push(i);
+            }
+            rising = state;
+        }
+
+        swings
+    }
+
+    fn is_plausible(rate: f64) -> bool {
+        (Self::MIN_BRPM..=Self::MAX_BRPM).contains(&rate)
+    }
+}
+
+impl RespiratoryRate {
+    /// Rebuild from the nullable `sleep_cycles` columns.
+    pub fn from_columns(min: Option<f64>, avg: Option<f64>, max: Option<f64>) -> Option<Self> {
+        Some(Self {
+            min: min?,
+            avg: avg?,
+            max: max?,
+        })
+    }
+
+    /// Average of several nights, field by field.
+    pub fn mean(rates: impl IntoIterator<Item = Self>) -> Option<Self> {
+        let rates = rates.into_iter().collect::<Vec<_>>();
+        if rates.is_empty() {
+            return None;
+        }
+
+        let field = |f: fn(&Self) -> f64| mean(&rates.iter().map(f).collect::<Vec<_>>());
+        Some(Self {
+            min: field(|r| r.min),
+            avg: field(|r| r.avg),
+            max: field(|r| r.max),
+        })
+    }
+}
+
+impl fmt::Display for RespiratoryRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:.1} br/min (min {:.1}, max {:.1})",
+            self.avg, self.min, self.max
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeDelta};
+    use openwhoop_codec::SensorData;
+
+    fn sensor(resp_rate_raw: u16) -> SensorData {
+        SensorData {
+            ppg_green: 0,
+            ppg_red_ir: 0,
+            spo2_red: 0,
+            spo2_ir: 0,
+            skin_temp_raw: 0,
+            ambient_light: 0,
+            led_drive_1: 0,
+            led_drive_2: 0,
+            resp_rate_raw,
+            signal_quality: 0,
+            skin_contact: 70,
+            accel_gravity: [0.0, 0.0, 1.0],
+            spo2_pct: None,
+        }
+    }
+
+    /// One beat per reading, RR modulated at `brpm` breaths per minute.
+    fn night(
+        minutes: usize,
+        brpm: f64,
+        sensor_data: Option<SensorData>,
+    ) -> Vec<ParsedHistoryReading> {
+        let base = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let mut elapsed = 0.0;
+        (0..minutes * 60)
+            .map(|i| {
+                let phase = 2.0 * std::f64::consts::PI * brpm * elapsed / 60.0 + 0.7;
+                let rr = 1000.0 + 60.0 * phase.sin();
+                elapsed += rr / 1000.0;
+                ParsedHistoryReading {
+                    time: base + TimeDelta::seconds(i as i64),
+                    bpm: 60,
+                    rr: vec![rr as u16],
+                    imu_data: None,
+                    gravity: None,
+                    sensor_data: sensor_data.clone(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rsa_recovers_breathing_frequency() {
+        let rate = RespiratoryRateCalculator::calculate(&night(10, 12.0, None)).unwrap();
+        assert!((rate.avg - 12.0).abs() < 1.5, "{rate:?}");
+        assert!(rate.min <= rate.avg && rate.avg <= rate.max);
+    }
+
+    #[test]
+    fn combines_sensor_and_rsa_estimates() {
+        let rate =
+            RespiratoryRateCalculator::calculate(&night(10, 12.0, Some(sensor(1400)))).unwrap();
+        assert!((rate.avg - 13.0).abs() < 1.0, "{rate:?}");
+    }
+
+    #[test]
+    fn ignores_implausible_sensor_values() {
+        let with_constant = night(10, 12.0, Some(sensor(3073)));
+        let without = night(10, 12.0, None);
+        assert_eq!(
+            RespiratoryRateCalculator::calculate(&with_constant),
+            RespiratoryRateCalculator::calculate(&without)
+        );
+    }
+
+    #[test]
+    fn short_history_yields_nothing() {
+        assert_eq!(
+            RespiratoryRateCalculator::calculate(&night(3, 12.0, None)),
+            None
+        );
+    }
+
+    #[test]
+    fn mean_of_nights() {
+        let rate = RespiratoryRate::mean([
+            RespiratoryRate {
+                min: 12.0,
+                avg: 14.0,
+                max: 16.0,
+            },
+            RespiratoryRate {
+                min: 14.0,
+                avg: 16.0,
+                max: 18.0,
+            },
+        ])
+        .unwrap();
+        assert_eq!(rate.avg, 15.0);
+        assert_eq!(rate.to_string(), "15.0 br/min (min 13.0, max 17.0)");
+        assert_eq!(RespiratoryRate::mean([]), None);
+    }
+}
@@ -0,0 +1,158 @@
+use openwhoop_codec::ParsedHistoryReading;
+
+use crate::helpers::time_math::median;
+
+/// RR intervals, in ms, with artifacts replaced.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CorrectedRr {
+    pub rr: Vec<f64>,
+    /// Number of intervals that were replaced.
+    pub artifacts: usize,
+}
+
+impl CorrectedRr {
+    /// Share of intervals that were artifacts, in percent.
+    pub fn artifact_rate(&self) -> f64 {
+        if self.rr.is_empty() {
+            return if self.artifacts > 0 { 100.0 } else { 0.0 };
+        }
+        self.artifacts as f64 / self.rr.len() as f64 * 100.0
+    }
+}
+
+/// Median filter artifact correction: an interval outside the physiological
+/// range, or more than 20% off the median of the beats around it, is an
+/// ectopic beat or a missed or extra detection and gets interpolated from the
+/// nearest good intervals.
+pub struct RrArtifactCorrector;
+
+impl RrArtifactCorrector {
+    /// Beats on each side of the one being checked.
+    const HALF_WINDOW: usize = 5;
+    /// Largest deviation from the local median, as a fraction of it.
+    const MAX_DEVIATION: f64 = 0.2;
+    /// 200 bpm.
+    const MIN_RR: f64 = 300.0;
+    /// 30 bpm.
+    const MAX_RR: f64 = 2000.0;
+
+    /// Corrected RR intervals of the readings. Zero intervals, which the
+    /// device sends when it has none, are dropped rather than corrected.
+    pub fn from_readings<'a>(
+        readings: impl IntoIterator<Item = &'a ParsedHistoryReading>,
+    ) -> CorrectedRr {
+        let rr = readings
+            .into_iter()
+            .flat_map(|reading| reading.rr.iter())
+            .filter(|&&rr| rr > 0)
+            .map(|&rr| f64::from(rr))
+            .collect::<Vec<_>>();
+        Self::correct(&rr)
+    }
+
+    /// Corrected series of the same length, or an empty one when no interval
+    /// is usable.
+    pub fn correct(rr: &[f64]) -> CorrectedRr {
+        let in_range = rr
+            .iter()
+            .map(|rr| (Self::MIN_RR..=Self::MAX_RR).contains(rr))
+            .collect::<Vec<_>>();
+
+        let good = (0..rr.len())
+            .map(|i| {
+                if !in_range[i] {
+                    return false;
+                }
+                let start = i.saturating_sub(Self::HALF_WINDOW);
+                let end = (i + Self::HALF_WINDOW + 1).min(rr.len());
+                let window = (start..end)
+                    .filter(|&j| in_range[j])
+                    .map(|j| rr[j])
+                    .collect::<Vec<_>>();
+                let median = median(&window);
+                (rr[i] - median).abs() <= median * Self::MAX_DEVIATION
+            })
+            .collect::<Vec<_>>();
+
+        let artifacts = good.iter().filter(|good| !**good).count();
+        if artifacts == rr.len() {
+            return CorrectedRr {
+                rr: Vec::new(),
+                artifacts,
+            };
+        }
+
+        let corrected = (0..rr.len())
+            .map(|i| {
+                if good[i] {
+                    return rr[i];
+                }
+                let previous = (0..i).rev().find(|&j| good[j]);
+                let next = (i + 1..rr.len()).find(|&j| good[j]);
+                match (previous, next) {
+                    (Some(p), Some(n)) => {
+                        let t = (i - p) as f64 / (n - p) as f64;
+                        rr[p] + (rr[n] - rr[p]) * t
+                    }
+                    (Some(j), None) | (None, Some(j)) => rr[j],
+                    (None, None) => unreachable!("at least one interval is good"),
+                }
+            })
+            .collect();
+
+        CorrectedRr {
+            rr: corrected,
+            artifacts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean_series_is_untouched() {
+        let rr = (0..40)
+            .map(|i| if i % 2 == 0 { 800.0 } else { 860.0 })
+            .collect::<Vec<_>>();
+        let corrected = RrArtifactCorrector::correct(&rr);
+        assert_eq!(corrected.rr, rr);
+        assert_eq!(corrected.artifacts, 0);
+        assert_eq!(corrected.artifact_rate(), 0.0);
+    }
+
+    #[test]
+    fn ectopic_and_missed_beats_are_interpolated() {
+        let mut rr = vec![1000.0; 20];
+        // Premature beat followed by a compensatory pause
+        rr[5] = 600.0;
+        rr[6] = 1400.0;
+        // Missed detection: two beats merged into one
+        rr[12] = 2000.0;
+        // Impossible interval
+        rr[16] = 150.0;
+
+        let corrected = RrArtifactCorrector::correct(&rr);
+        assert_eq!(corrected.rr, vec![1000.0; 20]);
+        assert_eq!(corrected.artifacts, 4);
+        assert_eq!(corrected.artifact_rate(), 20.0);
+    }
+
+    #[test]
+    fn interpolates_between_neighbours() {
+        let rr = [900.0, 950.0, 1500.0, 1050.0, 1100.0, 1000.0, 1000.0];
+        let corrected = RrArtifactCorrector::correct(&rr);
+        assert_eq!(corrected.rr[2], 1000.0);
+        assert_eq!(corrected.artifacts, 1);
+    }
+
+    #[test]
+    fn unusable_series_is_dropped() {
+        let corrected = RrArtifactCorrector::correct(&[100.0, 5000.0]);
+        assert!(corrected.rr.is_empty());
+        assert_eq!(corrected.artifact_rate(), 100.0);
+
+        assert_eq!(RrArtifactCorrector::correct(&[]), CorrectedRr::default());
+    }
+}
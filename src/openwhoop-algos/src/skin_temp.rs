@@ -0,0 +1,172 @@
+use chrono::NaiveDateTime;
+use openwhoop_codec::{ParsedHistoryReading, SensorData, constants::WhoopGeneration};
+
+use crate::helpers::time_math::mean;
+
+/// Linear fit of the skin thermistor ADC around the physiological range:
+/// `celsius = raw * slope + intercept`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkinTempCalibration {
+    pub slope: f64,
+    pub intercept: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SkinTempReading {
+    pub time: NaiveDateTime,
+    pub celsius: f64,
+}
+
+impl SkinTempCalibration {
+    /// Checked against the V12 captures in the codec tests: raw 924 on the
+    /// wrist (skin contact 70) reads 37.0 °C and raw 747 without skin
+    /// contact 29.9 °C.
+    pub const GEN4: Self = Self {
+        slope: 0.04,
+        intercept: 0.0,
+    };
+
+    /// Converted values outside this range are treated as bad contact.
+    const MIN_CELSIUS: f64 = 20.0;
+    const MAX_CELSIUS: f64 = 45.0;
+
+    /// `None` for Gen5: its K=18 packets do not carry the thermistor value
+    /// yet, so there is nothing to fit a calibration to.
+    pub fn for_generation(generation: WhoopGeneration) -> Option<Self> {
+        match generation {
+            WhoopGeneration::Gen4 => Some(Self::GEN4),
+            WhoopGeneration::Gen5 | WhoopGeneration::Placeholder => None,
+        }
+    }
+
+    pub fn celsius(&self, sensor: &SensorData) -> Option<f64> {
+        if sensor.skin_contact == 0 || sensor.skin_temp_raw == 0 {
+            return None;
+        }
+
+        let celsius = f64::from(sensor.skin_temp_raw) * self.slope + self.intercept;
+        (Self::MIN_CELSIUS..=Self::MAX_CELSIUS)
+            .contains(&celsius)
+            .then_some(celsius)
+    }
+
+    pub fn convert(&self, reading: &ParsedHistoryReading) -> Option<SkinTempReading> {
+        let celsius = self.celsius(reading.sensor_data.as_ref()?)?;
+        Some(SkinTempReading {
+            time: reading.time,
+            celsius,
+        })
+    }
+}
+
+pub struct SkinTempDeviation;
+
+impl SkinTempDeviation {
+    /// Nights kept in the rolling personal baseline.
+    pub const BASELINE_NIGHTS: usize = 30;
+    /// Nights needed before a deviation is reported.
+    pub const MIN_BASELINE_NIGHTS: usize = 3;
+    /// Converted readings needed for a night to count.
+    pub const MIN_NIGHT_READINGS: usize = 60;
+
+    /// Mean skin temperature over one sleep.
+    pub fn nightly(temps: &[f64]) -> Option<f64> {
+        (temps.len() >= Self::MIN_NIGHT_READINGS).then(|| mean(temps))
+    }
+
+    /// Deviation of `night` from the mean of the preceding nights.
+    pub fn deviation(night: f64, previous_nights: &[f64]) -> Option<f64> {
+        if previous_nights.len() < Self::MIN_BASELINE_NIGHTS {
+            return None;
+        }
+
+        let recent =
+            &previous_nights[previous_nights.len().saturating_sub(Self::BASELINE_NIGHTS)..];
+        Some(night - mean(recent))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn sensor(skin_temp_raw: u16, skin_contact: u8) -> SensorData {
+        SensorData {
+            ppg_green: 0,
+            ppg_red_ir: 0,
+            spo2_red: 0,
+            spo2_ir: 0,
+            skin_temp_raw,
+            ambient_light: 0,
+            led_drive_1: 0,
+            led_drive_2: 0,
+            resp_rate_raw: 0,
+            signal_quality: 0,
+            skin_contact,
+            accel_gravity: [0.0, 0.0, 1.0],
+            spo2_pct: None,
+        }
+    }
+
+    #[test]
+    fn converts_gen4_thermistor_values() {
+        let calibration = SkinTempCalibration::for_generation(WhoopGeneration::Gen4).unwrap();
+        let celsius = calibration.celsius(&sensor(827, 70)).unwrap();
+        assert!((celsius - 33.08).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejects_off_wrist_and_implausible_values() {
+        let calibration = SkinTempCalibration::GEN4;
+        assert_eq!(calibration.celsius(&sensor(827, 0)), None);
+        assert_eq!(calibration.celsius(&sensor(0, 70)), None);
+        assert_eq!(calibration.celsius(&sensor(2000, 70)), None);
+        assert!(SkinTempCalibration::for_generation(WhoopGeneration::Placeholder).is_none());
+        assert!(SkinTempCalibration::for_generation(WhoopGeneration::Gen5).is_none());
+    }
+
+    #[test]
+    fn convert_keeps_reading_time() {
+        let time = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(3, 0, 0)
+            .unwrap();
+        let reading = ParsedHistoryReading {
+            time,
+            bpm: 55,
+            rr: vec![],
+            imu_data: None,
+            gravity: None,
+            sensor_data: Some(sensor(850, 66)),
+        };
+        let converted = SkinTempCalibration::GEN4.convert(&reading).unwrap();
+        assert_eq!(converted.time, time);
+        assert!((converted.celsius - 34.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn deviation_needs_a_baseline() {
+        assert_eq!(SkinTempDeviation::deviation(34.0, &[33.5, 33.7]), None);
+
+        let deviation = SkinTempDeviation::deviation(34.0, &[33.5, 33.7, 33.6]).unwrap();
+        assert!((deviation - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn deviation_uses_most_recent_nights() {
+        let mut nights = vec![40.0; 10];
+        nights.extend(std::iter::repeat_n(
+            33.0,
+            SkinTempDeviation::BASELINE_NIGHTS,
+        ));
+        let deviation = SkinTempDeviation::deviation(33.5, &nights).unwrap();
+        assert!((deviation - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn nightly_requires_enough_readings() {
+        assert_eq!(SkinTempDeviation::nightly(&[33.0; 10]), None);
+        assert_eq!(SkinTempDeviation::nightly(&[33.0; 60]), Some(33.0));
+    }
+}
@@ -4,9 +4,12 @@ use openwhoop_codec::ParsedHistoryReading;
 use openwhoop_codec::WhoopError;
 use openwhoop_entities::sleep_cycles;
 
-use super::ActivityPeriod;
+use super::{
+    ActivityPeriod, CorrectedRr, HrvCalculator, RespiratoryRate, RespiratoryRateCalculator,
+    RrArtifactCorrector, SleepEpoch, SleepNeedCalculator, SleepScore, SleepStageTotals,
+};
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct SleepCycle {
     pub id: NaiveDate,
     pub start: NaiveDateTime,
@@ -18,6 +21,12 @@ pub struct SleepCycle {
     pub max_hrv: u16,
     pub avg_hrv: u16,
     pub score: f64,
+    pub resp_rate: Option<RespiratoryRate>,
+    pub stages: Option<SleepStageTotals>,
+    pub score_breakdown: Option<SleepScore>,
+    /// Share of RR intervals corrected as artifacts, in percent. `None`
+    /// when the night had no RR intervals.
+    pub rr_artifact_rate: Option<f64>,
 }
 
 impl SleepCycle {
@@ -25,14 +34,21 @@ impl SleepCycle {
         event: ActivityPeriod,
         history: &[ParsedHistoryReading],
     ) -> Result<SleepCycle, WhoopError> {
-        let (heart_rate, rr): (Vec<u64>, Vec<Vec<_>>) = history
+        let history = history
             .iter()
             .filter(|h| h.time >= event.start && h.time <= event.end)
+            .cloned()
+            .collect::<Vec<_>>();
+        let resp_rate = RespiratoryRateCalculator::calculate(&history);
+
+        let (heart_rate, rr): (Vec<u64>, Vec<Vec<_>>) = history
+            .iter()
             .map(|h| (u64::from(h.bpm), h.rr.clone()))
             .unzip();
 
         let rr = Self::clean_rr(rr);
-        let rolling_hrv = Self::rolling_hrv(rr);
+        let rr_artifact_rate = (!rr.rr.is_empty() || rr.artifacts > 0).then(|| rr.artifact_rate());
+        let rolling_hrv = Self::rolling_hrv(rr.rr.iter().map(|&rr| rr.round() as u64).collect());
 
         let min_hrv = u16::try_from(rolling_hrv.iter().min().copied().unwrap_or_default())
             .map_err(|_| WhoopError::Overflow)?;
@@ -65,19 +81,47 @@ impl SleepCycle {
             max_hrv,
             avg_hrv,
             score: Self::sleep_score(event.start, event.end),
+            resp_rate,
+            stages: None,
+            score_breakdown: None,
+            rr_artifact_rate,
         })
     }
 
+    /// Attach per-stage totals from a staged hypnogram of this sleep.
+    pub fn with_stages(mut self, epochs: &[SleepEpoch]) -> Self {
+        self.stages = (!epochs.is_empty()).then(|| SleepStageTotals::from_epochs(epochs));
+        self
+    }
+
+    /// Replace the duration-only score with a multi-factor one.
+    pub fn with_score(mut self, score: SleepScore) -> Self {
+        self.score = score.total();
+        self.score_breakdown = Some(score);
+        self
+    }
+
     pub fn duration(&self) -> TimeDelta {
         self.end - self.start
     }
 
-    fn clean_rr(rr: Vec<Vec<u16>>) -> Vec<u64> {
-        rr.into_iter()
+    /// Time asleep, falling back to time in bed when the sleep is not staged.
+    pub fn asleep(&self) -> TimeDelta {
+        self.stages
+            .map(|stages| stages.asleep())
+            .unwrap_or_else(|| self.duration())
+    }
+
+    /// RR intervals without the zeros the device sends when it has none,
+    /// artifact corrected.
+    fn clean_rr(rr: Vec<Vec<u16>>) -> CorrectedRr {
+        let rr = rr
+            .into_iter()
             .flatten()
             .filter(|&v| v > 0)
-            .map(u64::from)
-            .collect()
+            .map(f64::from)
+            .collect::<Vec<_>>();
+        RrArtifactCorrector::correct(&rr)
     }
 
     fn rolling_hrv(rr: Vec<u64>) -> Vec<u64> {
@@ -85,24 +129,17 @@ impl SleepCycle {
     }
 
     fn calculate_rmssd(window: &[u64]) -> Option<u64> {
-        if window.len() < 2 {
-            return None;
-        }
-
-        let rr_diff: Vec<f64> = window
-            .windows(2)
-            .map(|w| (w[1] as f64 - w[0] as f64).powi(2))
-            .collect();
-
-        let rr_count = rr_diff.len() as f64;
-        Some((rr_diff.into_iter().sum::<f64>() / rr_count).sqrt() as u64)
+        let window = window.iter().map(|&rr| rr as f64).collect::<Vec<_>>();
+        HrvCalculator::rmssd(&window).map(|rmssd| rmssd as u64)
     }
 
+    /// Duration-only score against the default sleep need, used when a
+    /// night has no stored score.
     pub fn sleep_score(start: NaiveDateTime, end: NaiveDateTime) -> f64 {
         let duration = (end - start).num_seconds();
-        const IDEAL_DURATION: i64 = 60 * 60 * 8;
+        let ideal_duration = SleepNeedCalculator::DEFAULT_BASELINE.num_seconds();
 
-        let score = (duration / IDEAL_DURATION) as f64;
+        let score = duration as f64 / ideal_duration as f64;
 
         (score * 100.0).clamp(0.0, 100.0)
     }
@@ -123,6 +160,20 @@ impl From<sleep_cycles::Model> for SleepCycle {
             avg_hrv,
             score,
             synced: _,
+            skin_temp: _,
+            skin_temp_deviation: _,
+            resp_rate_min,
+            resp_rate_avg,
+            resp_rate_max,
+            wake_seconds,
+            light_seconds,
+            deep_seconds,
+            rem_seconds,
+            score_duration,
+            score_efficiency,
+            score_disturbances,
+            score_consistency,
+            rr_artifact_rate,
         } = value;
 
         macro_rules! clamp {
@@ -142,6 +193,20 @@ impl From<sleep_cycles::Model> for SleepCycle {
             max_hrv: clamp!(max_hrv, u16),
             avg_hrv: clamp!(avg_hrv, u16),
             score: score.unwrap_or_else(|| Self::sleep_score(start, end)),
+            resp_rate: RespiratoryRate::from_columns(resp_rate_min, resp_rate_avg, resp_rate_max),
+            stages: SleepStageTotals::from_columns(
+                wake_seconds,
+                light_seconds,
+                deep_seconds,
+                rem_seconds,
+            ),
+            score_breakdown: SleepScore::from_columns(
+                score_duration,
+                score_efficiency,
+                score_disturbances,
+                score_consistency,
+            ),
+            rr_artifact_rate,
         }
     }
 }
@@ -165,10 +230,9 @@ mod tests {
     }
 
     #[test]
-    fn sleep_score_4h_is_0() {
-        // 4h / 8h = 0.5 -> integer division = 0 -> score = 0
+    fn sleep_score_4h_is_50() {
         let score = SleepCycle::sleep_score(dt(22, 0), dt(22, 0) + TimeDelta::hours(4));
-        assert_eq!(score, 0.0);
+        assert_eq!(score, 50.0);
     }
 
     #[test]
@@ -190,6 +254,7 @@ mod tests {
             max_hrv: 80,
             avg_hrv: 55,
             score: 100.0,
+            ..Default::default()
         };
         assert_eq!(cycle.duration(), TimeDelta::hours(8));
     }
@@ -198,13 +263,26 @@ mod tests {
     fn clean_rr_flattens_samples() {
         let rr = vec![vec![800, 900], vec![1000], vec![]];
         let result = SleepCycle::clean_rr(rr);
-        assert_eq!(result, vec![800, 900, 1000]);
+        assert_eq!(result.rr, vec![800.0, 900.0, 1000.0]);
+        assert_eq!(result.artifacts, 0);
     }
 
     #[test]
     fn clean_rr_empty_input() {
         let result = SleepCycle::clean_rr(vec![]);
-        assert!(result.is_empty());
+        assert!(result.rr.is_empty());
+    }
+
+    #[test]
+    fn clean_rr_corrects_artifacts() {
+        let rr = vec![
+            vec![1000, 1000, 0],
+            vec![1000, 1600, 1000],
+            vec![1000, 1000],
+        ];
+        let result = SleepCycle::clean_rr(rr);
+        assert_eq!(result.rr, vec![1000.0; 7]);
+        assert_eq!(result.artifacts, 1);
     }
 
     #[test]
@@ -259,6 +337,7 @@ mod tests {
                 rr: vec![1000],
                 imu_data: None,
                 gravity: None,
+                sensor_data: None,
             })
             .collect();
         let cycle = SleepCycle::from_event(event, &history).unwrap();
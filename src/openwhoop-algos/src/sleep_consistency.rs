@@ -217,6 +217,7 @@ mod tests {
                     max_hrv: 80,
                     avg_hrv: 55,
                     score: 100.0,
+                    ..Default::default()
                 }
             })
             .collect();
@@ -254,6 +255,7 @@ mod tests {
             max_hrv: 80,
             avg_hrv: 55,
             score: 100.0,
+            ..Default::default()
         }];
 
         let analyzer = SleepConsistencyAnalyzer::new(records);
@@ -0,0 +1,283 @@
+use std::fmt;
+
+use chrono::{NaiveDateTime, TimeDelta};
+
+use crate::{SleepCycle, helpers::format_hm::FormatHM, helpers::time_math::mean};
+
+/// Recommended sleep for a night and what it is made of.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SleepNeed {
+    /// Personal need learned from recent nights
+    pub baseline: TimeDelta,
+    /// Shortfall carried over from the last nights
+    pub debt: TimeDelta,
+    /// Extra sleep needed after a strenuous day
+    pub strain: TimeDelta,
+    /// Sleep already taken in naps since the last night
+    pub naps: TimeDelta,
+    /// Share of time in bed spent asleep over recent nights
+    pub efficiency: f64,
+}
+
+impl SleepNeed {
+    pub fn total(&self) -> TimeDelta {
+        (self.baseline + self.debt + self.strain - self.naps).max(TimeDelta::zero())
+    }
+
+    /// Time in bed needed to sleep [`SleepNeed::total`] at the usual
+    /// efficiency.
+    pub fn time_in_bed(&self) -> TimeDelta {
+        TimeDelta::seconds((self.total().num_seconds() as f64 / self.efficiency) as i64)
+    }
+
+    /// When to go to bed to meet the need by `wake`.
+    pub fn bedtime(&self, wake: NaiveDateTime) -> NaiveDateTime {
+        wake - self.time_in_bed()
+    }
+}
+
+impl fmt::Display for SleepNeed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (baseline {}, debt +{}, strain +{}, naps -{})",
+            self.total().format_hm(),
+            self.baseline.format_hm(),
+            self.debt.format_hm(),
+            self.strain.format_hm(),
+            self.naps.format_hm()
+        )
+    }
+}
+
+pub struct SleepNeedCalculator;
+
+impl SleepNeedCalculator {
+    pub const DEFAULT_BASELINE: TimeDelta = TimeDelta::hours(8);
+    /// Previous nights used for the personal baseline.
+    pub const BASELINE_NIGHTS: usize = 14;
+    const MIN_BASELINE_NIGHTS: usize = 3;
+    const MIN_BASELINE: TimeDelta = TimeDelta::hours(7);
+    const MAX_BASELINE: TimeDelta = TimeDelta::hours(9);
+    /// Lowest sleep efficiency used for the bedtime, so a few restless
+    /// nights don't send the user to bed in the afternoon.
+    const MIN_EFFICIENCY: f64 = 0.7;
+
+    /// Share of each recent night's shortfall still owed, latest night first.
+    const DEBT_WEIGHTS: [f64; 3] = [0.5, 0.3, 0.2];
+    const MAX_DEBT: TimeDelta = TimeDelta::hours(2);
+
+    /// Day strain above which extra sleep is needed, and the extra sleep
+    /// at the top of the 0-21 scale.
+    const STRAIN_THRESHOLD: f64 = 10.0;
+    const MAX_STRAIN: f64 = 21.0;
+    const MAX_STRAIN_EXTRA: TimeDelta = TimeDelta::hours(1);
+
+    /// Personal baseline: mean time asleep over the previous nights, kept
+    /// within the recommended adult range.
+    pub fn baseline(previous: &[SleepCycle]) -> TimeDelta {
+        let recent = &previous[previous.len().saturating_sub(Self::BASELINE_NIGHTS)..];
+        if recent.len() < Self::MIN_BASELINE_NIGHTS {
+            return Self::DEFAULT_BASELINE;
+        }
+
+        let asleep = recent
+            .iter()
+            .map(|sleep| sleep.asleep().num_seconds() as f64)
+            .collect::<Vec<_>>();
+        TimeDelta::seconds(mean(&asleep) as i64).clamp(Self::MIN_BASELINE, Self::MAX_BASELINE)
+    }
+
+    /// Share of time in bed spent asleep over the previous staged nights,
+    /// 1.0 when none of them are staged.
+    pub fn efficiency(previous: &[SleepCycle]) -> f64 {
+        let recent = &previous[previous.len().saturating_sub(Self::BASELINE_NIGHTS)..];
+        let (asleep, in_bed) = recent
+            .iter()
+            .filter_map(|sleep| {
+                sleep
+                    .stages
+                    .map(|stages| (stages.asleep(), sleep.duration()))
+            })
+            .fold(
+                (TimeDelta::zero(), TimeDelta::zero()),
+                |(asleep, in_bed), night| (asleep + night.0, in_bed + night.1),
+            );
+        if in_bed <= TimeDelta::zero() {
+            return 1.0;
+        }
+
+        (asleep.num_seconds() as f64 / in_bed.num_seconds() as f64).clamp(Self::MIN_EFFICIENCY, 1.0)
+    }
+
+    /// Sleep need for the night after `previous` (oldest first), given the
+    /// strain of the day before it and the time spent napping.
+    pub fn calculate(previous: &[SleepCycle], strain: Option<f64>, naps: TimeDelta) -> SleepNeed {
+        let baseline = Self::baseline(previous);
+
+        let debt_seconds = previous
+            .iter()
+            .rev()
+            .zip(Self::DEBT_WEIGHTS)
+            .map(|(sleep, weight)| {
+                (baseline - sleep.asleep())
+                    .max(TimeDelta::zero())
+                    .num_seconds() as f64
+                    * weight
+            })
+            .sum::<f64>();
+        let debt = TimeDelta::seconds(debt_seconds as i64).min(Self::MAX_DEBT);
+
+        let strain = strain.map_or(TimeDelta::zero(), |strain| {
+            let excess = ((strain - Self::STRAIN_THRESHOLD)
+                / (Self::MAX_STRAIN - Self::STRAIN_THRESHOLD))
+                .clamp(0.0, 1.0);
+            TimeDelta::seconds((Self::MAX_STRAIN_EXTRA.num_seconds() as f64 * excess) as i64)
+        });
+
+        SleepNeed {
+            baseline,
+            debt,
+            strain,
+            naps: naps.max(TimeDelta::zero()),
+            efficiency: Self::efficiency(previous),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SleepStageTotals;
+    use chrono::NaiveDate;
+
+    fn sleep(day: u32, minutes: i64) -> SleepCycle {
+        let start = NaiveDate::from_ymd_opt(2025, 1, day)
+            .unwrap()
+            .and_hms_opt(23, 0, 0)
+            .unwrap();
+        let end = start + TimeDelta::minutes(minutes);
+        SleepCycle {
+            id: end.date(),
+            start,
+            end,
+            min_bpm: 50,
+            max_bpm: 70,
+            avg_bpm: 60,
+            min_hrv: 30,
+            max_hrv: 80,
+            avg_hrv: 55,
+            score: 0.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn baseline_follows_recent_nights_within_bounds() {
+        assert_eq!(
+            SleepNeedCalculator::baseline(&[sleep(1, 360), sleep(2, 360)]),
+            SleepNeedCalculator::DEFAULT_BASELINE
+        );
+        assert_eq!(
+            SleepNeedCalculator::baseline(&[sleep(1, 480), sleep(2, 480), sleep(3, 540)]),
+            TimeDelta::minutes(500)
+        );
+        assert_eq!(
+            SleepNeedCalculator::baseline(&[sleep(1, 300), sleep(2, 300), sleep(3, 300)]),
+            TimeDelta::hours(7)
+        );
+    }
+
+    #[test]
+    fn rested_night_needs_baseline() {
+        let previous = (1..=5).map(|day| sleep(day, 480)).collect::<Vec<_>>();
+        let need = SleepNeedCalculator::calculate(&previous, None, TimeDelta::zero());
+        assert_eq!(need.total(), TimeDelta::hours(8));
+        assert_eq!(need.debt, TimeDelta::zero());
+    }
+
+    #[test]
+    fn debt_weighs_recent_nights_most() {
+        let mut previous = (1..=5).map(|day| sleep(day, 480)).collect::<Vec<_>>();
+        previous.push(sleep(6, 480 - 60));
+        let need = SleepNeedCalculator::calculate(&previous, None, TimeDelta::zero());
+        // Baseline drops to 470 minutes, the last night was 50 minutes short
+        assert_eq!(need.baseline, TimeDelta::minutes(470));
+        assert_eq!(need.debt, TimeDelta::minutes(25));
+    }
+
+    #[test]
+    fn strain_adds_and_naps_subtract() {
+        let previous = (1..=5).map(|day| sleep(day, 480)).collect::<Vec<_>>();
+        let easy = SleepNeedCalculator::calculate(&previous, Some(8.0), TimeDelta::zero());
+        assert_eq!(easy.strain, TimeDelta::zero());
+
+        let hard = SleepNeedCalculator::calculate(&previous, Some(21.0), TimeDelta::minutes(30));
+        assert_eq!(hard.strain, TimeDelta::hours(1));
+        assert_eq!(hard.naps, TimeDelta::minutes(30));
+        assert_eq!(hard.total(), TimeDelta::minutes(510));
+
+        let wake = NaiveDate::from_ymd_opt(2025, 1, 7)
+            .unwrap()
+            .and_hms_opt(7, 0, 0)
+            .unwrap();
+        assert_eq!(
+            hard.bedtime(wake),
+            NaiveDate::from_ymd_opt(2025, 1, 6)
+                .unwrap()
+                .and_hms_opt(22, 30, 0)
+                .unwrap()
+        );
+        assert_eq!(
+            hard.to_string(),
+            "08:30 (baseline 08:00, debt +00:00, strain +01:00, naps -00:30)"
+        );
+    }
+
+    #[test]
+    fn bedtime_allows_for_time_awake_in_bed() {
+        // Ten hours in bed with two of them awake: 80% efficiency.
+        let previous = (1..=5)
+            .map(|day| SleepCycle {
+                stages: Some(SleepStageTotals {
+                    wake: TimeDelta::hours(2),
+                    light: TimeDelta::hours(8),
+                    ..Default::default()
+                }),
+                ..sleep(day, 600)
+            })
+            .collect::<Vec<_>>();
+        let need = SleepNeedCalculator::calculate(&previous, None, TimeDelta::zero());
+        assert_eq!(need.total(), TimeDelta::hours(8));
+        assert_eq!(need.efficiency, 0.8);
+        assert_eq!(need.time_in_bed(), TimeDelta::hours(10));
+
+        let wake = NaiveDate::from_ymd_opt(2025, 1, 7)
+            .unwrap()
+            .and_hms_opt(7, 0, 0)
+            .unwrap();
+        assert_eq!(
+            need.bedtime(wake),
+            NaiveDate::from_ymd_opt(2025, 1, 6)
+                .unwrap()
+                .and_hms_opt(21, 0, 0)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn efficiency_is_floored() {
+        let restless = (1..=3)
+            .map(|day| SleepCycle {
+                stages: Some(SleepStageTotals {
+                    wake: TimeDelta::hours(6),
+                    light: TimeDelta::hours(2),
+                    ..Default::default()
+                }),
+                ..sleep(day, 480)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(SleepNeedCalculator::efficiency(&restless), 0.7);
+        assert_eq!(SleepNeedCalculator::efficiency(&[sleep(1, 480)]), 1.0);
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt;
+
+use chrono::TimeDelta;
+
+use openwhoop_codec::WhoopError;
+
+use crate::{SleepConsistencyAnalyzer, SleepCycle, SleepEpoch, SleepStage};
+
+/// Components of a sleep performance score, each 0-100. Components that
+/// could not be computed (no hypnogram, too little history) are `None` and
+/// left out of the total.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SleepScore {
+    /// Time asleep against the night's sleep need
+    pub duration: f64,
+    /// Time asleep against time in bed
+    pub efficiency: Option<f64>,
+    /// Penalty for wake bouts after sleep onset
+    pub disturbances: Option<f64>,
+    /// `SleepConsistencyAnalyzer` score over recent nights
+    pub consistency: Option<f64>,
+}
+
+impl SleepScore {
+    const DURATION_WEIGHT: f64 = 0.5;
+    const EFFICIENCY_WEIGHT: f64 = 0.2;
+    const DISTURBANCES_WEIGHT: f64 = 0.15;
+    const CONSISTENCY_WEIGHT: f64 = 0.15;
+
+    /// Weighted mean of the available components.
+    pub fn total(&self) -> f64 {
+        let components = [
+            (Some(self.duration), Self::DURATION_WEIGHT),
+            (self.efficiency, Self::EFFICIENCY_WEIGHT),
+            (self.disturbances, Self::DISTURBANCES_WEIGHT),
+            (self.consistency, Self::CONSISTENCY_WEIGHT),
+        ];
+
+        let (sum, weights) = components
+            .into_iter()
+            .filter_map(|(value, weight)| value.map(|value| (value * weight, weight)))
+            .fold((0.0, 0.0), |(sum, weights), (value, weight)| {
+                (sum + value, weights + weight)
+            });
+
+        (sum / weights).clamp(0.0, 100.0)
+    }
+
+    /// Rebuild from the nullable `sleep_cycles` columns.
+    pub fn from_columns(
+        duration: Option<f64>,
+        efficiency: Option<f64>,
+        disturbances: Option<f64>,
+        consistency: Option<f64>,
+    ) -> Option<Self> {
+        Some(Self {
+            duration: duration?,
+            efficiency,
+            disturbances,
+            consistency,
+        })
+    }
+}
+
+impl fmt::Display for SleepScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let component = |value: Option<f64>| {
+            value.map_or_else(|| "-".to_owned(), |value| format!("{:.0}", value))
+        };
+        write!(
+            f,
+            "{:.0} (duration {:.0}, efficiency {}, disturbances {}, consistency {})",
+            self.total(),
+            self.duration,
+            component(self.efficiency),
+            component(self.disturbances),
+            component(self.consistency)
+        )
+    }
+}
+
+pub struct SleepScoreCalculator;
+
+impl SleepScoreCalculator {
+    /// Nights, including the scored one, used for consistency.
+    const CONSISTENCY_NIGHTS: usize = 7;
+    const MIN_CONSISTENCY_NIGHTS: usize = 3;
+    /// Points lost per wake bout after sleep onset.
+    const DISTURBANCE_PENALTY: f64 = 5.0;
+
+    /// Consistency of this sleep with the week before it, `None` until
+    /// there are enough nights to compare.
+    pub fn consistency(
+        previous: &[SleepCycle],
+        sleep: &SleepCycle,
+    ) -> Result<Option<f64>, WhoopError> {
+        let recent = &previous[previous.len().saturating_sub(Self::CONSISTENCY_NIGHTS - 1)..];
+        if recent.len() + 1 < Self::MIN_CONSISTENCY_NIGHTS {
+            return Ok(None);
+        }
+
+        let mut nights = recent.to_vec();
+        nights.push(*sleep);
+        let metrics = SleepConsistencyAnalyzer::new(nights).calculate_consistency_metrics()?;
+        Ok(Some(metrics.score.total_score))
+    }
+
+    pub fn calculate(
+        sleep: &SleepCycle,
+        epochs: &[SleepEpoch],
+        sleep_need: TimeDelta,
+        consistency: Option<f64>,
+    ) -> SleepScore {
+        let duration = percentage(sleep.asleep(), sleep_need);
+        let efficiency = sleep
+            .stages
+            .map(|stages| percentage(stages.asleep(), sleep.duration()));
+        let disturbances = (!epochs.is_empty())
+            .then(|| (100.0 - Self::DISTURBANCE_PENALTY * wake_bouts(epochs) as f64).max(0.0));
+
+        SleepScore {
+            duration,
+            efficiency,
+            disturbances,
+            consistency: consistency.map(|score| score.clamp(0.0, 100.0)),
+        }
+    }
+}
+
+fn percentage(value: TimeDelta, target: TimeDelta) -> f64 {
+    if target <= TimeDelta::zero() {
+        return 0.0;
+    }
+    (value.num_seconds() as f64 / target.num_seconds() as f64 * 100.0).clamp(0.0, 100.0)
+}
+
+/// Runs of wake epochs between sleep onset and final awakening.
+fn wake_bouts(epochs: &[SleepEpoch]) -> usize {
+    let asleep = |epoch: &SleepEpoch| epoch.stage != SleepStage::Wake;
+    let (Some(first), Some(last)) = (
+        epochs.iter().position(asleep),
+        epochs.iter().rposition(asleep),
+    ) else {
+        return 0;
+    };
+
+    epochs[first..=last]
+        .windows(2)
+        .filter(|w| w[0].stage != SleepStage::Wake && w[1].stage == SleepStage::Wake)
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SleepNeedCalculator, SleepStageTotals, SleepStager};
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    fn start() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(23, 0, 0)
+            .unwrap()
+    }
+
+    fn sleep(hours: i64) -> SleepCycle {
+        let start = start();
+        let end = start + TimeDelta::hours(hours);
+        SleepCycle {
+            id: end.date(),
+            start,
+            end,
+            min_bpm: 50,
+            max_bpm: 70,
+            avg_bpm: 60,
+            min_hrv: 30,
+            max_hrv: 80,
+            avg_hrv: 55,
+            score: 0.0,
+            ..Default::default()
+        }
+    }
+
+    fn epochs(stages: &[SleepStage]) -> Vec<SleepEpoch> {
+        stages
+            .iter()
+            .enumerate()
+            .map(|(i, &stage)| SleepEpoch {
+                start: start() + SleepStager::EPOCH * i as i32,
+                stage,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn duration_only_score_is_proportional() {
+        let score = SleepScoreCalculator::calculate(
+            &sleep(6),
+            &[],
+            SleepNeedCalculator::DEFAULT_BASELINE,
+            None,
+        );
+        assert_eq!(score.duration, 75.0);
+        assert_eq!(score.efficiency, None);
+        assert_eq!(score.total(), 75.0);
+    }
+
+    #[test]
+    fn combines_all_components() {
+        use SleepStage::*;
+        let hypnogram = epochs(&[Wake, Light, Light, Wake, Deep, Deep, Wake, Rem, Light, Wake]);
+        let mut night = sleep(8);
+        night.stages = Some(SleepStageTotals {
+            wake: TimeDelta::hours(1),
+            light: TimeDelta::hours(4),
+            deep: TimeDelta::hours(1),
+            rem: TimeDelta::hours(2),
+        });
+
+        let score =
+            SleepScoreCalculator::calculate(&night, &hypnogram, TimeDelta::hours(8), Some(80.0));
+        assert_eq!(score.duration, 87.5);
+        assert_eq!(score.efficiency, Some(87.5));
+        // Leading and trailing wake do not count as disturbances
+        assert_eq!(score.disturbances, Some(90.0));
+        assert_eq!(score.consistency, Some(80.0));
+
+        let expected = 87.5 * 0.5 + 87.5 * 0.2 + 90.0 * 0.15 + 80.0 * 0.15;
+        assert!((score.total() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn consistency_needs_a_few_nights() {
+        let night = sleep(8);
+        assert_eq!(
+            SleepScoreCalculator::consistency(&[sleep(8)], &night).unwrap(),
+            None
+        );
+
+        let score = SleepScoreCalculator::consistency(&[sleep(8), sleep(8)], &night)
+            .unwrap()
+            .unwrap();
+        assert!(score > 90.0, "{score}");
+    }
+
+    #[test]
+    fn display_marks_missing_components() {
+        let score = SleepScore {
+            duration: 90.0,
+            efficiency: None,
+            disturbances: Some(80.0),
+            consistency: None,
+        };
+        assert_eq!(
+            score.to_string(),
+            "88 (duration 90, efficiency -, disturbances 80, consistency -)"
+        );
+    }
+}
@@ -0,0 +1,549 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{NaiveDateTime, TimeDelta};
+use openwhoop_codec::{ParsedHistoryReading, WhoopError};
+
+use crate::{
+    HrvCalculator, RespiratoryRateCalculator, RrArtifactCorrector,
+    helpers::{
+        format_hm::FormatHM,
+        gravity::gravity_delta,
+        time_math::{mean, std_dev},
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SleepStage {
+    Wake,
+    Light,
+    Deep,
+    Rem,
+}
+
+impl SleepStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Wake => "wake",
+            Self::Light => "light",
+            Self::Deep => "deep",
+            Self::Rem => "rem",
+        }
+    }
+}
+
+impl fmt::Display for SleepStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SleepStage {
+    type Err = WhoopError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wake" => Ok(Self::Wake),
+            "light" => Ok(Self::Light),
+            "deep" => Ok(Self::Deep),
+            "rem" => Ok(Self::Rem),
+            _ => Err(WhoopError::InvalidData),
+        }
+    }
+}
+
+/// One scored 30-second epoch of a sleep.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SleepEpoch {
+    pub start: NaiveDateTime,
+    pub stage: SleepStage,
+}
+
+/// Time spent in each stage over one sleep.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SleepStageTotals {
+    pub wake: TimeDelta,
+    pub light: TimeDelta,
+    pub deep: TimeDelta,
+    pub rem: TimeDelta,
+}
+
+impl SleepStageTotals {
+    pub fn from_epochs(epochs: &[SleepEpoch]) -> Self {
+        epochs.iter().fold(Self::default(), |mut totals, epoch| {
+            *totals.stage_mut(epoch.stage) += SleepStager::EPOCH;
+            totals
+        })
+    }
+
+    pub fn get(&self, stage: SleepStage) -> TimeDelta {
+        match stage {
+            SleepStage::Wake => self.wake,
+            SleepStage::Light => self.light,
+            SleepStage::Deep => self.deep,
+            SleepStage::Rem => self.rem,
+        }
+    }
+
+    fn stage_mut(&mut self, stage: SleepStage) -> &mut TimeDelta {
+        match stage {
+            SleepStage::Wake => &mut self.wake,
+            SleepStage::Light => &mut self.light,
+            SleepStage::Deep => &mut self.deep,
+            SleepStage::Rem => &mut self.rem,
+        }
+    }
+
+    /// Rebuild from the nullable `sleep_cycles` columns (seconds).
+    pub fn from_columns(
+        wake: Option<i32>,
+        light: Option<i32>,
+        deep: Option<i32>,
+        rem: Option<i32>,
+    ) -> Option<Self> {
+        let seconds = |value: Option<i32>| value.map(|s| TimeDelta::seconds(i64::from(s)));
+        Some(Self {
+            wake: seconds(wake)?,
+            light: seconds(light)?,
+            deep: seconds(deep)?,
+            rem: seconds(rem)?,
+        })
+    }
+
+    /// Time asleep, i.e. everything but wake.
+    pub fn asleep(&self) -> TimeDelta {
+        self.light + self.deep + self.rem
+    }
+}
+
+impl fmt::Display for SleepStageTotals {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "wake {}, light {}, deep {}, rem {}",
+            self.wake.format_hm(),
+            self.light.format_hm(),
+            self.deep.format_hm(),
+            self.rem.format_hm()
+        )
+    }
+}
+
+/// Per-epoch features, before normalisation against the rest of the night.
+#[derive(Debug, Default, Clone, Copy)]
+struct EpochFeatures {
+    hr: f64,
+    hrv: Option<f64>,
+    movement: f64,
+    resp_variability: Option<f64>,
+}
+
+/// Rule-based sleep stager.
+///
+/// Each 30-second epoch is described by mean HR, RMSSD and breath-to-breath
+/// variability (from RSA in the RR intervals) over the surrounding epochs,
+/// and movement from the gravity vector. HR, HRV and breathing are compared
+/// to the same night, so the rules do not depend on absolute levels.
+pub struct SleepStager;
+
+impl SleepStager {
+    pub const EPOCH: TimeDelta = TimeDelta::seconds(30);
+    /// Epochs on each side used for HRV and breathing features.
+    const CONTEXT_EPOCHS: usize = 4;
+    /// Epochs on each side used when smoothing the hypnogram.
+    const SMOOTHING_EPOCHS: usize = 2;
+    /// Mean change of the gravity vector (g) between readings above which
+    /// an epoch counts as movement.
+    const MOVEMENT_THRESHOLD: f64 = 0.05;
+    /// REM is rare right after sleep onset.
+    const NO_REM_AFTER_ONSET: TimeDelta = TimeDelta::minutes(30);
+
+    /// Label each epoch of `[start, end)` that has readings.
+    pub fn stage(
+        history: &[ParsedHistoryReading],
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Vec<SleepEpoch> {
+        let mut epochs: Vec<(NaiveDateTime, Vec<&ParsedHistoryReading>)> = Vec::new();
+        for reading in history
+            .iter()
+            .filter(|reading| reading.time >= start && reading.time < end)
+        {
+            let index = (reading.time - start).num_seconds() / Self::EPOCH.num_seconds();
+            let epoch_start = start + Self::EPOCH * i32::try_from(index).unwrap_or(i32::MAX);
+            match epochs.last_mut() {
+                Some((last, readings)) if *last == epoch_start => readings.push(reading),
+                _ => epochs.push((epoch_start, vec![reading])),
+            }
+        }
+
+        if epochs.is_empty() {
+            return Vec::new();
+        }
+
+        let features = Self::features(&epochs);
+        let hr = Normalized::new(features.iter().map(|f| Some(f.hr)));
+        let hrv = Normalized::new(features.iter().map(|f| f.hrv));
+        let resp = Normalized::new(features.iter().map(|f| f.resp_variability));
+
+        let stages = features
+            .iter()
+            .enumerate()
+            .map(|(i, feature)| {
+                Self::classify(
+                    feature.movement,
+                    hr.z(i).unwrap_or_default(),
+                    hrv.z(i).unwrap_or_default(),
+                    resp.z(i).unwrap_or_default(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let moving = features
+            .iter()
+            .map(|f| f.movement > Self::MOVEMENT_THRESHOLD)
+            .collect::<Vec<_>>();
+        let stages = Self::smooth(&stages, &moving);
+
+        epochs
+            .iter()
+            .zip(stages)
+            .map(|((epoch_start, _), stage)| SleepEpoch {
+                start: *epoch_start,
+                stage: if stage == SleepStage::Rem
+                    && *epoch_start - start < Self::NO_REM_AFTER_ONSET
+                {
+                    SleepStage::Light
+                } else {
+                    stage
+                },
+            })
+            .collect()
+    }
+
+    fn features(epochs: &[(NaiveDateTime, Vec<&ParsedHistoryReading>)]) -> Vec<EpochFeatures> {
+        // Correct the night as a whole, then split the beats back by epoch.
+        let corrected = RrArtifactCorrector::from_readings(
+            epochs
+                .iter()
+                .flat_map(|(_, readings)| readings.iter().copied()),
+        )
+        .rr;
+        let mut remaining = corrected.as_slice();
+        let rr = epochs
+            .iter()
+            .map(|(_, readings)| {
+                let beats = readings
+                    .iter()
+                    .flat_map(|reading| reading.rr.iter())
+                    .filter(|&&rr| rr > 0)
+                    .count()
+                    .min(remaining.len());
+                let (epoch, rest) = remaining.split_at(beats);
+                remaining = rest;
+                epoch.to_vec()
+            })
+            .collect::<Vec<_>>();
+
+        (0..epochs.len())
+            .map(|i| {
+                let readings = &epochs[i].1;
+                let context_start = i.saturating_sub(Self::CONTEXT_EPOCHS);
+                let context_end = (i + Self::CONTEXT_EPOCHS + 1).min(epochs.len());
+                let context_rr = rr[context_start..context_end].concat();
+
+                EpochFeatures {
+                    hr: readings.iter().map(|r| f64::from(r.bpm)).sum::<f64>()
+                        / readings.len() as f64,
+                    hrv: HrvCalculator::rmssd(&context_rr),
+                    movement: movement(readings),
+                    resp_variability: coefficient_of_variation(
+                        &RespiratoryRateCalculator::breath_intervals(&context_rr),
+                    ),
+                }
+            })
+            .collect()
+    }
+
+    fn classify(movement: f64, hr: f64, hrv: f64, resp: f64) -> SleepStage {
+        if movement > Self::MOVEMENT_THRESHOLD || hr > 1.5 {
+            SleepStage::Wake
+        } else if hrv > 0.5 && hr < -0.3 && resp < 0.5 {
+            SleepStage::Deep
+        } else if resp > 0.5 && hr > 0.0 && hrv < 0.5 {
+            SleepStage::Rem
+        } else {
+            SleepStage::Light
+        }
+    }
+
+    /// Majority vote over neighbouring epochs; epochs with movement stay
+    /// wake.
+    fn smooth(stages: &[SleepStage], moving: &[bool]) -> Vec<SleepStage> {
+        (0..stages.len())
+            .map(|i| {
+                if moving[i] {
+                    return SleepStage::Wake;
+                }
+
+                let window = &stages[i.saturating_sub(Self::SMOOTHING_EPOCHS)
+                    ..(i + Self::SMOOTHING_EPOCHS + 1).min(stages.len())];
+                [
+                    SleepStage::Wake,
+                    SleepStage::Light,
+                    SleepStage::Deep,
+                    SleepStage::Rem,
+                ]
+                .into_iter()
+                .max_by_key(|stage| {
+                    let votes = window.iter().filter(|s| *s == stage).count();
+                    (votes, *stage == stages[i])
+                })
+                .unwrap_or(stages[i])
+            })
+            .collect()
+    }
+}
+
+/// Z-scores of one feature across a night.
+struct Normalized {
+    values: Vec<Option<f64>>,
+    mean: f64,
+    std_dev: f64,
+}
+
+impl Normalized {
+    fn new(values: impl Iterator<Item = Option<f64>>) -> Self {
+        let values = values.collect::<Vec<_>>();
+        let present = values.iter().flatten().copied().collect::<Vec<_>>();
+        let mean = mean(&present);
+        let std_dev = std_dev(&present);
+
+        Self {
+            values,
+            mean,
+            std_dev,
+        }
+    }
+
+    fn z(&self, i: usize) -> Option<f64> {
+        let value = self.values.get(i).copied().flatten()?;
+        (self.std_dev > f64::EPSILON).then(|| (value - self.mean) / self.std_dev)
+    }
+}
+
+fn coefficient_of_variation(values: &[f64]) -> Option<f64> {
+    if values.len() < 3 {
+        return None;
+    }
+    let mean = mean(values);
+    (mean > 0.0).then(|| std_dev(values) / mean)
+}
+
+/// Mean change of the gravity vector between consecutive readings.
+fn movement(readings: &[&ParsedHistoryReading]) -> f64 {
+    let changes = readings
+        .iter()
+        .filter_map(|reading| reading.gravity)
+        .collect::<Vec<_>>()
+        .windows(2)
+        .map(|w| f64::from(gravity_delta(w[0], w[1])))
+        .collect::<Vec<_>>();
+
+    if changes.is_empty() {
+        0.0
+    } else {
+        changes.iter().sum::<f64>() / changes.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SyntheticNight;
+    use chrono::NaiveDate;
+
+    fn start() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(23, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn stages_synthetic_night() {
+        let start = start();
+        let end = start + TimeDelta::hours(8);
+        let night = SyntheticNight::generate(start, end, 55);
+
+        let epochs = SleepStager::stage(&night.readings, start, end);
+        assert_eq!(epochs.len(), night.hypnogram.len());
+
+        let agreement = epochs
+            .iter()
+            .zip(&night.hypnogram)
+            .filter(|(a, b)| a.stage == b.stage)
+            .count() as f64
+            / epochs.len() as f64;
+        assert!(agreement > 0.9, "agreement {agreement}");
+
+        let totals = SleepStageTotals::from_epochs(&epochs);
+        for stage in [
+            SleepStage::Wake,
+            SleepStage::Light,
+            SleepStage::Deep,
+            SleepStage::Rem,
+        ] {
+            assert!(totals.get(stage) > TimeDelta::zero(), "no {stage}");
+        }
+    }
+
+    /// One reading per second over `minutes`, with beats of `rr_ms`
+    /// swinging by `rsa_ms` over a five-beat breath, and the gravity vector
+    /// jumping by `jolt` g between readings.
+    fn segment(
+        start: NaiveDateTime,
+        minutes: i64,
+        bpm: u8,
+        rr_ms: f64,
+        rsa_ms: f64,
+        jolt: f32,
+    ) -> Vec<ParsedHistoryReading> {
+        let seconds = minutes * 60;
+        let mut readings = (0..seconds)
+            .map(|second| ParsedHistoryReading {
+                time: start + TimeDelta::seconds(second),
+                bpm,
+                rr: Vec::new(),
+                imu_data: None,
+                gravity: Some([if second % 2 == 0 { 0.0 } else { jolt }, 0.0, 1.0]),
+                sensor_data: None,
+            })
+            .collect::<Vec<_>>();
+
+        let mut elapsed = 0.0;
+        for beat in 0.. {
+            let rr = rr_ms + rsa_ms * (std::f64::consts::TAU * f64::from(beat) / 5.0).sin();
+            elapsed += rr / 1000.0;
+            let Some(reading) = readings.get_mut(elapsed as usize) else {
+                break;
+            };
+            reading.rr.push(rr.round() as u16);
+        }
+        readings
+    }
+
+    fn stages_between(
+        epochs: &[SleepEpoch],
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> Vec<SleepStage> {
+        epochs
+            .iter()
+            .filter(|e| e.start >= from && e.start < to)
+            .map(|e| e.stage)
+            .collect()
+    }
+
+    #[test]
+    fn classify_rules() {
+        // Still, low HR, high HRV and regular breathing.
+        assert_eq!(SleepStager::classify(0.0, -1.0, 1.0, 0.0), SleepStage::Deep);
+        // Movement or a high HR wins over everything else.
+        assert_eq!(SleepStager::classify(0.2, -1.0, 1.0, 0.0), SleepStage::Wake);
+        assert_eq!(SleepStager::classify(0.0, 2.0, 0.0, 0.0), SleepStage::Wake);
+        // Irregular breathing with a raised HR and low HRV.
+        assert_eq!(SleepStager::classify(0.0, 0.5, -0.5, 1.0), SleepStage::Rem);
+        assert_eq!(SleepStager::classify(0.0, 0.0, 0.0, 0.0), SleepStage::Light);
+    }
+
+    #[test]
+    fn low_hr_with_strong_rsa_is_deep_and_movement_is_wake() {
+        let start = start();
+        let deep_start = start + TimeDelta::minutes(40);
+        let wake_start = start + TimeDelta::minutes(60);
+        let end = start + TimeDelta::minutes(70);
+
+        let mut history = segment(start, 40, 60, 1000.0, 20.0, 0.0);
+        history.extend(segment(deep_start, 20, 50, 1200.0, 80.0, 0.0));
+        history.extend(segment(wake_start, 10, 60, 1000.0, 20.0, 0.2));
+
+        let epochs = SleepStager::stage(&history, start, end);
+        assert_eq!(epochs.len(), 140);
+
+        // The HRV and breathing context blurs the first and last few epochs
+        // of the block.
+        let blur = SleepStager::EPOCH * 5;
+        assert!(
+            stages_between(&epochs, deep_start + blur, wake_start - blur)
+                .iter()
+                .all(|stage| *stage == SleepStage::Deep)
+        );
+        assert!(
+            stages_between(&epochs, wake_start, end)
+                .iter()
+                .all(|stage| *stage == SleepStage::Wake)
+        );
+        assert!(
+            !stages_between(&epochs, start, deep_start).contains(&SleepStage::Deep),
+            "deep before the low-HR block"
+        );
+    }
+
+    #[test]
+    fn no_rem_right_after_onset() {
+        let start = start();
+        let end = start + TimeDelta::hours(8);
+        let night = SyntheticNight::generate(start, end, 55);
+
+        let epochs = SleepStager::stage(&night.readings, start, end);
+        assert!(
+            epochs
+                .iter()
+                .filter(|e| e.start - start < TimeDelta::minutes(30))
+                .all(|e| e.stage != SleepStage::Rem)
+        );
+    }
+
+    #[test]
+    fn totals_from_epochs() {
+        let start = start();
+        let epochs = [
+            SleepStage::Light,
+            SleepStage::Light,
+            SleepStage::Deep,
+            SleepStage::Wake,
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, stage)| SleepEpoch {
+            start: start + SleepStager::EPOCH * i as i32,
+            stage,
+        })
+        .collect::<Vec<_>>();
+
+        let totals = SleepStageTotals::from_epochs(&epochs);
+        assert_eq!(totals.light, TimeDelta::minutes(1));
+        assert_eq!(totals.deep, TimeDelta::seconds(30));
+        assert_eq!(totals.asleep(), TimeDelta::seconds(90));
+        assert_eq!(totals.rem, TimeDelta::zero());
+    }
+
+    #[test]
+    fn stage_round_trips_through_str() {
+        for stage in [
+            SleepStage::Wake,
+            SleepStage::Light,
+            SleepStage::Deep,
+            SleepStage::Rem,
+        ] {
+            assert_eq!(stage.as_str().parse::<SleepStage>().unwrap(), stage);
+        }
+        assert!("nap".parse::<SleepStage>().is_err());
+    }
+
+    #[test]
+    fn empty_history_has_no_epochs() {
+        let start = start();
+        assert!(SleepStager::stage(&[], start, start + TimeDelta::hours(1)).is_empty());
+    }
+}
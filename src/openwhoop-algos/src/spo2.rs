@@ -0,0 +1,184 @@
+use chrono::NaiveDateTime;
+use openwhoop_codec::{ParsedHistoryReading, SensorData};
+
+use crate::helpers::time_math::{mean, std_dev};
+
+pub struct SpO2Calculator;
+
+#[derive(Debug, Clone, Copy)]
+pub struct SpO2Score {
+    pub time: NaiveDateTime,
+    pub spo2: f64,
+}
+
+impl SpO2Calculator {
+    /// Readings (~1 Hz) per ratio-of-ratios window.
+    pub const WINDOW: usize = 30;
+    /// Minimum readings in a window that pass the skin contact gate.
+    pub const MIN_VALID_READINGS: usize = 20;
+
+    /// Empirical calibration `SpO2 = A - B * R` for reflectance oximetry.
+    const CALIBRATION_A: f64 = 110.0;
+    const CALIBRATION_B: f64 = 25.0;
+    const MIN_SPO2: f64 = 70.0;
+    const MAX_SPO2: f64 = 100.0;
+
+    /// Estimate SpO2 at the last reading of `window`. A strap-reported
+    /// percentage (Gen5) wins; otherwise the ratio of ratios over the raw
+    /// red / IR ADC values in the window is used.
+    pub fn calculate_spo2(window: &[ParsedHistoryReading]) -> Option<SpO2Score> {
+        let last = window.last()?;
+        let time = last.time;
+
+        if let Some(pct) = last
+            .sensor_data
+            .as_ref()
+            .and_then(|sensor| sensor.spo2_pct)
+            .filter(|pct| (1..=100).contains(pct))
+        {
+            return Some(SpO2Score {
+                time,
+                spo2: f64::from(pct),
+            });
+        }
+
+        if window.len() < Self::WINDOW {
+            return None;
+        }
+
+        let (red, ir): (Vec<f64>, Vec<f64>) = window
+            .iter()
+            .filter_map(|reading| reading.sensor_data.as_ref())
+            .filter(|sensor| Self::passes_gate(sensor))
+            .map(|sensor| (f64::from(sensor.spo2_red), f64::from(sensor.spo2_ir)))
+            .unzip();
+
+        if red.len() < Self::MIN_VALID_READINGS {
+            return None;
+        }
+
+        let ratio = Self::perfusion(&red)? / Self::perfusion(&ir)?;
+        if !ratio.is_finite() {
+            return None;
+        }
+
+        let spo2 = Self::CALIBRATION_A - Self::CALIBRATION_B * ratio;
+        (Self::MIN_SPO2..=Self::MAX_SPO2)
+            .contains(&spo2)
+            .then_some(SpO2Score { time, spo2 })
+    }
+
+    /// Readings need skin contact and both raw channels. The strap's
+    /// `signal_quality` field isn't used: every V12 capture reports 0x0c02,
+    /// with and without skin contact, so what its bits mean is unknown.
+    fn passes_gate(sensor: &SensorData) -> bool {
+        sensor.skin_contact > 0 && sensor.spo2_red > 0 && sensor.spo2_ir > 0
+    }
+
+    /// AC/DC ratio: standard deviation over mean of the raw channel.
+    fn perfusion(values: &[f64]) -> Option<f64> {
+        let dc = mean(values);
+        let ac = std_dev(values);
+        (dc > 0.0 && ac > 0.0).then_some(ac / dc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeDelta};
+
+    fn sensor(red: u16, ir: u16, skin_contact: u8, spo2_pct: Option<u8>) -> SensorData {
+        SensorData {
+            ppg_green: 0,
+            ppg_red_ir: 0,
+            spo2_red: red,
+            spo2_ir: ir,
+            skin_temp_raw: 0,
+            ambient_light: 0,
+            led_drive_1: 0,
+            led_drive_2: 0,
+            resp_rate_raw: 0,
+            signal_quality: 0x0c02,
+            skin_contact,
+            accel_gravity: [0.0, 0.0, 1.0],
+            spo2_pct,
+        }
+    }
+
+    fn readings(f: impl Fn(i64) -> SensorData) -> Vec<ParsedHistoryReading> {
+        let base = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        (0..SpO2Calculator::WINDOW as i64)
+            .map(|i| ParsedHistoryReading {
+                time: base + TimeDelta::seconds(i),
+                bpm: 60,
+                rr: vec![],
+                imu_data: None,
+                gravity: None,
+                sensor_data: Some(f(i)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn prefers_strap_reported_percentage() {
+        let window = readings(|_| sensor(0, 0, 0, Some(96)));
+        let score = SpO2Calculator::calculate_spo2(&window[..1]).unwrap();
+        assert_eq!(score.spo2, 96.0);
+    }
+
+    #[test]
+    fn ratio_of_ratios_from_raw_channels() {
+        // Red pulses at half the relative amplitude of IR: R = 0.5 -> 97.5%
+        let window = readings(|i| {
+            let phase = if i % 2 == 0 { 1.0 } else { -1.0 };
+            sensor(
+                (500.0 + 5.0 * phase) as u16,
+                (600.0 + 12.0 * phase) as u16,
+                70,
+                None,
+            )
+        });
+        let score = SpO2Calculator::calculate_spo2(&window).unwrap();
+        assert!((score.spo2 - 97.5).abs() < 0.01, "{}", score.spo2);
+        assert_eq!(score.time, window.last().unwrap().time);
+    }
+
+    #[test]
+    fn readings_without_skin_contact_are_dropped() {
+        // Every third reading has lost skin contact with a much larger red
+        // swing; dropping them keeps R = 0.5
+        let reading = |i: i64, skin_contact| {
+            let phase = if i % 2 == 0 { 1.0 } else { -1.0 };
+            let red_swing = if skin_contact == 0 { 100.0 } else { 5.0 };
+            sensor(
+                (500.0 + red_swing * phase) as u16,
+                (600.0 + 12.0 * phase) as u16,
+                skin_contact,
+                None,
+            )
+        };
+        let window = readings(|i| reading(i, if i % 3 == 0 { 0 } else { 70 }));
+        let score = SpO2Calculator::calculate_spo2(&window).unwrap();
+        assert!((score.spo2 - 97.5).abs() < 0.01, "{}", score.spo2);
+
+        // Too few readings left for a window
+        let window = readings(|i| reading(i, if i % 2 == 0 { 0 } else { 70 }));
+        assert!(SpO2Calculator::calculate_spo2(&window).is_none());
+    }
+
+    #[test]
+    fn off_wrist_readings_are_rejected() {
+        let window = readings(|i| sensor(500 + (i % 2) as u16, 600 + (i % 2) as u16, 0, None));
+        assert!(SpO2Calculator::calculate_spo2(&window).is_none());
+    }
+
+    #[test]
+    fn flat_signal_yields_no_estimate() {
+        let window = readings(|_| sensor(500, 600, 70, None));
+        assert!(SpO2Calculator::calculate_spo2(&window).is_none());
+    }
+}
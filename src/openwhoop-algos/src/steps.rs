@@ -0,0 +1,370 @@
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDateTime, TimeDelta};
+use openwhoop_codec::ParsedHistoryReading;
+
+use crate::helpers::{gravity::gravity_delta, time_math::truncate_to_minute};
+
+/// Steps taken during one wall-clock minute. `estimated` minutes had no
+/// IMU data and were counted from changes in the gravity vector instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StepMinute {
+    pub minute: NaiveDateTime,
+    pub steps: u16,
+    pub estimated: bool,
+}
+
+/// Counts steps in wrist accelerometer data: the acceleration magnitude is
+/// band-passed, peaks above a threshold are steps, and only sustained bouts
+/// at a plausible cadence are kept so gestures and vibration are ignored.
+pub struct StepCounter;
+
+#[derive(Default)]
+struct MinuteCounts {
+    imu: bool,
+    steps: u32,
+    proxy_seconds: u32,
+}
+
+impl StepCounter {
+    /// Readings further apart than this start a new stream.
+    const MAX_GAP: TimeDelta = TimeDelta::seconds(2);
+    /// Moving mean removed as gravity, in samples (1 s at 100 Hz).
+    const BASELINE_SAMPLES: usize = 100;
+    /// Moving mean that smooths out sensor noise, in samples.
+    const SMOOTHING_SAMPLES: usize = 5;
+    /// Filtered acceleration (g) a peak must reach.
+    const PEAK_THRESHOLD: f64 = 0.08;
+    /// Peaks closer than this belong to the same step.
+    const REFRACTORY: TimeDelta = TimeDelta::milliseconds(100);
+    /// Longest pause between steps of one bout.
+    const MAX_STEP_INTERVAL: TimeDelta = TimeDelta::seconds(2);
+    /// Shortest median step interval of a bout (240 steps/min).
+    const MIN_STEP_INTERVAL: TimeDelta = TimeDelta::milliseconds(250);
+    const MIN_BOUT_STEPS: usize = 6;
+
+    /// Gravity change between readings (g) that counts as movement.
+    const GRAVITY_MOVEMENT: f32 = 0.05;
+    /// Consecutive moving seconds before gravity movement counts as walking.
+    const MIN_PROXY_SECONDS: usize = 10;
+    /// Steps credited per moving second, a typical walking cadence.
+    const PROXY_STEPS_PER_SECOND: f64 = 1.6;
+
+    /// Per-minute step counts for every minute with IMU or gravity data.
+    pub fn count(history: &[ParsedHistoryReading]) -> Vec<StepMinute> {
+        let mut minutes: BTreeMap<NaiveDateTime, MinuteCounts> = BTreeMap::new();
+        for reading in history {
+            let has_imu = reading.imu_data.as_ref().is_some_and(|imu| !imu.is_empty());
+            if has_imu || reading.gravity.is_some() {
+                minutes
+                    .entry(truncate_to_minute(reading.time))
+                    .or_default()
+                    .imu |= has_imu;
+            }
+        }
+
+        for stream in Self::imu_streams(history) {
+            for step in Self::detect_steps(stream) {
+                if let Some(counts) = minutes.get_mut(&truncate_to_minute(step)) {
+                    counts.steps += 1;
+                }
+            }
+        }
+
+        for second in Self::proxy_seconds(history) {
+            if let Some(counts) = minutes.get_mut(&truncate_to_minute(second)) {
+                counts.proxy_seconds += 1;
+            }
+        }
+
+        minutes
+            .into_iter()
+            .map(|(minute, counts)| {
+                let steps = if counts.imu {
+                    f64::from(counts.steps)
+                } else {
+                    f64::from(counts.proxy_seconds) * Self::PROXY_STEPS_PER_SECOND
+                };
+                StepMinute {
+                    minute,
+                    steps: steps.round().clamp(0.0, f64::from(u16::MAX)) as u16,
+                    estimated: !counts.imu,
+                }
+            })
+            .collect()
+    }
+
+    /// Runs of consecutive readings that carry IMU data.
+    fn imu_streams(history: &[ParsedHistoryReading]) -> Vec<&[ParsedHistoryReading]> {
+        let has_imu = |r: &ParsedHistoryReading| r.imu_data.as_ref().is_some_and(|i| !i.is_empty());
+
+        let mut streams = Vec::new();
+        let mut start = None;
+        for (i, reading) in history.iter().enumerate() {
+            let continues = i > 0
+                && has_imu(&history[i - 1])
+                && reading.time - history[i - 1].time <= Self::MAX_GAP;
+            match (has_imu(reading), start) {
+                (true, Some(s)) if !continues => {
+                    streams.push(&history[s..i]);
+                    start = Some(i);
+                }
+                (true, None) => start = Some(i),
+                (false, Some(s)) => {
+                    streams.push(&history[s..i]);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = start {
+            streams.push(&history[s..]);
+        }
+        streams
+    }
+
+    /// Step times in one IMU stream.
+    fn detect_steps(stream: &[ParsedHistoryReading]) -> Vec<NaiveDateTime> {
+        let mut times = Vec::new();
+        let mut magnitude = Vec::new();
+        for reading in stream {
+            let imu = reading.imu_data.as_deref().unwrap_or_default();
+            let interval = 1_000_000 / imu.len().max(1) as i64;
+            for (i, sample) in imu.iter().enumerate() {
+                times.push(reading.time + TimeDelta::microseconds(interval * i as i64));
+                magnitude.push(f64::from(sample.acceleration()));
+            }
+        }
+
+        let baseline = moving_mean(&magnitude, Self::BASELINE_SAMPLES);
+        let dynamic = magnitude
+            .iter()
+            .zip(&baseline)
+            .map(|(m, b)| m - b)
+            .collect::<Vec<_>>();
+        let filtered = moving_mean(&dynamic, Self::SMOOTHING_SAMPLES);
+
+        let mut peaks: Vec<NaiveDateTime> = Vec::new();
+        for i in 1..filtered.len().saturating_sub(1) {
+            let is_peak = filtered[i] >= Self::PEAK_THRESHOLD
+                && filtered[i] >= filtered[i - 1]
+                && filtered[i] > filtered[i + 1];
+            if is_peak
+                && peaks
+                    .last()
+                    .is_none_or(|&last| times[i] - last >= Self::REFRACTORY)
+            {
+                peaks.push(times[i]);
+            }
+        }
+
+        let mut steps = Vec::new();
+        let mut bout_start = 0;
+        for i in 1..=peaks.len() {
+            if i == peaks.len() || peaks[i] - peaks[i - 1] > Self::MAX_STEP_INTERVAL {
+                let bout = &peaks[bout_start..i];
+                if Self::is_walking(bout) {
+                    steps.extend_from_slice(bout);
+                }
+                bout_start = i;
+            }
+        }
+        steps
+    }
+
+    /// A bout counts when it is long enough and its cadence is humanly
+    /// possible.
+    fn is_walking(bout: &[NaiveDateTime]) -> bool {
+        if bout.len() < Self::MIN_BOUT_STEPS {
+            return false;
+        }
+
+        let mut intervals = bout.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+        intervals.sort();
+        intervals[intervals.len() / 2] >= Self::MIN_STEP_INTERVAL
+    }
+
+    /// Seconds without IMU data whose gravity vector kept changing for long
+    /// enough to be walking rather than a gesture.
+    fn proxy_seconds(history: &[ParsedHistoryReading]) -> Vec<NaiveDateTime> {
+        let mut seconds = Vec::new();
+        let mut run: Vec<NaiveDateTime> = Vec::new();
+        let mut flush = |run: &mut Vec<NaiveDateTime>| {
+            if run.len() >= Self::MIN_PROXY_SECONDS {
+                seconds.append(run);
+            }
+            run.clear();
+        };
+
+        for pair in history.windows(2) {
+            let (previous, reading) = (&pair[0], &pair[1]);
+            let moving = match (previous.gravity, reading.gravity) {
+                (Some(a), Some(b))
+                    if reading.imu_data.as_ref().is_none_or(|imu| imu.is_empty())
+                        && reading.time - previous.time <= Self::MAX_GAP =>
+                {
+                    gravity_delta(a, b) > Self::GRAVITY_MOVEMENT
+                }
+                _ => false,
+            };
+
+            if moving {
+                run.push(reading.time);
+            } else {
+                flush(&mut run);
+            }
+        }
+        flush(&mut run);
+
+        seconds
+    }
+}
+
+/// Centered moving mean over `width` samples, narrower at the edges.
+fn moving_mean(values: &[f64], width: usize) -> Vec<f64> {
+    let mut prefix = Vec::with_capacity(values.len() + 1);
+    prefix.push(0.0);
+    for value in values {
+        prefix.push(prefix[prefix.len() - 1] + value);
+    }
+
+    let half = width / 2;
+    (0..values.len())
+        .map(|i| {
+            let start = i.saturating_sub(half);
+            let end = (i + width - half).min(values.len());
+            (prefix[end] - prefix[start]) / (end - start) as f64
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use openwhoop_codec::ImuSample;
+
+    fn base() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    /// One reading per second with 100 IMU samples; `segments` are
+    /// (seconds, cadence per minute, amplitude in g).
+    fn imu_history(segments: &[(i64, f64, f32)]) -> Vec<ParsedHistoryReading> {
+        let mut readings = Vec::new();
+        let mut second = 0;
+        for &(seconds, cadence, amplitude) in segments {
+            for _ in 0..seconds {
+                let imu = (0..100)
+                    .map(|i| {
+                        let t = second as f64 + f64::from(i) / 100.0;
+                        let phase = (2.0 * std::f64::consts::PI * cadence / 60.0 * t).sin();
+                        ImuSample {
+                            acc_x_g: 0.0,
+                            acc_y_g: 0.0,
+                            acc_z_g: 1.0 + amplitude * phase as f32,
+                            gyr_x_dps: 0.0,
+                            gyr_y_dps: 0.0,
+                            gyr_z_dps: 0.0,
+                        }
+                    })
+                    .collect();
+                readings.push(ParsedHistoryReading {
+                    time: base() + TimeDelta::seconds(second),
+                    bpm: 90,
+                    rr: Vec::new(),
+                    imu_data: Some(imu),
+                    gravity: None,
+                    sensor_data: None,
+                });
+                second += 1;
+            }
+        }
+        readings
+    }
+
+    /// One reading per second with only a gravity vector; `segments` are
+    /// (seconds, moving).
+    fn gravity_history(segments: &[(i64, bool)]) -> Vec<ParsedHistoryReading> {
+        let mut readings = Vec::new();
+        let mut second = 0;
+        for &(seconds, moving) in segments {
+            for _ in 0..seconds {
+                let tilt = if moving && second % 2 == 0 { 0.3 } else { 0.0 };
+                readings.push(ParsedHistoryReading {
+                    time: base() + TimeDelta::seconds(second),
+                    bpm: 90,
+                    rr: Vec::new(),
+                    imu_data: None,
+                    gravity: Some([tilt, 0.0, 1.0]),
+                    sensor_data: None,
+                });
+                second += 1;
+            }
+        }
+        readings
+    }
+
+    fn total(minutes: &[StepMinute]) -> u32 {
+        minutes.iter().map(|m| u32::from(m.steps)).sum()
+    }
+
+    #[test]
+    fn counts_walking_steps_per_minute() {
+        let minutes = StepCounter::count(&imu_history(&[(180, 110.0, 0.3)]));
+        assert_eq!(minutes.len(), 3);
+        assert!(minutes.iter().all(|m| !m.estimated));
+        for minute in &minutes {
+            assert!((108..=111).contains(&minute.steps), "{minute:?}");
+        }
+        assert_eq!(minutes[1].minute, base() + TimeDelta::minutes(1));
+    }
+
+    #[test]
+    fn ignores_stillness_gestures_and_vibration() {
+        assert_eq!(
+            total(&StepCounter::count(&imu_history(&[(120, 0.0, 0.0)]))),
+            0
+        );
+        // Three arm swings are not a walk
+        assert_eq!(
+            total(&StepCounter::count(&imu_history(&[
+                (60, 0.0, 0.0),
+                (2, 90.0, 0.4),
+                (60, 0.0, 0.0)
+            ]))),
+            0
+        );
+        // 6 Hz vibration, e.g. from a vehicle, is too fast to be steps
+        assert_eq!(
+            total(&StepCounter::count(&imu_history(&[(120, 360.0, 0.3)]))),
+            0
+        );
+    }
+
+    #[test]
+    fn falls_back_to_gravity_proxy() {
+        let minutes = StepCounter::count(&gravity_history(&[(60, false), (60, true), (5, true)]));
+        assert_eq!(minutes.len(), 3);
+        assert!(minutes.iter().all(|m| m.estimated));
+        assert_eq!(minutes[0].steps, 0);
+        // 60 moving seconds at the proxy cadence
+        assert_eq!(minutes[1].steps, 96);
+    }
+
+    #[test]
+    fn ignores_short_gravity_movement() {
+        let minutes = StepCounter::count(&gravity_history(&[(30, false), (5, true), (30, false)]));
+        assert_eq!(total(&minutes), 0);
+    }
+
+    #[test]
+    fn no_minutes_without_motion_data() {
+        let mut readings = gravity_history(&[(120, true)]);
+        readings.iter_mut().for_each(|r| r.gravity = None);
+        assert!(StepCounter::count(&readings).is_empty());
+    }
+}
@@ -1,5 +1,7 @@
 use openwhoop_codec::ParsedHistoryReading;
 
+use crate::{HeartRateZones, ZoneBasis};
+
 pub struct StrainCalculator {
     pub max_hr: u8,
     pub resting_hr: u8,
@@ -36,58 +38,10 @@ impl StrainCalculator {
             return None;
         }
 
-        let sample_duration_min = Self::sample_duration_minutes(hr);
-        let hr_reserve = f64::from(self.max_hr) - f64::from(self.resting_hr);
-        let trimp = Self::edwards_trimp(hr, self.resting_hr, hr_reserve, sample_duration_min);
-
-        Some(StrainScore(Self::trimp_to_strain(trimp)))
-    }
+        let zones = HeartRateZones::new(self.max_hr, self.resting_hr, ZoneBasis::HeartRateReserve)
+            .breakdown(hr)?;
 
-    /// Estimate the sample interval in minutes from the first two readings.
-    /// Falls back to 1/60 min (1 second) if only one reading or timestamps match.
-    fn sample_duration_minutes(hr: &[ParsedHistoryReading]) -> f64 {
-        if hr.len() < 2 {
-            return 1.0 / 60.0;
-        }
-        let dt = (hr[1].time - hr[0].time).num_milliseconds().unsigned_abs();
-        if dt == 0 {
-            1.0 / 60.0
-        } else {
-            dt as f64 / 60_000.0
-        }
-    }
-
-    /// Returns the Edwards zone weight (1-5) based on %HRR, or 0 if below zone 1.
-    /// Zones use Heart Rate Reserve: %HRR = (bpm - resting_hr) / hr_reserve x 100
-    fn zone_weight(bpm: u8, resting_hr: u8, hr_reserve: f64) -> u8 {
-        let pct = (f64::from(bpm) - f64::from(resting_hr)) / hr_reserve * 100.0;
-        if pct >= 90.0 {
-            5
-        } else if pct >= 80.0 {
-            4
-        } else if pct >= 70.0 {
-            3
-        } else if pct >= 60.0 {
-            2
-        } else if pct >= 50.0 {
-            1
-        } else {
-            0
-        }
-    }
-
-    /// Edwards' TRIMP with HRR zones: sum(duration_min x zone_weight)
-    fn edwards_trimp(
-        hr: &[ParsedHistoryReading],
-        resting_hr: u8,
-        hr_reserve: f64,
-        sample_duration_min: f64,
-    ) -> f64 {
-        hr.iter()
-            .map(|r| {
-                sample_duration_min * f64::from(Self::zone_weight(r.bpm, resting_hr, hr_reserve))
-            })
-            .sum()
+        Some(StrainScore(Self::trimp_to_strain(zones.trimp())))
     }
 
     /// Map raw TRIMP to 0-21 using calibrated log transform.
@@ -125,6 +79,7 @@ mod tests {
                 rr: vec![],
                 imu_data: None,
                 gravity: None,
+                sensor_data: None,
             })
             .collect()
     }
@@ -142,6 +97,7 @@ mod tests {
                 rr: vec![],
                 imu_data: None,
                 gravity: None,
+                sensor_data: None,
             })
             .collect()
     }
@@ -215,58 +171,24 @@ mod tests {
     fn zone_weights_with_hrr() {
         // max_hr=200, resting_hr=50 -> HR reserve = 150
         // Zone thresholds in bpm: 50%->125, 60%->140, 70%->155, 80%->170, 90%->185
-        let resting_hr: u8 = 50;
-        let hr_reserve: f64 = 150.0;
+        let zones = HeartRateZones::new(200, 50, ZoneBasis::HeartRateReserve);
 
         // Below zone 1: bpm < 125 (< 50% HRR)
-        assert_eq!(
-            StrainCalculator::zone_weight(120, resting_hr, hr_reserve),
-            0
-        );
+        assert_eq!(zones.zone(120), 0);
         // Zone 1: 50-60% HRR -> bpm 125-139
-        assert_eq!(
-            StrainCalculator::zone_weight(125, resting_hr, hr_reserve),
-            1
-        );
-        assert_eq!(
-            StrainCalculator::zone_weight(139, resting_hr, hr_reserve),
-            1
-        );
+        assert_eq!(zones.zone(125), 1);
+        assert_eq!(zones.zone(139), 1);
         // Zone 2: 60-70% HRR -> bpm 140-154
-        assert_eq!(
-            StrainCalculator::zone_weight(140, resting_hr, hr_reserve),
-            2
-        );
-        assert_eq!(
-            StrainCalculator::zone_weight(154, resting_hr, hr_reserve),
-            2
-        );
+        assert_eq!(zones.zone(140), 2);
+        assert_eq!(zones.zone(154), 2);
         // Zone 3: 70-80% HRR -> bpm 155-169
-        assert_eq!(
-            StrainCalculator::zone_weight(155, resting_hr, hr_reserve),
-            3
-        );
-        assert_eq!(
-            StrainCalculator::zone_weight(169, resting_hr, hr_reserve),
-            3
-        );
+        assert_eq!(zones.zone(155), 3);
+        assert_eq!(zones.zone(169), 3);
         // Zone 4: 80-90% HRR -> bpm 170-184
-        assert_eq!(
-            StrainCalculator::zone_weight(170, resting_hr, hr_reserve),
-            4
-        );
-        assert_eq!(
-            StrainCalculator::zone_weight(184, resting_hr, hr_reserve),
-            4
-        );
+        assert_eq!(zones.zone(170), 4);
+        assert_eq!(zones.zone(184), 4);
         // Zone 5: 90-100% HRR -> bpm 185-200
-        assert_eq!(
-            StrainCalculator::zone_weight(185, resting_hr, hr_reserve),
-            5
-        );
-        assert_eq!(
-            StrainCalculator::zone_weight(200, resting_hr, hr_reserve),
-            5
-        );
+        assert_eq!(zones.zone(185), 5);
+        assert_eq!(zones.zone(200), 5);
     }
 }
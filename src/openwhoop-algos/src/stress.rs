@@ -4,12 +4,17 @@ use std::collections::BTreeMap;
 
 use openwhoop_codec::WhoopError;
 
+use crate::RrArtifactCorrector;
+
 pub struct StressCalculator;
 
 #[derive(Debug, Clone, Copy)]
 pub struct StressScore {
     pub time: NaiveDateTime,
     pub score: f64,
+    /// Share of RR intervals corrected as artifacts, in percent. `None`
+    /// when the score falls back to intervals derived from heart rate.
+    pub artifact_rate: Option<f64>,
 }
 
 impl StressCalculator {
@@ -32,19 +37,27 @@ impl StressCalculator {
         let time = hr.last()?.time;
 
         // Prefer real RR intervals from the device
-        let real_rr: Vec<u16> = hr.iter().flat_map(|r| r.rr.iter().copied()).collect();
+        let real_rr = RrArtifactCorrector::from_readings(hr);
 
-        let rr = if real_rr.len() >= min_reading_period {
-            real_rr
+        let (rr, artifact_rate) = if real_rr.rr.len() >= min_reading_period {
+            let rate = real_rr.artifact_rate();
+            let rr = real_rr.rr.into_iter().map(|rr| rr.round() as u16).collect();
+            (rr, Some(rate))
         } else {
             // Fall back to BPM-derived RR
-            hr.iter()
+            let rr = hr
+                .iter()
                 .map(|r| (60.0 / f64::from(r.bpm) * 1000.0).round() as u16)
-                .collect()
+                .collect();
+            (rr, None)
         };
 
         let score = StressCalcParams::new(rr).ok()?.stress_score();
-        Some(StressScore { time, score })
+        Some(StressScore {
+            time,
+            score,
+            artifact_rate,
+        })
     }
 }
 
@@ -183,6 +196,7 @@ mod tests {
                 rr: vec![],
                 imu_data: None,
                 gravity: None,
+                sensor_data: None,
             })
             .collect();
         assert!(StressCalculator::calculate_stress(&readings).is_none());
@@ -204,6 +218,7 @@ mod tests {
                 rr: vec![],
                 imu_data: None,
                 gravity: None,
+                sensor_data: None,
             })
             .collect();
         let result = StressCalculator::calculate_stress(&readings);
@@ -0,0 +1,358 @@
+use std::{f64::consts::TAU, fmt, str::FromStr};
+
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+use openwhoop_codec::{ParsedHistoryReading, SensorData, WhoopError};
+
+use crate::{NightlyMetrics, SleepEpoch, SleepStage, SleepStager};
+
+/// Deterministic synthetic night for offline testing: 1 Hz readings whose
+/// HR, RR (with respiratory sinus arrhythmia) and gravity follow a known
+/// hypnogram.
+pub struct SyntheticNight {
+    pub readings: Vec<ParsedHistoryReading>,
+    pub hypnogram: Vec<SleepEpoch>,
+}
+
+/// Signal shape of one stage.
+struct StageProfile {
+    /// Offset from the sleeping heart rate
+    hr_offset: f64,
+    /// Peak RR modulation by breathing (ms)
+    rsa_amplitude: f64,
+    breaths_per_minute: f64,
+    /// Relative breath-to-breath jitter of the breathing rate
+    breath_jitter: f64,
+    /// Gravity vector jitter (g)
+    movement: f64,
+}
+
+impl StageProfile {
+    fn of(stage: SleepStage) -> Self {
+        match stage {
+            SleepStage::Wake => Self {
+                hr_offset: 14.0,
+                rsa_amplitude: 15.0,
+                breaths_per_minute: 16.0,
+                breath_jitter: 0.35,
+                movement: 0.15,
+            },
+            SleepStage::Light => Self {
+                hr_offset: 4.0,
+                rsa_amplitude: 35.0,
+                breaths_per_minute: 14.0,
+                breath_jitter: 0.08,
+                movement: 0.002,
+            },
+            SleepStage::Deep => Self {
+                hr_offset: -3.0,
+                rsa_amplitude: 60.0,
+                breaths_per_minute: 13.0,
+                breath_jitter: 0.02,
+                movement: 0.001,
+            },
+            SleepStage::Rem => Self {
+                hr_offset: 8.0,
+                rsa_amplitude: 20.0,
+                breaths_per_minute: 17.0,
+                breath_jitter: 0.35,
+                movement: 0.002,
+            },
+        }
+    }
+}
+
+impl SyntheticNight {
+    const CYCLE_MINUTES: i64 = 90;
+
+    pub fn generate(start: NaiveDateTime, end: NaiveDateTime, sleep_hr: u8) -> Self {
+        let hypnogram = Self::hypnogram(start, end);
+        let mut noise = Noise(0x5eed ^ start.and_utc().timestamp().unsigned_abs());
+
+        let mut readings = Vec::new();
+        let mut beat_ms = 0.0;
+        let mut breath_phase: f64 = 0.0;
+        let mut breath_rate_scale = 1.0;
+        let mut gravity = [0.0f32, 0.0, 1.0];
+
+        for (second, epoch) in (0i64..).zip(hypnogram.iter().flat_map(|epoch| {
+            std::iter::repeat_n(epoch, SleepStager::EPOCH.num_seconds() as usize)
+        })) {
+            let profile = StageProfile::of(epoch.stage);
+            let second_end = (second + 1) as f64 * 1000.0;
+            let base_rr = 60_000.0 / (f64::from(sleep_hr) + profile.hr_offset);
+
+            let mut rr = Vec::new();
+            while beat_ms < second_end {
+                let interval = base_rr + profile.rsa_amplitude * breath_phase.sin();
+                beat_ms += interval;
+                rr.push(interval.round() as u16);
+
+                let previous_phase = breath_phase;
+                breath_phase +=
+                    TAU * profile.breaths_per_minute * breath_rate_scale / 60.0 * interval / 1000.0;
+                if (breath_phase / TAU).floor() > (previous_phase / TAU).floor() {
+                    breath_rate_scale = 1.0 + profile.breath_jitter * noise.signed();
+                }
+            }
+
+            for axis in &mut gravity {
+                *axis = (*axis + (profile.movement * noise.signed()) as f32).clamp(-1.0, 1.0);
+            }
+            if profile.movement < 0.01 {
+                gravity = [0.0, 0.0, 1.0];
+            }
+
+            let bpm = if rr.is_empty() {
+                f64::from(sleep_hr) + profile.hr_offset
+            } else {
+                60_000.0 / (rr.iter().map(|&rr| f64::from(rr)).sum::<f64>() / rr.len() as f64)
+            };
+
+            readings.push(ParsedHistoryReading {
+                time: start + TimeDelta::seconds(second),
+                bpm: bpm.round().clamp(30.0, 220.0) as u8,
+                rr,
+                imu_data: None,
+                gravity: Some(gravity),
+                sensor_data: Some(SensorData {
+                    ppg_green: 0,
+                    ppg_red_ir: 0,
+                    spo2_red: 0,
+                    spo2_ir: 0,
+                    skin_temp_raw: 0,
+                    ambient_light: 0,
+                    led_drive_1: 0,
+                    led_drive_2: 0,
+                    resp_rate_raw: 0,
+                    signal_quality: 0,
+                    skin_contact: 1,
+                    accel_gravity: gravity,
+                    spo2_pct: None,
+                }),
+            });
+        }
+
+        Self {
+            readings,
+            hypnogram,
+        }
+    }
+
+    /// 90-minute cycles of light, deep, light and REM. Deep sleep shortens
+    /// and REM lengthens through the night; each cycle after the first
+    /// starts with a brief awakening.
+    fn hypnogram(start: NaiveDateTime, end: NaiveDateTime) -> Vec<SleepEpoch> {
+        let epochs_per_minute = 60 / SleepStager::EPOCH.num_seconds();
+        let total = (end - start).num_seconds() / SleepStager::EPOCH.num_seconds();
+
+        let mut stages = Vec::new();
+        for cycle in 0.. {
+            let wake = if cycle == 0 { 0 } else { 2 };
+            let deep = (30 - 8 * cycle).max(5);
+            let rem = (10 + 8 * cycle).min(35);
+            let light = Self::CYCLE_MINUTES - wake - deep - rem;
+
+            for (stage, minutes) in [
+                (SleepStage::Wake, wake),
+                (SleepStage::Light, light / 2),
+                (SleepStage::Deep, deep),
+                (SleepStage::Light, light - light / 2),
+                (SleepStage::Rem, rem),
+            ] {
+                stages.extend(std::iter::repeat_n(
+                    stage,
+                    (minutes * epochs_per_minute) as usize,
+                ));
+            }
+
+            if stages.len() as i64 >= total {
+                break;
+            }
+        }
+
+        stages
+            .into_iter()
+            .take(usize::try_from(total).unwrap_or_default())
+            .enumerate()
+            .map(|(i, stage)| SleepEpoch {
+                start: start + SleepStager::EPOCH * i32::try_from(i).unwrap_or(i32::MAX),
+                stage,
+            })
+            .collect()
+    }
+}
+
+/// Synthetic movement for tests: vertical acceleration oscillating at
+/// `cadence` per minute with `amplitude` g, like steps, and a constant
+/// `rotation` in deg/s.
+#[cfg(test)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SyntheticMotion {
+    pub seconds: i64,
+    pub cadence: f64,
+    pub amplitude: f32,
+    pub rotation: f32,
+    pub bpm: u8,
+}
+
+#[cfg(test)]
+impl SyntheticMotion {
+    /// 1 Hz readings with 100 IMU samples each for `segments` back to back
+    /// from `start`.
+    pub fn readings(start: NaiveDateTime, segments: &[Self]) -> Vec<ParsedHistoryReading> {
+        let mut readings = Vec::new();
+        let mut second = 0;
+        for segment in segments {
+            for _ in 0..segment.seconds {
+                let imu = (0..100)
+                    .map(|i| {
+                        let t = second as f64 + f64::from(i) / 100.0;
+                        let phase = (TAU * segment.cadence / 60.0 * t).sin();
+                        openwhoop_codec::ImuSample {
+                            acc_x_g: 0.0,
+                            acc_y_g: 0.0,
+                            acc_z_g: 1.0 + segment.amplitude * phase as f32,
+                            gyr_x_dps: segment.rotation,
+                            gyr_y_dps: 0.0,
+                            gyr_z_dps: 0.0,
+                        }
+                    })
+                    .collect();
+                readings.push(ParsedHistoryReading {
+                    time: start + TimeDelta::seconds(second),
+                    bpm: segment.bpm,
+                    rr: Vec::new(),
+                    imu_data: Some(imu),
+                    gravity: None,
+                    sensor_data: None,
+                });
+                second += 1;
+            }
+        }
+        readings
+    }
+}
+
+/// How the nightly vitals of generated data develop over the days: steady,
+/// or steady until the last [`SyntheticScenario::AFFECTED_NIGHTS`] nights
+/// turn into an illness (heart, temperature, breathing and SpO2 all off) or
+/// overreaching (only heart rate and HRV off).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyntheticScenario {
+    #[default]
+    Healthy,
+    Illness,
+    Overreaching,
+}
+
+/// Nightly averages of one generated night.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SyntheticVitals {
+    pub sleep_hr: u8,
+    pub hrv: u16,
+    pub skin_temp: f64,
+    pub resp_rate: f64,
+    pub spo2: f64,
+}
+
+impl SyntheticVitals {
+    pub fn metrics(&self, date: NaiveDate) -> NightlyMetrics {
+        NightlyMetrics {
+            date,
+            rhr: Some(f64::from(self.sleep_hr)),
+            hrv: Some(f64::from(self.hrv)),
+            skin_temp: Some(self.skin_temp),
+            resp_rate: Some(self.resp_rate),
+            spo2: Some(self.spo2),
+        }
+    }
+}
+
+impl SyntheticScenario {
+    pub const AFFECTED_NIGHTS: usize = 3;
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Healthy => "healthy",
+            Self::Illness => "illness",
+            Self::Overreaching => "overreaching",
+        }
+    }
+
+    /// Vitals of night `night` (0-based) of `nights`, with a little
+    /// reproducible night-to-night noise.
+    pub fn vitals(self, night: usize, nights: usize, sleep_hr: u8) -> SyntheticVitals {
+        let mut noise = Noise(0x5eed ^ (night as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        let affected = night + Self::AFFECTED_NIGHTS >= nights;
+        // Heart rate offset, HRV factor, skin temp, respiratory rate and
+        // SpO2 offsets
+        let (hr, hrv, skin_temp, resp_rate, spo2) = match (self, affected) {
+            (Self::Illness, true) => (6.0, 0.7, 0.8, 2.5, -2.5),
+            (Self::Overreaching, true) => (5.0, 0.7, 0.0, 0.0, 0.0),
+            _ => (0.0, 1.0, 0.0, 0.0, 0.0),
+        };
+
+        SyntheticVitals {
+            sleep_hr: (f64::from(sleep_hr) + hr + noise.signed())
+                .round()
+                .clamp(30.0, 220.0) as u8,
+            hrv: ((64.0 + 4.0 * noise.signed()) * hrv).round() as u16,
+            skin_temp: 33.5 + skin_temp + 0.1 * noise.signed(),
+            resp_rate: 14.0 + resp_rate + 0.3 * noise.signed(),
+            spo2: 96.5 + spo2 + 0.5 * noise.signed(),
+        }
+    }
+}
+
+impl fmt::Display for SyntheticScenario {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for SyntheticScenario {
+    type Err = WhoopError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "healthy" => Ok(Self::Healthy),
+            "illness" | "ill" => Ok(Self::Illness),
+            "overreaching" => Ok(Self::Overreaching),
+            _ => Err(WhoopError::InvalidData),
+        }
+    }
+}
+
+/// Small xorshift generator so synthetic data is reproducible without
+/// pulling in a random number crate.
+struct Noise(u64);
+
+impl Noise {
+    /// Uniform value in `[-1, 1]`.
+    fn signed(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn generates_one_reading_per_second() {
+        let start = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(23, 0, 0)
+            .unwrap();
+        let night = SyntheticNight::generate(start, start + TimeDelta::hours(2), 55);
+
+        assert_eq!(night.readings.len(), 2 * 60 * 60);
+        assert_eq!(night.hypnogram.len(), 2 * 60 * 2);
+        assert_eq!(night.hypnogram[0].stage, SleepStage::Light);
+        assert!(night.readings.iter().all(|r| (40..=80).contains(&r.bpm)));
+    }
+}
@@ -0,0 +1,306 @@
+use std::{collections::BTreeMap, fmt};
+
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime, TimeDelta};
+use openwhoop_codec::ParsedHistoryReading;
+use openwhoop_entities::vo2max;
+
+use crate::{
+    StepCounter, UserProfile,
+    helpers::time_math::{linear_fit, median, truncate_to_minute},
+};
+
+/// Cardio fitness for one week, ml/kg/min.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vo2MaxEstimate {
+    /// Monday the week starts on.
+    pub week: NaiveDate,
+    pub vo2max: f64,
+    /// Estimate from the max to resting heart rate ratio alone.
+    pub uth: f64,
+    /// Median estimate from minutes of walking and running, when there
+    /// were enough of them.
+    pub submaximal: Option<f64>,
+    pub submaximal_minutes: usize,
+    pub resting_hr: u8,
+    pub max_hr: u8,
+}
+
+impl From<vo2max::Model> for Vo2MaxEstimate {
+    fn from(value: vo2max::Model) -> Self {
+        Self {
+            week: value.week,
+            vo2max: value.vo2max,
+            uth: value.uth,
+            submaximal: value.submaximal,
+            submaximal_minutes: usize::try_from(value.submaximal_minutes).unwrap_or(0),
+            resting_hr: u8::try_from(value.resting_hr).unwrap_or(0),
+            max_hr: u8::try_from(value.max_hr).unwrap_or(0),
+        }
+    }
+}
+
+impl fmt::Display for Vo2MaxEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: VO2max {:.1} ml/kg/min (HR ratio {:.1}",
+            self.week, self.vo2max, self.uth
+        )?;
+        if let Some(submaximal) = self.submaximal {
+            write!(
+                f,
+                ", {} min walking/running {:.1}",
+                self.submaximal_minutes, submaximal
+            )?;
+        }
+        write!(
+            f,
+            "; resting {} bpm, max {} bpm)",
+            self.resting_hr, self.max_hr
+        )
+    }
+}
+
+/// Estimates VO2max from the ratio of max to resting heart rate (Uth et al.,
+/// 2004), refined with minutes of walking and running: the speed from the
+/// step cadence gives the oxygen cost (ACSM metabolic equations), and the
+/// heart rate reserve used for it extrapolates to the maximum, as %HRR
+/// tracks %VO2 reserve (Swain and Leutholtz, 1997).
+pub struct Vo2MaxEstimator {
+    resting_hr: u8,
+    max_hr: u8,
+    height_cm: Option<f64>,
+}
+
+impl Vo2MaxEstimator {
+    /// Resting oxygen uptake, ml/kg/min.
+    const RESTING_VO2: f64 = 3.5;
+    /// Minutes at the start of a walk or run skipped while heart rate
+    /// catches up with the pace.
+    const WARM_UP_MINUTES: i64 = 3;
+    /// Heart rate reserve range where heart rate rises linearly with
+    /// oxygen uptake.
+    const HRR_RANGE: (f64, f64) = (0.4, 0.85);
+    const WALKING_CADENCE: (f64, f64) = (80.0, 130.0);
+    const RUNNING_CADENCE: (f64, f64) = (130.0, 210.0);
+    /// Step length as a fraction of height.
+    const WALKING_STEP_LENGTH: f64 = 0.415;
+    const RUNNING_STEP_LENGTH: f64 = 0.6;
+    /// Plausible range of a single minute's estimate.
+    const PLAUSIBLE: (f64, f64) = (15.0, 90.0);
+    /// Minutes of walking or running needed before they refine the
+    /// heart rate ratio estimate.
+    pub const MIN_SUBMAXIMAL_MINUTES: usize = 10;
+    /// Minutes at which the refinement gets its full weight.
+    const FULL_WEIGHT_MINUTES: f64 = 30.0;
+    const MAX_SUBMAXIMAL_WEIGHT: f64 = 0.75;
+
+    /// `None` when the max heart rate isn't above the resting one. The
+    /// refinement needs the height from `profile` for the step length.
+    pub fn new(resting_hr: u8, max_hr: u8, profile: &UserProfile) -> Option<Self> {
+        (resting_hr > 0 && max_hr > resting_hr).then_some(Self {
+            resting_hr,
+            max_hr,
+            height_cm: profile.height_cm.filter(|height| *height > 0.0),
+        })
+    }
+
+    /// Uth estimate: 15.3 times max over resting heart rate.
+    pub fn uth(&self) -> f64 {
+        15.3 * f64::from(self.max_hr) / f64::from(self.resting_hr)
+    }
+
+    /// One estimate per steady minute of a walk or run with IMU data.
+    pub fn submaximal(&self, history: &[ParsedHistoryReading]) -> Vec<f64> {
+        let (Some(height_cm), Some(first)) = (self.height_cm, history.first()) else {
+            return Vec::new();
+        };
+        let warm_up_end = first.time + TimeDelta::minutes(Self::WARM_UP_MINUTES);
+        let reserve = f64::from(self.max_hr - self.resting_hr);
+        let bpm = minute_bpm(history);
+
+        StepCounter::count(history)
+            .into_iter()
+            .filter(|minute| !minute.estimated && minute.minute >= warm_up_end)
+            .filter_map(|minute| {
+                let cadence = f64::from(minute.steps);
+                let (step_length, cost_per_meter) = if in_range(cadence, Self::WALKING_CADENCE) {
+                    (Self::WALKING_STEP_LENGTH, 0.1)
+                } else if in_range(cadence, Self::RUNNING_CADENCE) {
+                    (Self::RUNNING_STEP_LENGTH, 0.2)
+                } else {
+                    return None;
+                };
+                let speed = cadence * step_length * height_cm / 100.0;
+                let vo2 = Self::RESTING_VO2 + cost_per_meter * speed;
+
+                let hrr = (bpm.get(&minute.minute)? - f64::from(self.resting_hr)) / reserve;
+                if !in_range(hrr, Self::HRR_RANGE) {
+                    return None;
+                }
+
+                let estimate = Self::RESTING_VO2 + (vo2 - Self::RESTING_VO2) / hrr;
+                in_range(estimate, Self::PLAUSIBLE).then_some(estimate)
+            })
+            .collect()
+    }
+
+    /// Estimate for the week starting `week` from the heart rate ratio and
+    /// the week's submaximal minutes. The median of the minutes is blended
+    /// in with a weight that grows with their number.
+    pub fn estimate(&self, week: NaiveDate, submaximal: &[f64]) -> Vo2MaxEstimate {
+        let uth = self.uth();
+        let minutes = submaximal.len();
+        let submaximal = (minutes >= Self::MIN_SUBMAXIMAL_MINUTES).then(|| median(submaximal));
+
+        let vo2max = match submaximal {
+            Some(submaximal) => {
+                let weight = Self::MAX_SUBMAXIMAL_WEIGHT
+                    * (minutes as f64 / Self::FULL_WEIGHT_MINUTES).min(1.0);
+                uth * (1.0 - weight) + submaximal * weight
+            }
+            None => uth,
+        };
+
+        Vo2MaxEstimate {
+            week,
+            vo2max,
+            uth,
+            submaximal,
+            submaximal_minutes: minutes,
+            resting_hr: self.resting_hr,
+            max_hr: self.max_hr,
+        }
+    }
+
+    /// Monday of the week `date` falls in.
+    pub fn week_start(date: NaiveDate) -> NaiveDate {
+        date - Days::new(u64::from(date.weekday().num_days_from_monday()))
+    }
+
+    /// Least-squares change per week over `estimates`, oldest first. `None`
+    /// with fewer than two weeks.
+    pub fn trend(estimates: &[Vo2MaxEstimate]) -> Option<f64> {
+        let first = estimates.first()?.week;
+        let points = estimates
+            .iter()
+            .map(|e| ((e.week - first).num_days() as f64 / 7.0, e.vo2max))
+            .collect::<Vec<_>>();
+
+        linear_fit(&points).map(|(slope, _)| slope)
+    }
+}
+
+fn in_range(value: f64, (low, high): (f64, f64)) -> bool {
+    (low..=high).contains(&value)
+}
+
+/// Mean heart rate of each wall-clock minute.
+fn minute_bpm(history: &[ParsedHistoryReading]) -> BTreeMap<NaiveDateTime, f64> {
+    let mut minutes = BTreeMap::<NaiveDateTime, (f64, u32)>::new();
+    for reading in history.iter().filter(|r| r.bpm > 0) {
+        let (sum, count) = minutes.entry(truncate_to_minute(reading.time)).or_default();
+        *sum += f64::from(reading.bpm);
+        *count += 1;
+    }
+
+    minutes
+        .into_iter()
+        .map(|(minute, (sum, count))| (minute, sum / f64::from(count)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic::SyntheticMotion;
+
+    fn profile() -> UserProfile {
+        UserProfile {
+            height_cm: Some(180.0),
+            ..Default::default()
+        }
+    }
+
+    fn week(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    /// One reading per second with 100 IMU samples oscillating at `cadence`
+    /// per minute.
+    fn imu_history(minutes: i64, cadence: f64, bpm: u8) -> Vec<ParsedHistoryReading> {
+        SyntheticMotion::readings(
+            week(3).and_hms_opt(7, 0, 0).unwrap(),
+            &[SyntheticMotion {
+                seconds: minutes * 60,
+                cadence,
+                amplitude: 0.5,
+                rotation: 0.0,
+                bpm,
+            }],
+        )
+    }
+
+    #[test]
+    fn uth_uses_heart_rate_ratio() {
+        let estimator = Vo2MaxEstimator::new(50, 190, &UserProfile::default()).unwrap();
+        // 15.3 * 190 / 50
+        assert!((estimator.uth() - 58.14).abs() < 1e-9);
+
+        let estimate = estimator.estimate(week(3), &[]);
+        assert_eq!(estimate.vo2max, estimate.uth);
+        assert_eq!(estimate.submaximal, None);
+
+        assert!(Vo2MaxEstimator::new(60, 60, &profile()).is_none());
+    }
+
+    #[test]
+    fn running_minutes_refine_the_estimate() {
+        let estimator = Vo2MaxEstimator::new(50, 190, &profile()).unwrap();
+        let minutes = estimator.submaximal(&imu_history(20, 165.0, 155));
+
+        // Warm-up skipped; 165 steps/min of 1.08 m is 178 m/min, 39.1
+        // ml/kg/min at 75% of heart rate reserve
+        assert_eq!(minutes.len(), 17);
+        for estimate in &minutes {
+            assert!((estimate - 51.0).abs() < 1.0, "{estimate}");
+        }
+
+        let estimate = estimator.estimate(week(3), &minutes);
+        let submaximal = estimate.submaximal.unwrap();
+        assert!(estimate.vo2max < estimate.uth && estimate.vo2max > submaximal);
+        assert_eq!(estimate.submaximal_minutes, 17);
+    }
+
+    #[test]
+    fn unusable_minutes_are_skipped() {
+        let estimator = Vo2MaxEstimator::new(50, 190, &profile()).unwrap();
+        // Heart rate too low for the linear range
+        assert!(estimator.submaximal(&imu_history(10, 110.0, 80)).is_empty());
+        // No height for the step length
+        let estimator = Vo2MaxEstimator::new(50, 190, &UserProfile::default()).unwrap();
+        assert!(
+            estimator
+                .submaximal(&imu_history(10, 165.0, 155))
+                .is_empty()
+        );
+
+        // Too few minutes to refine
+        let estimator = Vo2MaxEstimator::new(50, 190, &profile()).unwrap();
+        assert_eq!(estimator.estimate(week(3), &[45.0; 9]).submaximal, None);
+    }
+
+    #[test]
+    fn weeks_and_trend() {
+        assert_eq!(Vo2MaxEstimator::week_start(week(9)), week(3));
+        assert_eq!(Vo2MaxEstimator::week_start(week(3)), week(3));
+
+        let estimator = Vo2MaxEstimator::new(50, 190, &profile()).unwrap();
+        let estimates = [(3, 40.0), (10, 41.0), (24, 43.0)].map(|(day, value)| Vo2MaxEstimate {
+            vo2max: value,
+            ..estimator.estimate(week(day), &[])
+        });
+        assert!((Vo2MaxEstimator::trend(&estimates).unwrap() - 1.0).abs() < 1e-9);
+        assert_eq!(Vo2MaxEstimator::trend(&estimates[..1]), None);
+    }
+}
@@ -0,0 +1,244 @@
+use chrono::{NaiveDateTime, TimeDelta};
+use openwhoop_codec::ParsedHistoryReading;
+
+use crate::helpers::{gravity::gravity_delta, time_math::truncate_to_minute};
+
+/// A bout of sustained exercise found in the history.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Workout {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    pub avg_bpm: u8,
+}
+
+/// Finds workouts as sustained heart rate elevation above a fraction of the
+/// heart rate reserve, confirmed by wrist movement.
+pub struct WorkoutDetector {
+    pub resting_hr: u8,
+    pub max_hr: u8,
+}
+
+/// Readings of one wall-clock minute.
+struct Minute {
+    start: NaiveDateTime,
+    elevated: bool,
+    moving: bool,
+    bpm: f64,
+}
+
+impl WorkoutDetector {
+    /// Fraction of the heart rate reserve above which HR counts as elevated.
+    const HRR_THRESHOLD: f64 = 0.4;
+    /// Share of a minute's readings that must be elevated.
+    const MIN_ELEVATED_FRACTION: f64 = 0.7;
+    /// Gravity change between readings (g) that counts as movement.
+    const GRAVITY_MOVEMENT: f32 = 0.05;
+    /// Accelerometer deviation from 1 g that counts as movement.
+    const IMU_MOVEMENT: f32 = 0.15;
+    /// Share of a minute's readings that must show movement.
+    const MIN_MOVING_READINGS: f64 = 0.1;
+    /// Share of a bout's minutes that must show movement.
+    const MIN_MOVING_MINUTES: f64 = 0.3;
+    /// Bouts separated by at most this much are merged.
+    pub const MERGE_GAP: TimeDelta = TimeDelta::minutes(5);
+    pub const MIN_DURATION: TimeDelta = TimeDelta::minutes(10);
+
+    pub fn new(resting_hr: u8, max_hr: u8) -> Self {
+        Self { resting_hr, max_hr }
+    }
+
+    /// Heart rate above which a reading counts as elevated.
+    pub fn threshold(&self) -> f64 {
+        let reserve = f64::from(self.max_hr) - f64::from(self.resting_hr);
+        f64::from(self.resting_hr) + reserve * Self::HRR_THRESHOLD
+    }
+
+    pub fn detect(&self, history: &[ParsedHistoryReading]) -> Vec<Workout> {
+        if self.max_hr <= self.resting_hr {
+            return Vec::new();
+        }
+
+        let minutes = self.minutes(history);
+        let mut bouts: Vec<Vec<&Minute>> = Vec::new();
+        for minute in minutes.iter().filter(|m| m.elevated) {
+            match bouts.last_mut() {
+                Some(bout)
+                    if minute.start - bout[bout.len() - 1].start
+                        <= Self::MERGE_GAP + TimeDelta::minutes(1) =>
+                {
+                    bout.push(minute)
+                }
+                _ => bouts.push(vec![minute]),
+            }
+        }
+
+        bouts
+            .into_iter()
+            .filter_map(|bout| {
+                let start = bout[0].start;
+                let end = bout[bout.len() - 1].start + TimeDelta::minutes(1);
+                let moving = bout.iter().filter(|m| m.moving).count() as f64 / bout.len() as f64;
+                if end - start < Self::MIN_DURATION || moving < Self::MIN_MOVING_MINUTES {
+                    return None;
+                }
+
+                let avg_bpm = bout.iter().map(|m| m.bpm).sum::<f64>() / bout.len() as f64;
+                Some(Workout {
+                    start,
+                    end,
+                    avg_bpm: avg_bpm.round().clamp(0.0, 255.0) as u8,
+                })
+            })
+            .collect()
+    }
+
+    fn minutes(&self, history: &[ParsedHistoryReading]) -> Vec<Minute> {
+        let threshold = self.threshold();
+        let mut minutes = Vec::new();
+
+        let mut start = 0;
+        while start < history.len() {
+            let minute = truncate_to_minute(history[start].time);
+            let len = history[start..]
+                .iter()
+                .take_while(|r| truncate_to_minute(r.time) == minute)
+                .count();
+            let readings = &history[start..start + len];
+            let previous = start.checked_sub(1).map(|i| &history[i]);
+
+            let elevated = readings
+                .iter()
+                .filter(|r| f64::from(r.bpm) >= threshold)
+                .count();
+            let moving = readings
+                .iter()
+                .enumerate()
+                .filter(|(i, r)| {
+                    let before = if *i == 0 {
+                        previous
+                    } else {
+                        Some(&readings[i - 1])
+                    };
+                    Self::is_moving(before, r)
+                })
+                .count();
+
+            minutes.push(Minute {
+                start: minute,
+                elevated: elevated as f64 / len as f64 >= Self::MIN_ELEVATED_FRACTION,
+                moving: moving as f64 / len as f64 >= Self::MIN_MOVING_READINGS,
+                bpm: readings.iter().map(|r| f64::from(r.bpm)).sum::<f64>() / len as f64,
+            });
+            start += len;
+        }
+
+        minutes
+    }
+
+    /// Movement from the IMU when present, else from the change in gravity.
+    /// Readings without either count as moving, like in gravity-based
+    /// sleep detection, so older packet formats rely on heart rate alone.
+    fn is_moving(previous: Option<&ParsedHistoryReading>, reading: &ParsedHistoryReading) -> bool {
+        if let Some(imu) = reading.imu_data.as_ref().filter(|imu| !imu.is_empty()) {
+            return imu
+                .iter()
+                .any(|sample| (sample.acceleration() - 1.0).abs() > Self::IMU_MOVEMENT);
+        }
+
+        match (previous.and_then(|p| p.gravity), reading.gravity) {
+            (Some(a), Some(b)) => gravity_delta(a, b) > Self::GRAVITY_MOVEMENT,
+            (_, None) => true,
+            (None, Some(_)) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn base() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(17, 0, 0)
+            .unwrap()
+    }
+
+    /// One reading per second; `segments` are (minutes, bpm, moving).
+    fn history(segments: &[(i64, u8, bool)]) -> Vec<ParsedHistoryReading> {
+        let mut readings = Vec::new();
+        let mut second = 0;
+        for &(minutes, bpm, moving) in segments {
+            for _ in 0..minutes * 60 {
+                let tilt = if moving && second % 2 == 0 { 0.3 } else { 0.0 };
+                readings.push(ParsedHistoryReading {
+                    time: base() + TimeDelta::seconds(second),
+                    bpm,
+                    rr: Vec::new(),
+                    imu_data: None,
+                    gravity: Some([tilt, 0.0, 1.0]),
+                    sensor_data: None,
+                });
+                second += 1;
+            }
+        }
+        readings
+    }
+
+    fn detector() -> WorkoutDetector {
+        // Threshold: 60 + 0.4 * 130 = 112
+        WorkoutDetector::new(60, 190)
+    }
+
+    #[test]
+    fn detects_sustained_elevation_with_movement() {
+        let workouts = detector().detect(&history(&[
+            (10, 70, false),
+            (30, 150, true),
+            (10, 75, false),
+        ]));
+        assert_eq!(
+            workouts,
+            vec![Workout {
+                start: base() + TimeDelta::minutes(10),
+                end: base() + TimeDelta::minutes(40),
+                avg_bpm: 150,
+            }]
+        );
+    }
+
+    #[test]
+    fn ignores_elevation_without_movement() {
+        let workouts = detector().detect(&history(&[(10, 70, false), (30, 150, false)]));
+        assert!(workouts.is_empty());
+    }
+
+    #[test]
+    fn ignores_short_bouts() {
+        let workouts =
+            detector().detect(&history(&[(10, 70, true), (5, 150, true), (10, 70, true)]));
+        assert!(workouts.is_empty());
+    }
+
+    #[test]
+    fn merges_nearby_bouts() {
+        let workouts = detector().detect(&history(&[
+            (15, 150, true),
+            (3, 90, true),
+            (15, 150, true),
+            (20, 70, false),
+            (15, 150, true),
+        ]));
+        assert_eq!(workouts.len(), 2);
+        assert_eq!(workouts[0].start, base());
+        assert_eq!(workouts[0].end, base() + TimeDelta::minutes(33));
+        assert_eq!(workouts[1].start, base() + TimeDelta::minutes(53));
+    }
+
+    #[test]
+    fn no_detection_without_reserve() {
+        let workouts = WorkoutDetector::new(80, 80).detect(&history(&[(30, 150, true)]));
+        assert!(workouts.is_empty());
+    }
+}
@@ -0,0 +1,307 @@
+use std::{fmt, iter::Sum, ops::Add, str::FromStr};
+
+use openwhoop_codec::{ParsedHistoryReading, WhoopError};
+use openwhoop_entities::{activities, heart_rate_zones};
+
+/// What the zone boundaries are a percentage of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ZoneBasis {
+    /// Heart rate reserve: `(bpm - resting_hr) / (max_hr - resting_hr)`.
+    #[default]
+    HeartRateReserve,
+    /// Max heart rate: `bpm / max_hr`.
+    MaxHeartRate,
+}
+
+impl ZoneBasis {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::HeartRateReserve => "hrr",
+            Self::MaxHeartRate => "hrmax",
+        }
+    }
+}
+
+impl fmt::Display for ZoneBasis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ZoneBasis {
+    type Err = WhoopError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "hrr" | "reserve" => Ok(Self::HeartRateReserve),
+            "hrmax" | "max" => Ok(Self::MaxHeartRate),
+            _ => Err(WhoopError::InvalidData),
+        }
+    }
+}
+
+/// Minutes spent in each of the heart rate zones 1-5.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ZoneBreakdown {
+    pub minutes: [f64; 5],
+}
+
+impl ZoneBreakdown {
+    pub fn total(&self) -> f64 {
+        self.minutes.iter().sum()
+    }
+
+    /// Edwards' TRIMP: minutes in each zone weighted by the zone number.
+    pub fn trimp(&self) -> f64 {
+        self.minutes
+            .iter()
+            .zip(1..)
+            .map(|(minutes, weight)| minutes * f64::from(weight))
+            .sum()
+    }
+
+    /// Zones stored on an activity, `None` when they were never calculated.
+    pub fn from_activity(activity: &activities::Model) -> Option<Self> {
+        Some(Self {
+            minutes: [
+                activity.zone_1?,
+                activity.zone_2?,
+                activity.zone_3?,
+                activity.zone_4?,
+                activity.zone_5?,
+            ],
+        })
+    }
+}
+
+impl Add for ZoneBreakdown {
+    type Output = Self;
+
+    fn add(mut self, rhs: Self) -> Self {
+        for (minutes, other) in self.minutes.iter_mut().zip(rhs.minutes) {
+            *minutes += other;
+        }
+        self
+    }
+}
+
+impl Sum for ZoneBreakdown {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+impl From<heart_rate_zones::Model> for ZoneBreakdown {
+    fn from(value: heart_rate_zones::Model) -> Self {
+        Self {
+            minutes: [
+                value.zone_1,
+                value.zone_2,
+                value.zone_3,
+                value.zone_4,
+                value.zone_5,
+            ],
+        }
+    }
+}
+
+impl fmt::Display for ZoneBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (zone, minutes) in self.minutes.iter().enumerate() {
+            if zone > 0 {
+                f.write_str("  ")?;
+            }
+            write!(f, "Z{}: {:.0}m", zone + 1, minutes)?;
+        }
+        Ok(())
+    }
+}
+
+/// Edwards' five heart rate zones, starting at 50% of the heart rate reserve
+/// or max heart rate in steps of 10%.
+pub struct HeartRateZones {
+    pub max_hr: u8,
+    pub resting_hr: u8,
+    pub basis: ZoneBasis,
+}
+
+impl HeartRateZones {
+    /// Lower bound of each zone, in percent.
+    const LOWER_BOUNDS: [f64; 5] = [50.0, 60.0, 70.0, 80.0, 90.0];
+
+    pub fn new(max_hr: u8, resting_hr: u8, basis: ZoneBasis) -> Self {
+        Self {
+            max_hr,
+            resting_hr,
+            basis,
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        match self.basis {
+            ZoneBasis::HeartRateReserve => self.max_hr > self.resting_hr,
+            ZoneBasis::MaxHeartRate => self.max_hr > 0,
+        }
+    }
+
+    fn percent(&self, bpm: u8) -> f64 {
+        let (bpm, max_hr, resting_hr) = (
+            f64::from(bpm),
+            f64::from(self.max_hr),
+            f64::from(self.resting_hr),
+        );
+        match self.basis {
+            ZoneBasis::HeartRateReserve => (bpm - resting_hr) / (max_hr - resting_hr) * 100.0,
+            ZoneBasis::MaxHeartRate => bpm / max_hr * 100.0,
+        }
+    }
+
+    /// Zone (1-5) of `bpm`, or 0 below zone 1.
+    pub fn zone(&self, bpm: u8) -> u8 {
+        let pct = self.percent(bpm);
+        Self::LOWER_BOUNDS
+            .iter()
+            .take_while(|bound| pct >= **bound)
+            .count() as u8
+    }
+
+    /// Lowest heart rate of each zone.
+    pub fn lower_bounds(&self) -> [u8; 5] {
+        let (max_hr, resting_hr) = (f64::from(self.max_hr), f64::from(self.resting_hr));
+        Self::LOWER_BOUNDS.map(|pct| {
+            let bpm = match self.basis {
+                ZoneBasis::HeartRateReserve => resting_hr + (max_hr - resting_hr) * pct / 100.0,
+                ZoneBasis::MaxHeartRate => max_hr * pct / 100.0,
+            };
+            bpm.ceil().clamp(0.0, 255.0) as u8
+        })
+    }
+
+    /// Time in each zone, every reading counting for the sample interval.
+    /// `None` when the heart rate limits don't allow zones.
+    pub fn breakdown(&self, hr: &[ParsedHistoryReading]) -> Option<ZoneBreakdown> {
+        if !self.is_valid() {
+            return None;
+        }
+
+        let sample_duration_min = sample_duration_minutes(hr);
+        let mut breakdown = ZoneBreakdown::default();
+        for reading in hr {
+            let zone = self.zone(reading.bpm);
+            if zone > 0 {
+                breakdown.minutes[usize::from(zone - 1)] += sample_duration_min;
+            }
+        }
+
+        Some(breakdown)
+    }
+}
+
+/// Estimate the sample interval in minutes from the first two readings.
+/// Falls back to 1/60 min (1 second) if only one reading or timestamps match.
+fn sample_duration_minutes(hr: &[ParsedHistoryReading]) -> f64 {
+    if hr.len() < 2 {
+        return 1.0 / 60.0;
+    }
+    let dt = (hr[1].time - hr[0].time).num_milliseconds().unsigned_abs();
+    if dt == 0 {
+        1.0 / 60.0
+    } else {
+        dt as f64 / 60_000.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn readings(bpms: &[(u8, usize)]) -> Vec<ParsedHistoryReading> {
+        let base = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+
+        bpms.iter()
+            .flat_map(|(bpm, seconds)| std::iter::repeat_n(*bpm, *seconds))
+            .enumerate()
+            .map(|(i, bpm)| ParsedHistoryReading {
+                time: base + chrono::Duration::seconds(i64::try_from(i).unwrap()),
+                bpm,
+                rr: vec![],
+                imu_data: None,
+                gravity: None,
+                sensor_data: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn breakdown_counts_time_per_zone() {
+        // max 200, resting 50: zone 1 from 125, zone 3 from 155, zone 5 from 185
+        let zones = HeartRateZones::new(200, 50, ZoneBasis::HeartRateReserve);
+        let hr = readings(&[(100, 600), (130, 120), (160, 300), (190, 60)]);
+
+        let breakdown = zones.breakdown(&hr).unwrap();
+        let expected = [2.0, 0.0, 5.0, 0.0, 1.0];
+        for (minutes, expected) in breakdown.minutes.iter().zip(expected) {
+            assert!((minutes - expected).abs() < 1e-9, "{breakdown:?}");
+        }
+        assert!((breakdown.total() - 8.0).abs() < 1e-9);
+        assert!((breakdown.trimp() - (2.0 + 15.0 + 5.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn basis_moves_the_boundaries() {
+        let reserve = HeartRateZones::new(200, 50, ZoneBasis::HeartRateReserve);
+        let max = HeartRateZones::new(200, 50, ZoneBasis::MaxHeartRate);
+        assert_eq!(reserve.lower_bounds(), [125, 140, 155, 170, 185]);
+        assert_eq!(max.lower_bounds(), [100, 120, 140, 160, 180]);
+
+        // 60% HRR, 70% HRmax
+        assert_eq!(reserve.zone(140), 2);
+        assert_eq!(max.zone(140), 3);
+        assert_eq!(max.zone(99), 0);
+    }
+
+    #[test]
+    fn invalid_limits_have_no_zones() {
+        let hr = readings(&[(120, 60)]);
+        assert!(
+            HeartRateZones::new(60, 60, ZoneBasis::HeartRateReserve)
+                .breakdown(&hr)
+                .is_none()
+        );
+        assert!(
+            HeartRateZones::new(60, 60, ZoneBasis::MaxHeartRate)
+                .breakdown(&hr)
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn breakdowns_add_up_and_parse_basis() {
+        let a = ZoneBreakdown {
+            minutes: [1.0, 2.0, 0.0, 0.0, 0.5],
+        };
+        let b = ZoneBreakdown {
+            minutes: [0.0, 1.0, 3.0, 0.0, 0.5],
+        };
+        assert_eq!(
+            [a, b].into_iter().sum::<ZoneBreakdown>().minutes,
+            [1.0, 3.0, 3.0, 0.0, 1.0]
+        );
+        assert_eq!(
+            "HRmax".parse::<ZoneBasis>().unwrap(),
+            ZoneBasis::MaxHeartRate
+        );
+        assert_eq!(
+            ZoneBasis::HeartRateReserve
+                .to_string()
+                .parse::<ZoneBasis>()
+                .unwrap(),
+            ZoneBasis::HeartRateReserve
+        );
+        assert!("vo2".parse::<ZoneBasis>().is_err());
+    }
+}
@@ -11,6 +11,9 @@ pub mod constants;
 
 mod helpers;
 
+mod memfault;
+pub use memfault::{MemfaultChunkAssembler, MemfaultMessage};
+
 mod whoop_data;
 pub use whoop_data::*;
 
@@ -0,0 +1,145 @@
+use crate::{WhoopError, WhoopPacket};
+
+/// Reassembles Memfault chunks received on the `MEMFAULT` characteristic.
+///
+/// Chunk layout (Memfault chunk transport):
+///   [0]     header: bit 7 = continuation, bit 6 = more data follows
+///   varint  first chunk of a multi-chunk message: total message length;
+///           continuation chunk: offset of this chunk within the message
+///   [..]    message bytes
+///
+/// A message is complete once a chunk arrives without the more-data bit.
+#[derive(Debug, Default)]
+pub struct MemfaultChunkAssembler {
+    data: Vec<u8>,
+    chunks: usize,
+    expected_len: Option<usize>,
+    in_progress: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemfaultMessage {
+    pub data: Vec<u8>,
+    pub chunks: usize,
+    /// Total length announced by the first chunk, if any
+    pub expected_len: Option<usize>,
+}
+
+impl MemfaultMessage {
+    /// Length and CRC32 of the message data as hex, to recognise a message
+    /// received or replayed again.
+    pub fn hash(&self) -> String {
+        format!(
+            "{:08x}{:08x}",
+            self.data.len(),
+            WhoopPacket::crc32(&self.data)
+        )
+    }
+}
+
+impl MemfaultChunkAssembler {
+    const CONTINUATION: u8 = 0x80;
+    const MORE_DATA: u8 = 0x40;
+
+    /// Feed one chunk. Returns the message once its last chunk arrives.
+    /// An out-of-order chunk drops the partial message and returns an error.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<Option<MemfaultMessage>, WhoopError> {
+        let (&header, mut rest) = chunk.split_first().ok_or(WhoopError::PacketTooShort)?;
+        let more = header & Self::MORE_DATA != 0;
+
+        if header & Self::CONTINUATION != 0 {
+            let offset = read_varint(&mut rest)?;
+            if !self.in_progress || offset != self.data.len() {
+                self.reset();
+                return Err(WhoopError::InvalidIndexError);
+            }
+        } else {
+            self.reset();
+            if more {
+                self.expected_len = Some(read_varint(&mut rest)?);
+            }
+            self.in_progress = true;
+        }
+
+        self.data.extend_from_slice(rest);
+        self.chunks += 1;
+
+        if more {
+            return Ok(None);
+        }
+
+        let message = MemfaultMessage {
+            data: std::mem::take(&mut self.data),
+            chunks: self.chunks,
+            expected_len: self.expected_len,
+        };
+        self.reset();
+        Ok(Some(message))
+    }
+
+    pub fn reset(&mut self) {
+        self.data.clear();
+        self.chunks = 0;
+        self.expected_len = None;
+        self.in_progress = false;
+    }
+}
+
+fn read_varint(data: &mut &[u8]) -> Result<usize, WhoopError> {
+    let mut value: usize = 0;
+    for shift in (0..35).step_by(7) {
+        let (&byte, rest) = data.split_first().ok_or(WhoopError::PacketTooShort)?;
+        *data = rest;
+        value |= usize::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(WhoopError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_chunk_message() {
+        let mut assembler = MemfaultChunkAssembler::default();
+        let message = assembler.push(&[0x00, 1, 2, 3]).unwrap().unwrap();
+        assert_eq!(message.data, vec![1, 2, 3]);
+        assert_eq!(message.chunks, 1);
+        assert_eq!(message.expected_len, None);
+        assert_eq!(message.hash(), "0000000355bc801d");
+    }
+
+    #[test]
+    fn reassembles_multi_chunk_message() {
+        let mut assembler = MemfaultChunkAssembler::default();
+        let payload: Vec<u8> = (0..200).map(|i| i as u8).collect();
+
+        // total length 200 encodes as varint [0xc8, 0x01]
+        let mut first = vec![0x40, 0xc8, 0x01];
+        first.extend_from_slice(&payload[..130]);
+        assert_eq!(assembler.push(&first).unwrap(), None);
+
+        // offset 130 encodes as varint [0x82, 0x01]
+        let mut second = vec![0x80, 0x82, 0x01];
+        second.extend_from_slice(&payload[130..]);
+        let message = assembler.push(&second).unwrap().unwrap();
+
+        assert_eq!(message.data, payload);
+        assert_eq!(message.chunks, 2);
+        assert_eq!(message.expected_len, Some(200));
+    }
+
+    #[test]
+    fn out_of_order_continuation_drops_partial_message() {
+        let mut assembler = MemfaultChunkAssembler::default();
+        assert_eq!(assembler.push(&[0x40, 0x04, 1, 2]).unwrap(), None);
+        assert!(assembler.push(&[0x80, 0x03, 4]).is_err());
+        assert!(assembler.push(&[0x80, 0x02, 3, 4]).is_err());
+
+        let message = assembler.push(&[0x00, 9]).unwrap().unwrap();
+        assert_eq!(message.data, vec![9]);
+    }
+}
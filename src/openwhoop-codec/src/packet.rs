@@ -225,7 +225,7 @@ impl WhoopPacket {
         crc
     }
 
-    fn crc32(data: &[u8]) -> u32 {
+    pub(crate) fn crc32(data: &[u8]) -> u32 {
         let mut crc: u32 = 0xFFFFFFFF;
         for &byte in data {
             crc ^= u32::from(byte);
@@ -1,7 +1,7 @@
 use chrono::Utc;
 
 use crate::{
-    WhoopPacket,
+    WhoopPacket, Wrist,
     constants::{CommandNumber, PacketType, WhoopGeneration},
     error::WhoopError,
 };
@@ -72,6 +72,33 @@ impl WhoopPacket {
         )
     }
 
+    pub fn get_extended_battery_info() -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::GetExtendedBatteryInfo.as_u8(),
+            vec![0x00],
+        )
+    }
+
+    pub fn get_body_location() -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::GetBodyLocationAndStatus.as_u8(),
+            vec![0x00],
+        )
+    }
+
+    pub fn select_wrist(wrist: Wrist) -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::SelectWrist.as_u8(),
+            vec![wrist.as_u8()],
+        )
+    }
+
     pub fn hello_harvard() -> WhoopPacket {
         WhoopPacket::new(
             PacketType::Command,
@@ -112,17 +139,30 @@ impl WhoopPacket {
     }
 
     pub fn set_time() -> Result<WhoopPacket, WhoopError> {
-        let mut data = vec![];
         let current_time =
             u32::try_from(Utc::now().timestamp()).map_err(|_| WhoopError::Overflow)?;
-        data.extend_from_slice(&current_time.to_le_bytes());
+        Ok(WhoopPacket::set_clock(current_time))
+    }
+
+    pub fn set_clock(unix: u32) -> WhoopPacket {
+        let mut data = vec![];
+        data.extend_from_slice(&unix.to_le_bytes());
         data.append(&mut vec![0, 0, 0, 0, 0]); // padding
-        Ok(WhoopPacket::new(
+        WhoopPacket::new(
             PacketType::Command,
             0,
             CommandNumber::SetClock.as_u8(),
             data,
-        ))
+        )
+    }
+
+    pub fn get_clock() -> WhoopPacket {
+        WhoopPacket::new(
+            PacketType::Command,
+            0,
+            CommandNumber::GetClock.as_u8(),
+            vec![0x00],
+        )
     }
 
     pub fn history_end(end_data: [u8; 8]) -> WhoopPacket {
@@ -499,6 +539,38 @@ mod tests {
         assert_roundtrip(&off);
     }
 
+    #[test]
+    fn get_clock_packet() {
+        let p = WhoopPacket::get_clock();
+        assert_command_packet(&p, CommandNumber::GetClock);
+        assert_roundtrip(&p);
+    }
+
+    #[test]
+    fn get_extended_battery_info_packet() {
+        let p = WhoopPacket::get_extended_battery_info();
+        assert_command_packet(&p, CommandNumber::GetExtendedBatteryInfo);
+        assert_eq!(p.data, vec![0x00]);
+        assert_roundtrip(&p);
+    }
+
+    #[test]
+    fn select_wrist_packet() {
+        let p = WhoopPacket::select_wrist(Wrist::Right);
+        assert_command_packet(&p, CommandNumber::SelectWrist);
+        assert_eq!(p.data, vec![0x01]);
+        assert_roundtrip(&p);
+    }
+
+    #[test]
+    fn set_clock_encodes_unix_with_padding() {
+        let p = WhoopPacket::set_clock(1_700_000_000);
+        assert_command_packet(&p, CommandNumber::SetClock);
+        assert_eq!(p.data[..4], 1_700_000_000u32.to_le_bytes());
+        assert_eq!(p.data[4..], [0; 5]);
+        assert_roundtrip(&p);
+    }
+
     #[test]
     fn alarm_gen5() -> Result<(), WhoopError> {
         let packet = WhoopPacket::alarm_time(1772710140, WhoopGeneration::Gen5).with_seq(56);
@@ -4,6 +4,15 @@ use crate::{
     helpers::BufferReader,
 };
 
+mod battery;
+pub use battery::{BatteryPackInfo, ChargeState, ExtendedBatteryInfo};
+
+mod wear;
+pub use wear::{BodyLocation, BodyLocationInfo, Wrist};
+
+mod console_log;
+pub use console_log::ConsoleLogEvent;
+
 mod history;
 pub use history::{
     Activity, HistoryReading, HistoryReadingResult, ImuSample, ParsedHistoryReading, SensorData,
@@ -85,6 +94,26 @@ impl WhoopCommandResponse {
         }
     }
 
+    /// Strap RTC value from a `GetClock` response. The body starts with a
+    /// one-byte revision, followed by the unix time (u32 LE, seconds).
+    pub fn get_clock_response(&self) -> Option<u32> {
+        if CommandNumber::from_u8(self.cmd) != Some(CommandNumber::GetClock) {
+            return None;
+        }
+
+        Some(u32::from_le_bytes(self.body.get(1..5)?.try_into().ok()?))
+    }
+
+    /// Wear location from a `GetBodyLocationAndStatus` response. The body
+    /// starts with a one-byte revision.
+    pub fn body_location_response(&self) -> Option<BodyLocationInfo> {
+        if CommandNumber::from_u8(self.cmd) != Some(CommandNumber::GetBodyLocationAndStatus) {
+            return None;
+        }
+
+        BodyLocationInfo::parse(self.body.get(1..)?.to_vec()).ok()
+    }
+
     pub fn get_data_range_response(&self) -> Option<GetDataRangeResponse> {
         if CommandNumber::from_u8(self.cmd) != Some(CommandNumber::GetDataRange) {
             return None;
@@ -138,6 +167,12 @@ pub enum WhoopData {
         enabled: bool,
         unix: u32,
     },
+    ClockInfo {
+        unix: u32,
+    },
+    ExtendedBatteryInfo(ExtendedBatteryInfo),
+    BatteryPackInfo(BatteryPackInfo),
+    BodyLocation(BodyLocationInfo),
 }
 
 impl WhoopData {
@@ -173,6 +208,16 @@ impl WhoopData {
                         Self::parse_report_version_info(packet.data)
                     }
                     CommandNumber::GetAlarmTime => Self::parse_alarm_time_response(packet.data),
+                    CommandNumber::GetClock => Self::parse_clock_response(packet.data),
+                    CommandNumber::GetExtendedBatteryInfo => {
+                        Self::parse_extended_battery_response(packet.data)
+                    }
+                    CommandNumber::GetBatteryPackInfo => {
+                        Self::parse_battery_pack_response(packet.data)
+                    }
+                    CommandNumber::GetBodyLocationAndStatus => {
+                        Self::parse_body_location_response(packet.data)
+                    }
                     _ => Self::parse_command_response(packet),
                 }
             }
@@ -654,6 +699,27 @@ impl WhoopData {
         let unix = data.read_u32_le()?;
         Ok(Self::AlarmInfo { enabled, unix })
     }
+
+    fn parse_clock_response(mut data: Vec<u8>) -> Result<Self, WhoopError> {
+        let _ = data.read::<3>()?; // skip CommandResponse prefix
+        let unix = data.read_u32_le()?;
+        Ok(Self::ClockInfo { unix })
+    }
+
+    fn parse_extended_battery_response(mut data: Vec<u8>) -> Result<Self, WhoopError> {
+        let _ = data.read::<3>()?; // skip CommandResponse prefix
+        Ok(Self::ExtendedBatteryInfo(ExtendedBatteryInfo::parse(data)?))
+    }
+
+    fn parse_battery_pack_response(mut data: Vec<u8>) -> Result<Self, WhoopError> {
+        let _ = data.read::<3>()?; // skip CommandResponse prefix
+        Ok(Self::BatteryPackInfo(BatteryPackInfo::parse(data)?))
+    }
+
+    fn parse_body_location_response(mut data: Vec<u8>) -> Result<Self, WhoopError> {
+        let _ = data.read::<3>()?; // skip CommandResponse prefix
+        Ok(Self::BodyLocation(BodyLocationInfo::parse(data)?))
+    }
 }
 
 #[cfg(test)]
@@ -662,7 +728,8 @@ mod tests {
         WhoopPacket,
         constants::{CommandNumber, MetadataType, PacketType, WhoopGeneration},
         whoop_data::{
-            GetDataRangeResponse, WhoopData,
+            BatteryPackInfo, BodyLocation, BodyLocationInfo, ChargeState, ExtendedBatteryInfo,
+            GetDataRangeResponse, WhoopData, Wrist,
             history::{HistoryReading, ImuSample},
         },
     };
@@ -2518,6 +2585,191 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_clock_response_gen4() {
+        let mut data = vec![3, 1, 0];
+        data.extend_from_slice(&1_700_000_000u32.to_le_bytes());
+        let packet = WhoopPacket::new(
+            PacketType::CommandResponse,
+            0,
+            CommandNumber::GetClock.as_u8(),
+            data,
+        );
+        let parsed = WhoopData::from_packet(packet, WhoopGeneration::Gen4).expect("invalid packet");
+        assert_eq!(
+            parsed,
+            WhoopData::ClockInfo {
+                unix: 1_700_000_000
+            }
+        );
+    }
+
+    #[test]
+    fn parse_clock_response_gen5() {
+        let mut data = vec![4, 1, 0];
+        data.extend_from_slice(&1_700_000_000u32.to_le_bytes());
+        let packet = WhoopPacket::new(
+            PacketType::CommandResponse,
+            0,
+            CommandNumber::GetClock.as_u8(),
+            data,
+        );
+        let parsed = WhoopData::from_packet(packet, WhoopGeneration::Gen5).expect("invalid packet");
+
+        match parsed {
+            WhoopData::CommandResponse(resp) => {
+                assert_eq!(resp.origin_seq, 4);
+                assert_eq!(resp.get_clock_response(), Some(1_700_000_000));
+                assert_eq!(resp.get_data_range_response(), None);
+            }
+            other => panic!("expected command response, got {other:?}"),
+        }
+    }
+
+    fn extended_battery_payload() -> Vec<u8> {
+        let mut data = vec![87];
+        data.extend_from_slice(&4012u16.to_le_bytes());
+        data.extend_from_slice(&312i16.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(&143u16.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn parse_extended_battery_response_gen4() {
+        let mut data = vec![5, 1, 0];
+        data.extend(extended_battery_payload());
+        let packet = WhoopPacket::new(
+            PacketType::CommandResponse,
+            0,
+            CommandNumber::GetExtendedBatteryInfo.as_u8(),
+            data,
+        );
+        let parsed = WhoopData::from_packet(packet, WhoopGeneration::Gen4).expect("invalid packet");
+        assert_eq!(
+            parsed,
+            WhoopData::ExtendedBatteryInfo(ExtendedBatteryInfo {
+                level: 87,
+                voltage_mv: 4012,
+                temperature_c: 31.2,
+                charge_state: ChargeState::Charging,
+                cycle_count: 143,
+            })
+        );
+    }
+
+    #[test]
+    fn extended_battery_response_is_not_decoded_on_gen5() {
+        let mut data = vec![6, 1, 1];
+        data.extend(extended_battery_payload());
+        let packet = WhoopPacket::new(
+            PacketType::CommandResponse,
+            0,
+            CommandNumber::GetExtendedBatteryInfo.as_u8(),
+            data,
+        );
+        let parsed = WhoopData::from_packet(packet, WhoopGeneration::Gen5).expect("invalid packet");
+
+        match parsed {
+            WhoopData::CommandResponse(resp) => {
+                assert_eq!(resp.cmd, CommandNumber::GetExtendedBatteryInfo.as_u8());
+                assert_eq!(resp.body[1..], extended_battery_payload()[..]);
+            }
+            other => panic!("expected command response, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn parse_battery_pack_response_gen4() {
+        let mut data = vec![7, 1, 0, 1, 64];
+        data.extend_from_slice(&3850u16.to_le_bytes());
+        data.push(0);
+        let packet = WhoopPacket::new(
+            PacketType::CommandResponse,
+            0,
+            CommandNumber::GetBatteryPackInfo.as_u8(),
+            data,
+        );
+        let parsed = WhoopData::from_packet(packet, WhoopGeneration::Gen4).expect("invalid packet");
+        assert_eq!(
+            parsed,
+            WhoopData::BatteryPackInfo(BatteryPackInfo {
+                connected: true,
+                level: 64,
+                voltage_mv: 3850,
+                charge_state: ChargeState::NotCharging,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_body_location_response_gen4() {
+        let packet = WhoopPacket::new(
+            PacketType::CommandResponse,
+            0,
+            CommandNumber::GetBodyLocationAndStatus.as_u8(),
+            vec![8, 1, 0, 1, 0, 1],
+        );
+        let parsed = WhoopData::from_packet(packet, WhoopGeneration::Gen4).expect("invalid packet");
+        assert_eq!(
+            parsed,
+            WhoopData::BodyLocation(BodyLocationInfo {
+                location: BodyLocation::Wrist,
+                wrist: Some(Wrist::Left),
+                on_body: true,
+            })
+        );
+    }
+
+    #[test]
+    fn parse_body_location_response_gen5() {
+        let packet = WhoopPacket::new(
+            PacketType::CommandResponse,
+            0,
+            CommandNumber::GetBodyLocationAndStatus.as_u8(),
+            vec![9, 1, 1, 0, 0xff, 0],
+        );
+        let parsed = WhoopData::from_packet(packet, WhoopGeneration::Gen5).expect("invalid packet");
+
+        match parsed {
+            WhoopData::CommandResponse(resp) => assert_eq!(
+                resp.body_location_response(),
+                Some(BodyLocationInfo {
+                    location: BodyLocation::OffBody,
+                    wrist: None,
+                    on_body: false,
+                })
+            ),
+            other => panic!("expected command response, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn wrist_from_str_accepts_short_names() {
+        assert_eq!("L".parse::<Wrist>().unwrap(), Wrist::Left);
+        assert_eq!("right".parse::<Wrist>().unwrap(), Wrist::Right);
+        assert!("bicep".parse::<Wrist>().is_err());
+    }
+
+    #[test]
+    fn body_location_from_str_reads_back_stored_names() {
+        for location in [
+            BodyLocation::Wrist,
+            BodyLocation::Bicep,
+            BodyLocation::OffBody,
+        ] {
+            assert_eq!(location.as_str().parse::<BodyLocation>().unwrap(), location);
+        }
+        assert!("unknown".parse::<BodyLocation>().is_err());
+    }
+
+    #[test]
+    fn charge_state_keeps_unknown_values() {
+        assert_eq!(ChargeState::from_u8(9), ChargeState::Unknown(9));
+        assert_eq!(ChargeState::Unknown(9).to_string(), "unknown (9)");
+        assert_eq!(ChargeState::Full.to_string(), "full");
+    }
+
     #[test]
     fn parse_version_response() {
         let response = hex::decode("aa50000c2477070a01012900000011000000020000000000000011000000020000000200000000000000030000000400000000000000000000000300000006000000000000000000000008050100000074b95569").expect("invalid data");
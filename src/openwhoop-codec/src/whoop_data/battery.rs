@@ -0,0 +1,116 @@
+use std::fmt;
+
+use crate::{WhoopError, helpers::BufferReader};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChargeState {
+    NotCharging,
+    Charging,
+    Full,
+    Fault,
+    Unknown(u8),
+}
+
+impl ChargeState {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::NotCharging,
+            1 => Self::Charging,
+            2 => Self::Full,
+            3 => Self::Fault,
+            other => Self::Unknown(other),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NotCharging => "not_charging",
+            Self::Charging => "charging",
+            Self::Full => "full",
+            Self::Fault => "fault",
+            Self::Unknown(_) => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for ChargeState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(value) => write!(f, "unknown ({value})"),
+            _ => f.write_str(self.as_str()),
+        }
+    }
+}
+
+/// Battery diagnostics from a `GetExtendedBatteryInfo` response.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExtendedBatteryInfo {
+    /// State of charge, percent
+    pub level: u8,
+    /// Cell voltage, millivolts
+    pub voltage_mv: u16,
+    /// Cell temperature, degrees Celsius
+    pub temperature_c: f64,
+    pub charge_state: ChargeState,
+    /// Full charge cycles reported by the fuel gauge
+    pub cycle_count: u16,
+}
+
+impl ExtendedBatteryInfo {
+    /// Gen4 layout (offsets after the response prefix). Gen5 straps answer
+    /// on the Maverick command channel and aren't decoded: there is no Gen5
+    /// capture to check a layout against yet.
+    ///   [0]     state of charge (u8, %)
+    ///   [1:3]   voltage (u16 LE, mV)
+    ///   [3:5]   temperature (i16 LE, 0.1 °C)
+    ///   [5]     charge state
+    ///   [6:8]   cycle count (u16 LE)
+    pub(crate) fn parse(mut data: Vec<u8>) -> Result<Self, WhoopError> {
+        let level = data.pop_front()?;
+        let voltage_mv = data.read_u16_le()?;
+        let temperature_c = f64::from(i16::from_le_bytes(data.read::<2>()?)) / 10.0;
+        let charge_state = ChargeState::from_u8(data.pop_front()?);
+        let cycle_count = data.read_u16_le()?;
+
+        Ok(Self {
+            level,
+            voltage_mv,
+            temperature_c,
+            charge_state,
+            cycle_count,
+        })
+    }
+}
+
+/// Battery pack (clip-on charger) status from a `GetBatteryPackInfo` response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatteryPackInfo {
+    pub connected: bool,
+    /// Pack state of charge, percent
+    pub level: u8,
+    /// Pack voltage, millivolts
+    pub voltage_mv: u16,
+    pub charge_state: ChargeState,
+}
+
+impl BatteryPackInfo {
+    /// Gen4 layout (offsets after the response prefix); Gen5 responses
+    /// aren't decoded, as for [`ExtendedBatteryInfo::parse`].
+    ///   [0]     pack connected flag
+    ///   [1]     pack state of charge (u8, %)
+    ///   [2:4]   pack voltage (u16 LE, mV)
+    ///   [4]     pack charge state
+    pub(crate) fn parse(mut data: Vec<u8>) -> Result<Self, WhoopError> {
+        let connected = data.pop_front()? != 0;
+        let level = data.pop_front()?;
+        let voltage_mv = data.read_u16_le()?;
+        let charge_state = ChargeState::from_u8(data.pop_front()?);
+
+        Ok(Self {
+            connected,
+            level,
+            voltage_mv,
+            charge_state,
+        })
+    }
+}
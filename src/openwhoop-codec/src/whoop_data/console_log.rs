@@ -0,0 +1,199 @@
+/// Recurring firmware console messages lifted into structured events.
+///
+/// Matching is keyword based and case-insensitive, since message wording
+/// differs between firmware builds. Markers match whole words, so "low"
+/// doesn't match "below" or "allow"; a part number may follow a marker, as
+/// in "AFE4500".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsoleLogEvent {
+    Reset { reason: Option<String> },
+    SensorFault { sensor: &'static str },
+    BatteryWarning,
+}
+
+const RESET_MARKERS: &[&str] = &[
+    "reset reason",
+    "boot reason",
+    "rebooting",
+    "watchdog",
+    "hardfault",
+    "hard fault",
+];
+
+const BATTERY_MARKERS: &[&str] = &["batt", "battery", "fuel gauge", "charger"];
+const BATTERY_WARNINGS: &[&str] = &[
+    "low",
+    "critical",
+    "warn",
+    "warning",
+    "over",
+    "overvoltage",
+    "overtemp",
+    "under",
+    "undervoltage",
+    "fault",
+    "shutdown",
+];
+
+const FAULT_MARKERS: &[&str] = &[
+    "fault",
+    "fail",
+    "failed",
+    "failure",
+    "error",
+    "err",
+    "timeout",
+    "not responding",
+];
+const SENSORS: &[(&str, &str)] = &[
+    ("ppg", "ppg"),
+    ("afe", "ppg"),
+    ("spo2", "spo2"),
+    ("imu", "imu"),
+    ("accel", "imu"),
+    ("accelerometer", "imu"),
+    ("gyro", "imu"),
+    ("gyroscope", "imu"),
+    ("therm", "skin_temp"),
+    ("thermistor", "skin_temp"),
+    ("skin temp", "skin_temp"),
+];
+
+impl ConsoleLogEvent {
+    pub fn parse(log: &str) -> Option<Self> {
+        let lower = log.to_ascii_lowercase();
+        let words = lower
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<_>>();
+        let contains_any = |markers: &[&str]| markers.iter().any(|m| Self::has_marker(&words, m));
+
+        if contains_any(RESET_MARKERS) {
+            return Some(Self::Reset {
+                reason: Self::reset_reason(log, &lower),
+            });
+        }
+
+        if contains_any(BATTERY_MARKERS) && contains_any(BATTERY_WARNINGS) {
+            return Some(Self::BatteryWarning);
+        }
+
+        if contains_any(FAULT_MARKERS) {
+            if let Some((_, sensor)) = SENSORS.iter().find(|(m, _)| Self::has_marker(&words, m)) {
+                return Some(Self::SensorFault { sensor });
+            }
+        }
+
+        None
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Reset { .. } => "reset",
+            Self::SensorFault { .. } => "sensor_fault",
+            Self::BatteryWarning => "battery_warning",
+        }
+    }
+
+    pub fn detail(&self) -> Option<&str> {
+        match self {
+            Self::Reset { reason } => reason.as_deref(),
+            Self::SensorFault { sensor } => Some(sensor),
+            Self::BatteryWarning => None,
+        }
+    }
+
+    /// Whether the words of `marker` appear in a row in `words`, each word
+    /// whole or followed only by digits.
+    fn has_marker(words: &[&str], marker: &str) -> bool {
+        let marker = marker.split(' ').collect::<Vec<_>>();
+        words.windows(marker.len()).any(|window| {
+            window.iter().zip(&marker).all(|(word, m)| {
+                word.strip_prefix(m)
+                    .is_some_and(|rest| rest.bytes().all(|b| b.is_ascii_digit()))
+            })
+        })
+    }
+
+    fn reset_reason(log: &str, lower: &str) -> Option<String> {
+        if let Some(idx) = lower.find("reason") {
+            let reason = log[idx + "reason".len()..]
+                .trim_start_matches([':', '=', ' '])
+                .split([',', ';', '\n'])
+                .next()
+                .unwrap_or_default()
+                .trim();
+            if !reason.is_empty() {
+                return Some(reason.to_owned());
+            }
+        }
+
+        if lower.contains("watchdog") {
+            Some("watchdog".to_owned())
+        } else if lower.contains("hardfault") || lower.contains("hard fault") {
+            Some("hard_fault".to_owned())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_reset_reason() {
+        assert_eq!(
+            ConsoleLogEvent::parse("Boot reason: WDT, count 3"),
+            Some(ConsoleLogEvent::Reset {
+                reason: Some("WDT".to_owned())
+            })
+        );
+        assert_eq!(
+            ConsoleLogEvent::parse("HardFault handler entered"),
+            Some(ConsoleLogEvent::Reset {
+                reason: Some("hard_fault".to_owned())
+            })
+        );
+    }
+
+    #[test]
+    fn parses_battery_warning_before_sensor_fault() {
+        let event = ConsoleLogEvent::parse("fuel gauge: battery temp over limit").unwrap();
+        assert_eq!(event, ConsoleLogEvent::BatteryWarning);
+        assert_eq!(event.kind(), "battery_warning");
+    }
+
+    #[test]
+    fn parses_sensor_fault() {
+        let event = ConsoleLogEvent::parse("AFE4500 read timeout").unwrap();
+        assert_eq!(event, ConsoleLogEvent::SensorFault { sensor: "ppg" });
+        assert_eq!(event.detail(), Some("ppg"));
+    }
+
+    #[test]
+    fn markers_match_whole_words() {
+        assert_eq!(ConsoleLogEvent::parse("safe mode timeout"), None);
+        assert_eq!(ConsoleLogEvent::parse("charger overall status ok"), None);
+        assert_eq!(
+            ConsoleLogEvent::parse("battery flow below limit, allow"),
+            None
+        );
+        assert_eq!(ConsoleLogEvent::parse("thermal model error"), None);
+        assert_eq!(
+            ConsoleLogEvent::parse("batt_low"),
+            Some(ConsoleLogEvent::BatteryWarning)
+        );
+        assert_eq!(
+            ConsoleLogEvent::parse("SpO2 LED fault"),
+            Some(ConsoleLogEvent::SensorFault { sensor: "spo2" })
+        );
+    }
+
+    #[test]
+    fn ignores_routine_messages() {
+        assert_eq!(ConsoleLogEvent::parse("BLE connected, mtu 247"), None);
+        assert_eq!(ConsoleLogEvent::parse("battery 87%"), None);
+    }
+}
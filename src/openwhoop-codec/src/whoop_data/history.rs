@@ -65,6 +65,8 @@ pub struct ParsedHistoryReading {
     pub imu_data: Option<Vec<ImuSample>>,
     /// Gravity vector [x, y, z] from V12/V24 packets. `None` for older packet formats.
     pub gravity: Option<[f32; 3]>,
+    /// DSP sensor fields from V12/V24 (and Gen5 K=18) packets.
+    pub sensor_data: Option<SensorData>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
@@ -77,6 +79,18 @@ pub enum Activity {
     Awake,
 }
 
+impl ImuSample {
+    /// Magnitude of the acceleration vector, in g.
+    pub fn acceleration(&self) -> f32 {
+        (self.acc_x_g.powi(2) + self.acc_y_g.powi(2) + self.acc_z_g.powi(2)).sqrt()
+    }
+
+    /// Magnitude of the angular velocity vector, in degrees per second.
+    pub fn rotation(&self) -> f32 {
+        (self.gyr_x_dps.powi(2) + self.gyr_y_dps.powi(2) + self.gyr_z_dps.powi(2)).sqrt()
+    }
+}
+
 impl HistoryReading {
     pub fn is_valid(&self) -> bool {
         self.bpm > 0
@@ -110,4 +124,18 @@ mod tests {
         };
         assert!(!reading.is_valid());
     }
+
+    #[test]
+    fn imu_sample_magnitudes() {
+        let sample = ImuSample {
+            acc_x_g: 0.0,
+            acc_y_g: 0.6,
+            acc_z_g: 0.8,
+            gyr_x_dps: 30.0,
+            gyr_y_dps: 0.0,
+            gyr_z_dps: 40.0,
+        };
+        assert!((sample.acceleration() - 1.0).abs() < 1e-6);
+        assert!((sample.rotation() - 50.0).abs() < 1e-4);
+    }
 }
@@ -0,0 +1,131 @@
+use std::{fmt, str::FromStr};
+
+use crate::{WhoopError, helpers::BufferReader};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wrist {
+    Left,
+    Right,
+}
+
+impl Wrist {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Left),
+            1 => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    pub fn as_u8(&self) -> u8 {
+        match self {
+            Self::Left => 0,
+            Self::Right => 1,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Left => "left",
+            Self::Right => "right",
+        }
+    }
+}
+
+impl fmt::Display for Wrist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Wrist {
+    type Err = WhoopError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "left" | "l" => Ok(Self::Left),
+            "right" | "r" => Ok(Self::Right),
+            _ => Err(WhoopError::InvalidData),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyLocation {
+    Wrist,
+    Bicep,
+    OffBody,
+    Unknown(u8),
+}
+
+impl BodyLocation {
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::OffBody,
+            1 => Self::Wrist,
+            2 => Self::Bicep,
+            other => Self::Unknown(other),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Wrist => "wrist",
+            Self::Bicep => "bicep",
+            Self::OffBody => "off_body",
+            Self::Unknown(_) => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for BodyLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown(value) => write!(f, "unknown ({value})"),
+            _ => f.write_str(self.as_str()),
+        }
+    }
+}
+
+impl FromStr for BodyLocation {
+    type Err = WhoopError;
+
+    /// Parses the names written by [`BodyLocation::as_str`], so stored wear
+    /// windows can be read back. `unknown` has no value to restore.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wrist" => Ok(Self::Wrist),
+            "bicep" => Ok(Self::Bicep),
+            "off_body" => Ok(Self::OffBody),
+            _ => Err(WhoopError::InvalidData),
+        }
+    }
+}
+
+/// Wear location and status from a `GetBodyLocationAndStatus` response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyLocationInfo {
+    pub location: BodyLocation,
+    /// Configured wrist; only meaningful when worn on the wrist
+    pub wrist: Option<Wrist>,
+    /// Skin contact detected by the strap
+    pub on_body: bool,
+}
+
+impl BodyLocationInfo {
+    /// Layout (offsets after the response prefix / revision byte):
+    ///   [0]     body location (0 = off body, 1 = wrist, 2 = bicep)
+    ///   [1]     wrist (0 = left, 1 = right)
+    ///   [2]     on-body flag
+    pub(crate) fn parse(mut data: Vec<u8>) -> Result<Self, WhoopError> {
+        let location = BodyLocation::from_u8(data.pop_front()?);
+        let wrist = Wrist::from_u8(data.pop_front()?);
+        let on_body = data.pop_front()? != 0;
+
+        Ok(Self {
+            location,
+            wrist,
+            on_body,
+        })
+    }
+}
@@ -0,0 +1,244 @@
+use chrono::{Days, NaiveDate, NaiveDateTime};
+use openwhoop_algos::{CalorieCalculator, Calories};
+use openwhoop_entities::calories;
+use openwhoop_migration::OnConflict;
+use openwhoop_types::activities::ActivityPeriod;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use uuid::Uuid;
+
+use crate::{DatabaseHandler, SearchHistory};
+
+impl DatabaseHandler {
+    pub async fn get_latest_calories(&self) -> anyhow::Result<Option<calories::Model>> {
+        Ok(calories::Entity::find()
+            .order_by_desc(calories::Column::Date)
+            .one(&self.db)
+            .await?)
+    }
+
+    pub async fn get_calories_for_date(
+        &self,
+        date: NaiveDate,
+    ) -> anyhow::Result<Option<calories::Model>> {
+        Ok(calories::Entity::find()
+            .filter(calories::Column::Date.eq(date))
+            .one(&self.db)
+            .await?)
+    }
+
+    /// Calculator for the period from `from` to `to`, with the resting heart
+    /// rate of the last sleep before `to` so a day includes its own night.
+    /// `None` while the profile is incomplete or there is no heart rate
+    /// baseline.
+    async fn calorie_calculator(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Option<CalorieCalculator>> {
+        let profile = self.get_profile().await?;
+        let Some(max_hr) = self.get_max_hr(from, to).await? else {
+            return Ok(None);
+        };
+        let Some(resting_hr) = self.get_resting_hr_before(to).await? else {
+            return Ok(None);
+        };
+
+        Ok(CalorieCalculator::new(
+            &profile,
+            from.date(),
+            resting_hr,
+            max_hr,
+        ))
+    }
+
+    /// Daily calories from the day after the last stored one, recalculating
+    /// the previous day as its data may have been incomplete.
+    pub async fn calculate_latest_calories(&self) -> anyhow::Result<()> {
+        let Some(first_date) = self.get_first_reading_date().await? else {
+            return Ok(());
+        };
+        let Some(last_date) = self.get_latest_reading_date().await? else {
+            return Ok(());
+        };
+
+        let next_unsaved_date = self
+            .get_latest_calories()
+            .await?
+            .map(|row| row.date.checked_add_days(Days::new(1)).unwrap_or(row.date))
+            .unwrap_or(first_date);
+        let recalc_from = last_date
+            .checked_sub_days(Days::new(1))
+            .unwrap_or(last_date);
+
+        let mut date = next_unsaved_date.min(recalc_from).max(first_date);
+        while date <= last_date {
+            let from = date.and_hms_opt(0, 0, 0).expect("valid start of day");
+            let to = date
+                .checked_add_days(Days::new(1))
+                .and_then(|next| next.and_hms_opt(0, 0, 0))
+                .expect("valid next day");
+
+            if let Some(calculator) = self.calorie_calculator(from, to).await? {
+                let history = self
+                    .search_history(SearchHistory {
+                        from: Some(from - chrono::TimeDelta::milliseconds(1)),
+                        to: Some(to),
+                        limit: None,
+                    })
+                    .await?;
+                self.create_or_update_calories(date, calculator.calculate_day(&history))
+                    .await?;
+            }
+
+            date = match date.checked_add_days(Days::new(1)) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        Ok(())
+    }
+
+    pub async fn calculate_calories_for_activity(
+        &self,
+        activity: ActivityPeriod,
+    ) -> anyhow::Result<Option<f64>> {
+        let to = match activity.to {
+            Some(to) => to,
+            None => match self.get_latest_reading_time().await? {
+                Some(to) => to,
+                None => return Ok(None),
+            },
+        };
+
+        let Some(calculator) = self.calorie_calculator(activity.from, to).await? else {
+            return Ok(None);
+        };
+
+        let history = self
+            .search_history(SearchHistory {
+                from: Some(activity.from - chrono::TimeDelta::milliseconds(1)),
+                to: activity.to,
+                limit: None,
+            })
+            .await?;
+        if history.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(calculator.calculate(&history).total))
+    }
+
+    async fn create_or_update_calories(
+        &self,
+        date: NaiveDate,
+        calories: Calories,
+    ) -> anyhow::Result<()> {
+        let model = calories::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            date: Set(date),
+            total: Set(calories.total),
+            active: Set(calories.active),
+        };
+
+        calories::Entity::insert(model)
+            .on_conflict(
+                OnConflict::column(calories::Column::Date)
+                    .update_columns([calories::Column::Total, calories::Column::Active])
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openwhoop_algos::{Sex, SleepCycle, UserProfile};
+    use openwhoop_entities::heart_rate;
+    use openwhoop_types::activities::ActivityType;
+    use sea_orm::{ActiveModelTrait, ActiveValue::NotSet};
+
+    #[tokio::test]
+    async fn calories_need_profile_and_cover_days_and_activities() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let date = NaiveDate::from_ymd_opt(2025, 6, 15).unwrap();
+
+        db.create_sleep(SleepCycle {
+            id: date,
+            start: date.pred_opt().unwrap().and_hms_opt(23, 0, 0).unwrap(),
+            end: date.and_hms_opt(7, 0, 0).unwrap(),
+            min_bpm: 55,
+            max_bpm: 70,
+            avg_bpm: 60,
+            min_hrv: 30,
+            max_hrv: 60,
+            avg_hrv: 45,
+            score: 100.0,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let activity = ActivityPeriod {
+            period_id: date,
+            from: date.and_hms_opt(10, 0, 0).unwrap(),
+            to: Some(date.and_hms_opt(10, 30, 0).unwrap()),
+            activity: ActivityType::Running,
+            strain: None,
+            calories: None,
+        };
+        for i in 0..1800 {
+            heart_rate::ActiveModel {
+                id: NotSet,
+                bpm: Set(150),
+                time: Set(activity.from + chrono::TimeDelta::seconds(i)),
+                rr_intervals: Set("400".to_string()),
+                activity: NotSet,
+                stress: NotSet,
+                stress_artifact_rate: NotSet,
+                spo2: NotSet,
+                skin_temp: NotSet,
+                imu_data: NotSet,
+                sensor_data: NotSet,
+                synced: Set(false),
+            }
+            .insert(&db.db)
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(
+            db.calculate_calories_for_activity(activity).await.unwrap(),
+            None
+        );
+        db.calculate_latest_calories().await.unwrap();
+        assert!(db.get_latest_calories().await.unwrap().is_none());
+
+        db.set_profile(UserProfile {
+            birth_date: NaiveDate::from_ymd_opt(1990, 6, 15),
+            sex: Some(Sex::Male),
+            weight_kg: Some(80.0),
+            height_cm: Some(180.0),
+            max_hr: Some(190),
+            zone_basis: None,
+        })
+        .await
+        .unwrap();
+
+        let workout = db
+            .calculate_calories_for_activity(activity)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!((workout - 448.1).abs() < 0.5, "{workout}");
+
+        db.calculate_latest_calories().await.unwrap();
+        let day = db.get_calories_for_date(date).await.unwrap().unwrap();
+        assert!((day.total - (workout + 1755.0 * 1410.0 / 1440.0)).abs() < 1.0);
+        assert!((day.active - (workout - 1755.0 / 48.0)).abs() < 1.0);
+    }
+}
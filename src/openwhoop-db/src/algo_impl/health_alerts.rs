@@ -0,0 +1,229 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use openwhoop_algos::{HealthAlert, HealthMetric, NightlyMetrics, helpers::time_math::mean};
+use openwhoop_entities::{health_alerts, heart_rate, sleep_cycles};
+use sea_orm::{
+    ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, SelectColumns, Set,
+    TransactionTrait,
+};
+use uuid::Uuid;
+
+use crate::DatabaseHandler;
+
+impl DatabaseHandler {
+    /// Nightly values of the metrics watched for health alerts, one per
+    /// sleep, oldest first. Resting HR is the night's minimum heart rate,
+    /// like [`Self::get_resting_hr_before`], and SpO2 is the average of the
+    /// night's readings.
+    pub async fn get_nightly_metrics(&self) -> anyhow::Result<Vec<NightlyMetrics>> {
+        let sleeps = sleep_cycles::Entity::find()
+            .order_by_asc(sleep_cycles::Column::Start)
+            .all(&self.db)
+            .await?;
+        let (Some(first), Some(last_end)) = (sleeps.first(), sleeps.iter().map(|s| s.end).max())
+        else {
+            return Ok(Vec::new());
+        };
+
+        let spo2: Vec<(NaiveDateTime, Option<f64>)> = heart_rate::Entity::find()
+            .filter(heart_rate::Column::Time.gte(first.start))
+            .filter(heart_rate::Column::Time.lte(last_end))
+            .filter(heart_rate::Column::Spo2.is_not_null())
+            .order_by_asc(heart_rate::Column::Time)
+            .select_only()
+            .select_column(heart_rate::Column::Time)
+            .select_column(heart_rate::Column::Spo2)
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+
+        Ok(sleeps
+            .into_iter()
+            .map(|sleep| {
+                let from = spo2.partition_point(|(time, _)| *time < sleep.start);
+                let to = spo2.partition_point(|(time, _)| *time <= sleep.end);
+                let night = spo2[from..to]
+                    .iter()
+                    .filter_map(|(_, spo2)| *spo2)
+                    .collect::<Vec<_>>();
+
+                NightlyMetrics {
+                    date: sleep.sleep_id,
+                    rhr: Some(f64::from(sleep.min_bpm)),
+                    hrv: Some(f64::from(sleep.avg_hrv)),
+                    skin_temp: sleep.skin_temp,
+                    resp_rate: sleep.resp_rate_avg,
+                    spo2: (!night.is_empty()).then(|| mean(&night)),
+                }
+            })
+            .collect())
+    }
+
+    /// Health alerts from `from` (inclusive), oldest first.
+    pub async fn get_health_alerts(
+        &self,
+        from: Option<NaiveDate>,
+    ) -> anyhow::Result<Vec<HealthAlert>> {
+        let mut query = health_alerts::Entity::find().order_by_asc(health_alerts::Column::Date);
+        if let Some(from) = from {
+            query = query.filter(health_alerts::Column::Date.gte(from));
+        }
+
+        Ok(query
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(HealthAlert::from)
+            .collect())
+    }
+
+    /// Replace all stored alerts, as changed thresholds or baselines can
+    /// drop earlier alerts as well as add new ones.
+    pub async fn replace_health_alerts(&self, alerts: &[HealthAlert]) -> anyhow::Result<()> {
+        let models = alerts
+            .iter()
+            .map(|alert| {
+                let z = |metric| Set(alert.z_score(metric));
+                health_alerts::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    date: Set(alert.date),
+                    nights: Set(i32::try_from(alert.nights).unwrap_or(i32::MAX)),
+                    rhr_z: z(HealthMetric::RestingHr),
+                    hrv_z: z(HealthMetric::Hrv),
+                    skin_temp_z: z(HealthMetric::SkinTemp),
+                    resp_rate_z: z(HealthMetric::RespRate),
+                    spo2_z: z(HealthMetric::Spo2),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let txn = self.db.begin().await?;
+        health_alerts::Entity::delete_many().exec(&txn).await?;
+        for chunk in models.chunks(500) {
+            health_alerts::Entity::insert_many(chunk.to_vec())
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openwhoop_algos::{RespiratoryRate, SleepCycle};
+    use sea_orm::{ActiveModelTrait, ActiveValue::NotSet};
+
+    #[tokio::test]
+    async fn nightly_metrics_and_alerts_round_trip() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let date = NaiveDate::from_ymd_opt(2025, 6, 15).unwrap();
+        let start = date.pred_opt().unwrap().and_hms_opt(23, 0, 0).unwrap();
+
+        db.create_sleep(SleepCycle {
+            id: date,
+            start,
+            end: date.and_hms_opt(7, 0, 0).unwrap(),
+            min_bpm: 50,
+            max_bpm: 70,
+            avg_bpm: 58,
+            min_hrv: 30,
+            max_hrv: 80,
+            avg_hrv: 61,
+            score: 100.0,
+            resp_rate: Some(RespiratoryRate {
+                min: 12.0,
+                avg: 14.5,
+                max: 17.0,
+            }),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        db.update_sleep_skin_temp(date, 33.6, None).await.unwrap();
+
+        for (hours, spo2) in [(1, Some(96.0)), (2, Some(98.0)), (3, None), (9, Some(90.0))] {
+            heart_rate::ActiveModel {
+                id: NotSet,
+                bpm: Set(58),
+                time: Set(start + chrono::TimeDelta::hours(hours)),
+                rr_intervals: Set(String::new()),
+                activity: NotSet,
+                stress: NotSet,
+                stress_artifact_rate: NotSet,
+                spo2: Set(spo2),
+                skin_temp: NotSet,
+                imu_data: NotSet,
+                sensor_data: NotSet,
+                synced: Set(false),
+            }
+            .insert(&db.db)
+            .await
+            .unwrap();
+        }
+
+        // A second night picks up only its own readings
+        let next = date.succ_opt().unwrap();
+        db.create_sleep(SleepCycle {
+            id: next,
+            start: date.and_hms_opt(23, 0, 0).unwrap(),
+            end: next.and_hms_opt(7, 0, 0).unwrap(),
+            min_bpm: 52,
+            max_bpm: 72,
+            avg_bpm: 60,
+            min_hrv: 30,
+            max_hrv: 80,
+            avg_hrv: 59,
+            score: 100.0,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(
+            db.get_nightly_metrics().await.unwrap(),
+            vec![
+                NightlyMetrics {
+                    date,
+                    rhr: Some(50.0),
+                    hrv: Some(61.0),
+                    skin_temp: Some(33.6),
+                    resp_rate: Some(14.5),
+                    spo2: Some(97.0),
+                },
+                NightlyMetrics {
+                    date: next,
+                    rhr: Some(52.0),
+                    hrv: Some(59.0),
+                    skin_temp: None,
+                    resp_rate: None,
+                    spo2: None,
+                }
+            ]
+        );
+
+        let alert = |day: u32, nights: usize| HealthAlert {
+            date: NaiveDate::from_ymd_opt(2025, 6, day).unwrap(),
+            nights,
+            deviations: vec![(HealthMetric::RestingHr, 3.1), (HealthMetric::Hrv, -2.4)],
+        };
+        db.replace_health_alerts(&[alert(10, 2), alert(11, 3)])
+            .await
+            .unwrap();
+        db.replace_health_alerts(&[alert(11, 2), alert(12, 3)])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            db.get_health_alerts(None).await.unwrap(),
+            vec![alert(11, 2), alert(12, 3)]
+        );
+        assert_eq!(
+            db.get_health_alerts(NaiveDate::from_ymd_opt(2025, 6, 12))
+                .await
+                .unwrap(),
+            vec![alert(12, 3)]
+        );
+    }
+}
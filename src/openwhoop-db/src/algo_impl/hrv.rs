@@ -0,0 +1,250 @@
+use chrono::NaiveDateTime;
+use openwhoop_algos::{HrvCalculator, HrvMetrics, HrvSample};
+use openwhoop_entities::{hrv_samples, job_progress};
+use openwhoop_migration::OnConflict;
+use sea_orm::{
+    ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, SelectColumns, Set,
+    TransactionTrait,
+};
+use uuid::Uuid;
+
+use crate::{DatabaseHandler, SearchHistory};
+
+const HRV_SAMPLES_JOB: &str = "hrv_samples";
+
+impl DatabaseHandler {
+    /// HRV of the RR intervals of the readings matching `options`. `None`
+    /// with fewer than [`HrvCalculator::MIN_INTERVALS`] intervals.
+    pub async fn get_hrv(&self, options: SearchHistory) -> anyhow::Result<Option<HrvMetrics>> {
+        let history = self.search_history(options).await?;
+        Ok(HrvCalculator::from_history(&history))
+    }
+
+    pub async fn last_hrv_sample_start(&self) -> anyhow::Result<Option<NaiveDateTime>> {
+        Ok(hrv_samples::Entity::find()
+            .order_by_desc(hrv_samples::Column::Start)
+            .select_only()
+            .select_column(hrv_samples::Column::Start)
+            .into_tuple()
+            .one(&self.db)
+            .await?)
+    }
+
+    /// Start of the last window the HRV sample job scanned, stored or not.
+    pub async fn hrv_samples_processed_until(&self) -> anyhow::Result<Option<NaiveDateTime>> {
+        Ok(job_progress::Entity::find_by_id(HRV_SAMPLES_JOB)
+            .one(&self.db)
+            .await?
+            .map(|progress| progress.processed_until))
+    }
+
+    pub async fn set_hrv_samples_processed_until(&self, time: NaiveDateTime) -> anyhow::Result<()> {
+        let model = job_progress::ActiveModel {
+            job: Set(HRV_SAMPLES_JOB.to_owned()),
+            processed_until: Set(time),
+        };
+        job_progress::Entity::insert(model)
+            .on_conflict(
+                OnConflict::column(job_progress::Column::Job)
+                    .update_column(job_progress::Column::ProcessedUntil)
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Store HRV samples, replacing earlier samples of the same windows.
+    pub async fn create_hrv_samples(&self, samples: &[HrvSample]) -> anyhow::Result<()> {
+        let models = samples
+            .iter()
+            .map(|sample| hrv_samples::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                start: Set(sample.start),
+                beats: Set(i32::try_from(sample.beats).unwrap_or(i32::MAX)),
+                mean_nn: Set(sample.mean_nn),
+                sdnn: Set(sample.sdnn),
+                rmssd: Set(sample.rmssd),
+                artifact_rate: Set(sample.artifact_rate),
+                coverage: Set(sample.coverage),
+            })
+            .collect::<Vec<_>>();
+
+        let txn = self.db.begin().await?;
+        for chunk in models.chunks(500) {
+            hrv_samples::Entity::insert_many(chunk.to_vec())
+                .on_conflict(
+                    OnConflict::column(hrv_samples::Column::Start)
+                        .update_columns([
+                            hrv_samples::Column::Beats,
+                            hrv_samples::Column::MeanNn,
+                            hrv_samples::Column::Sdnn,
+                            hrv_samples::Column::Rmssd,
+                            hrv_samples::Column::ArtifactRate,
+                            hrv_samples::Column::Coverage,
+                        ])
+                        .to_owned(),
+                )
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// HRV samples whose window starts in `[from, to)`, oldest first. With
+    /// `reliable_only`, windows with too many artifacts or too little signal
+    /// are left out.
+    pub async fn get_hrv_samples(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        reliable_only: bool,
+    ) -> anyhow::Result<Vec<HrvSample>> {
+        let mut query = hrv_samples::Entity::find()
+            .filter(hrv_samples::Column::Start.gte(from))
+            .filter(hrv_samples::Column::Start.lt(to));
+        if reliable_only {
+            query = query
+                .filter(hrv_samples::Column::ArtifactRate.lte(HrvSample::MAX_ARTIFACT_RATE))
+                .filter(hrv_samples::Column::Coverage.gte(HrvSample::MIN_COVERAGE));
+        }
+
+        Ok(query
+            .order_by_asc(hrv_samples::Column::Start)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(HrvSample::from)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use openwhoop_entities::heart_rate;
+    use sea_orm::{ActiveModelTrait, ActiveValue::NotSet, Set};
+
+    #[tokio::test]
+    async fn hrv_for_time_range() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let start = NaiveDate::from_ymd_opt(2025, 6, 15)
+            .unwrap()
+            .and_hms_opt(3, 0, 0)
+            .unwrap();
+
+        for i in 0..60 {
+            let rr = if i % 2 == 0 { "800" } else { "860" };
+            heart_rate::ActiveModel {
+                id: NotSet,
+                bpm: Set(72),
+                time: Set(start + chrono::TimeDelta::seconds(i)),
+                rr_intervals: Set(rr.to_string()),
+                activity: NotSet,
+                stress: NotSet,
+                stress_artifact_rate: NotSet,
+                spo2: NotSet,
+                skin_temp: NotSet,
+                imu_data: NotSet,
+                sensor_data: NotSet,
+                synced: Set(false),
+            }
+            .insert(&db.db)
+            .await
+            .unwrap();
+        }
+
+        let all = db.get_hrv(SearchHistory::default()).await.unwrap().unwrap();
+        assert_eq!(all.count, 60);
+        assert_eq!(all.rmssd, 60.0);
+        assert_eq!(all.mean_nn, 830.0);
+
+        let too_short = db
+            .get_hrv(SearchHistory {
+                from: Some(start),
+                to: Some(start + chrono::TimeDelta::seconds(20)),
+                limit: None,
+            })
+            .await
+            .unwrap();
+        assert!(too_short.is_none());
+    }
+
+    #[tokio::test]
+    async fn hrv_samples_round_trip() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        assert!(db.last_hrv_sample_start().await.unwrap().is_none());
+
+        let start = NaiveDate::from_ymd_opt(2025, 6, 15)
+            .unwrap()
+            .and_hms_opt(14, 0, 0)
+            .unwrap();
+        let sample = |minutes: i64, rmssd: f64, coverage: f64| HrvSample {
+            start: start + chrono::TimeDelta::minutes(minutes),
+            beats: 300,
+            mean_nn: 1000.0,
+            sdnn: 50.0,
+            rmssd,
+            artifact_rate: 0.5,
+            coverage,
+        };
+
+        db.create_hrv_samples(&[sample(0, 40.0, 100.0), sample(5, 45.0, 30.0)])
+            .await
+            .unwrap();
+        // Recalculated window replaces the stored one
+        db.create_hrv_samples(&[sample(5, 50.0, 100.0), sample(10, 35.0, 20.0)])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            db.last_hrv_sample_start().await.unwrap(),
+            Some(start + chrono::TimeDelta::minutes(10))
+        );
+
+        let end = start + chrono::TimeDelta::hours(1);
+        let all = db.get_hrv_samples(start, end, false).await.unwrap();
+        assert_eq!(
+            all,
+            vec![
+                sample(0, 40.0, 100.0),
+                sample(5, 50.0, 100.0),
+                sample(10, 35.0, 20.0)
+            ]
+        );
+
+        let reliable = db.get_hrv_samples(start, end, true).await.unwrap();
+        assert_eq!(reliable.len(), 2);
+        assert!(reliable.iter().all(HrvSample::is_reliable));
+
+        let later = start + chrono::TimeDelta::minutes(5);
+        assert_eq!(
+            db.get_hrv_samples(later, end, false).await.unwrap().len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn hrv_samples_processed_until_is_replaced() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        assert_eq!(db.hrv_samples_processed_until().await.unwrap(), None);
+
+        let first = chrono::NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(8, 0, 0)
+            .unwrap();
+        let second = first + chrono::TimeDelta::hours(1);
+        db.set_hrv_samples_processed_until(first).await.unwrap();
+        db.set_hrv_samples_processed_until(second).await.unwrap();
+
+        assert_eq!(
+            db.hrv_samples_processed_until().await.unwrap(),
+            Some(second)
+        );
+        // Scanning history stores no samples by itself
+        assert_eq!(db.last_hrv_sample_start().await.unwrap(), None);
+    }
+}
@@ -1,3 +1,14 @@
+mod calories;
+mod health_alerts;
+mod hrv;
+mod profile;
+mod recovery;
+mod resp_rate;
+mod skin_temp;
 mod sleep;
+mod spo2;
+mod steps;
 mod strain;
 mod stress;
+mod vo2max;
+mod zones;
@@ -0,0 +1,101 @@
+use chrono::NaiveDateTime;
+use openwhoop_algos::UserProfile;
+use openwhoop_entities::profile;
+use openwhoop_migration::OnConflict;
+use sea_orm::{EntityTrait, Set};
+
+use crate::DatabaseHandler;
+
+/// The profile is a single row.
+const PROFILE_ID: i32 = 1;
+
+impl DatabaseHandler {
+    /// The stored profile, empty when none was set.
+    pub async fn get_profile(&self) -> anyhow::Result<UserProfile> {
+        Ok(profile::Entity::find_by_id(PROFILE_ID)
+            .one(&self.db)
+            .await?
+            .map(UserProfile::from)
+            .unwrap_or_default())
+    }
+
+    pub async fn set_profile(&self, profile: UserProfile) -> anyhow::Result<()> {
+        let model = profile::ActiveModel {
+            id: Set(PROFILE_ID),
+            birth_date: Set(profile.birth_date),
+            sex: Set(profile.sex.map(|sex| sex.as_str().to_owned())),
+            weight_kg: Set(profile.weight_kg),
+            height_cm: Set(profile.height_cm),
+            max_hr: Set(profile.max_hr.map(i32::from)),
+            zone_basis: Set(profile.zone_basis.map(|basis| basis.as_str().to_owned())),
+        };
+
+        profile::Entity::insert(model)
+            .on_conflict(
+                OnConflict::column(profile::Column::Id)
+                    .update_columns([
+                        profile::Column::BirthDate,
+                        profile::Column::Sex,
+                        profile::Column::WeightKg,
+                        profile::Column::HeightCm,
+                        profile::Column::MaxHr,
+                        profile::Column::ZoneBasis,
+                    ])
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Max heart rate for calculations at `before`: the configured value,
+    /// else the age prediction, else the highest sustained heart rate seen
+    /// (see [`Self::get_max_hr_before`]).
+    pub async fn get_max_hr(
+        &self,
+        before: NaiveDateTime,
+        fallback_to: NaiveDateTime,
+    ) -> anyhow::Result<Option<u8>> {
+        match self.get_profile().await?.max_hr_on(before.date()) {
+            Some(max_hr) => Ok(Some(max_hr)),
+            None => self.get_max_hr_before(before, fallback_to).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use openwhoop_algos::{Sex, ZoneBasis};
+
+    #[tokio::test]
+    async fn profile_round_trip_and_max_hr() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        assert_eq!(db.get_profile().await.unwrap(), UserProfile::default());
+
+        let now = NaiveDate::from_ymd_opt(2025, 6, 15)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        assert_eq!(db.get_max_hr(now, now).await.unwrap(), None);
+
+        let mut profile = UserProfile {
+            birth_date: NaiveDate::from_ymd_opt(1990, 6, 15),
+            sex: Some(Sex::Female),
+            weight_kg: Some(62.5),
+            height_cm: Some(168.0),
+            max_hr: None,
+            zone_basis: Some(ZoneBasis::MaxHeartRate),
+        };
+        db.set_profile(profile).await.unwrap();
+        assert_eq!(db.get_profile().await.unwrap(), profile);
+        assert_eq!(db.get_max_hr(now, now).await.unwrap(), Some(184));
+
+        profile.max_hr = Some(190);
+        db.set_profile(profile).await.unwrap();
+        assert_eq!(db.get_profile().await.unwrap(), profile);
+        assert_eq!(db.get_max_hr(now, now).await.unwrap(), Some(190));
+    }
+}
@@ -0,0 +1,129 @@
+use chrono::NaiveDate;
+use openwhoop_algos::RecoveryScore;
+use openwhoop_entities::recovery;
+use openwhoop_migration::OnConflict;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use uuid::Uuid;
+
+use crate::DatabaseHandler;
+
+impl DatabaseHandler {
+    pub async fn get_latest_recovery(&self) -> anyhow::Result<Option<RecoveryScore>> {
+        Ok(recovery::Entity::find()
+            .order_by_desc(recovery::Column::Date)
+            .one(&self.db)
+            .await?
+            .map(RecoveryScore::from))
+    }
+
+    pub async fn get_recovery_for_date(
+        &self,
+        date: NaiveDate,
+    ) -> anyhow::Result<Option<RecoveryScore>> {
+        Ok(recovery::Entity::find()
+            .filter(recovery::Column::Date.eq(date))
+            .one(&self.db)
+            .await?
+            .map(RecoveryScore::from))
+    }
+
+    /// Recovery scores from `from` (inclusive), oldest first.
+    pub async fn get_recoveries(
+        &self,
+        from: Option<NaiveDate>,
+    ) -> anyhow::Result<Vec<RecoveryScore>> {
+        let mut query = recovery::Entity::find().order_by_asc(recovery::Column::Date);
+        if let Some(from) = from {
+            query = query.filter(recovery::Column::Date.gte(from));
+        }
+
+        Ok(query
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(RecoveryScore::from)
+            .collect())
+    }
+
+    pub async fn create_or_update_recovery(&self, recovery: RecoveryScore) -> anyhow::Result<()> {
+        let model = recovery::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            date: Set(recovery.date),
+            score: Set(recovery.score),
+            hrv_score: Set(recovery.hrv),
+            rhr_score: Set(recovery.rhr),
+            sleep_score: Set(recovery.sleep),
+            resp_rate_score: Set(recovery.resp_rate),
+            skin_temp_score: Set(recovery.skin_temp),
+        };
+
+        recovery::Entity::insert(model)
+            .on_conflict(
+                OnConflict::column(recovery::Column::Date)
+                    .update_columns([
+                        recovery::Column::Score,
+                        recovery::Column::HrvScore,
+                        recovery::Column::RhrScore,
+                        recovery::Column::SleepScore,
+                        recovery::Column::RespRateScore,
+                        recovery::Column::SkinTempScore,
+                    ])
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recovery(day: u32, score: f64) -> RecoveryScore {
+        RecoveryScore {
+            date: NaiveDate::from_ymd_opt(2025, 1, day).unwrap(),
+            score,
+            hrv: 60.0,
+            rhr: 55.0,
+            sleep: 80.0,
+            resp_rate: None,
+            skin_temp: Some(90.0),
+        }
+    }
+
+    #[tokio::test]
+    async fn recovery_upsert_and_query() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        assert!(db.get_latest_recovery().await.unwrap().is_none());
+
+        db.create_or_update_recovery(recovery(1, 40.0))
+            .await
+            .unwrap();
+        db.create_or_update_recovery(recovery(2, 50.0))
+            .await
+            .unwrap();
+        db.create_or_update_recovery(recovery(2, 70.0))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            db.get_latest_recovery().await.unwrap(),
+            Some(recovery(2, 70.0))
+        );
+        assert_eq!(
+            db.get_recovery_for_date(recovery(1, 0.0).date)
+                .await
+                .unwrap(),
+            Some(recovery(1, 40.0))
+        );
+        assert_eq!(db.get_recoveries(None).await.unwrap().len(), 2);
+        assert_eq!(
+            db.get_recoveries(Some(recovery(2, 0.0).date))
+                .await
+                .unwrap(),
+            vec![recovery(2, 70.0)]
+        );
+    }
+}
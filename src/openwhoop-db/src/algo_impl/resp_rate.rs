@@ -0,0 +1,97 @@
+use chrono::NaiveDate;
+use openwhoop_algos::RespiratoryRate;
+use openwhoop_entities::sleep_cycles;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, sea_query::Expr};
+
+use crate::DatabaseHandler;
+
+impl DatabaseHandler {
+    pub async fn get_sleeps_without_resp_rate(&self) -> anyhow::Result<Vec<sleep_cycles::Model>> {
+        Ok(sleep_cycles::Entity::find()
+            .filter(sleep_cycles::Column::RespRateAvg.is_null())
+            .order_by_asc(sleep_cycles::Column::Start)
+            .all(&self.db)
+            .await?)
+    }
+
+    pub async fn update_sleep_resp_rate(
+        &self,
+        sleep_id: NaiveDate,
+        resp_rate: RespiratoryRate,
+    ) -> anyhow::Result<()> {
+        sleep_cycles::Entity::update_many()
+            .filter(sleep_cycles::Column::SleepId.eq(sleep_id))
+            .col_expr(
+                sleep_cycles::Column::RespRateMin,
+                Expr::value(resp_rate.min),
+            )
+            .col_expr(
+                sleep_cycles::Column::RespRateAvg,
+                Expr::value(resp_rate.avg),
+            )
+            .col_expr(
+                sleep_cycles::Column::RespRateMax,
+                Expr::value(resp_rate.max),
+            )
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use openwhoop_algos::SleepCycle;
+
+    #[tokio::test]
+    async fn sleeps_without_resp_rate_are_backfilled() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+
+        let first_end = NaiveDate::from_ymd_opt(2025, 1, 2)
+            .unwrap()
+            .and_hms_opt(6, 0, 0)
+            .unwrap();
+        for day in 0..2 {
+            let end = first_end + TimeDelta::days(day);
+            db.create_sleep(SleepCycle {
+                id: end.date(),
+                start: end - TimeDelta::hours(8),
+                end,
+                min_bpm: 50,
+                max_bpm: 70,
+                avg_bpm: 60,
+                min_hrv: 30,
+                max_hrv: 80,
+                avg_hrv: 55,
+                score: 100.0,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        }
+        assert_eq!(db.get_sleeps_without_resp_rate().await.unwrap().len(), 2);
+
+        let resp_rate = RespiratoryRate {
+            min: 12.5,
+            avg: 14.0,
+            max: 16.0,
+        };
+        db.update_sleep_resp_rate(first_end.date(), resp_rate)
+            .await
+            .unwrap();
+
+        let pending = db.get_sleeps_without_resp_rate().await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].sleep_id, (first_end + TimeDelta::days(1)).date());
+
+        let sleep = db
+            .get_sleep_for_date(first_end.date())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(sleep.resp_rate, Some(resp_rate));
+    }
+}
@@ -0,0 +1,243 @@
+use crate::DatabaseHandler;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use openwhoop_algos::{SkinTempDeviation, SkinTempReading};
+use openwhoop_entities::{heart_rate, sleep_cycles};
+use sea_orm::{
+    ActiveValue::NotSet, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    SelectColumns, Set, Unchanged, sea_query::Expr,
+};
+
+impl DatabaseHandler {
+    pub async fn last_skin_temp_time(&self) -> anyhow::Result<Option<NaiveDateTime>> {
+        let reading = heart_rate::Entity::find()
+            .filter(heart_rate::Column::SkinTemp.is_not_null())
+            .order_by_desc(heart_rate::Column::Time)
+            .select_only()
+            .select_column(heart_rate::Column::Time)
+            .into_tuple()
+            .one(&self.db)
+            .await?;
+
+        Ok(reading)
+    }
+
+    pub async fn update_skin_temp_on_reading(
+        &self,
+        reading: SkinTempReading,
+    ) -> anyhow::Result<()> {
+        let model = heart_rate::ActiveModel {
+            id: NotSet,
+            bpm: NotSet,
+            time: Unchanged(reading.time),
+            rr_intervals: NotSet,
+            activity: NotSet,
+            stress: NotSet,
+            stress_artifact_rate: NotSet,
+            spo2: NotSet,
+            skin_temp: Set(Some(reading.celsius)),
+            imu_data: NotSet,
+            sensor_data: NotSet,
+            synced: NotSet,
+        };
+
+        heart_rate::Entity::update_many()
+            .filter(heart_rate::Column::Time.eq(reading.time))
+            .set(model)
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Converted skin temperatures in `[from, to]`.
+    pub async fn get_skin_temps(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Vec<f64>> {
+        Ok(heart_rate::Entity::find()
+            .filter(heart_rate::Column::Time.gte(from))
+            .filter(heart_rate::Column::Time.lte(to))
+            .filter(heart_rate::Column::SkinTemp.is_not_null())
+            .order_by_asc(heart_rate::Column::Time)
+            .select_only()
+            .select_column(heart_rate::Column::SkinTemp)
+            .into_tuple()
+            .all(&self.db)
+            .await?)
+    }
+
+    pub async fn get_sleeps_without_skin_temp(&self) -> anyhow::Result<Vec<sleep_cycles::Model>> {
+        Ok(sleep_cycles::Entity::find()
+            .filter(sleep_cycles::Column::SkinTemp.is_null())
+            .order_by_asc(sleep_cycles::Column::Start)
+            .all(&self.db)
+            .await?)
+    }
+
+    /// Nightly skin temperatures of the sleeps before `sleep_id`, oldest
+    /// first, limited to the baseline window.
+    pub async fn get_skin_temp_baseline(&self, sleep_id: NaiveDate) -> anyhow::Result<Vec<f64>> {
+        let mut nights: Vec<f64> = sleep_cycles::Entity::find()
+            .filter(sleep_cycles::Column::SleepId.lt(sleep_id))
+            .filter(sleep_cycles::Column::SkinTemp.is_not_null())
+            .order_by_desc(sleep_cycles::Column::SleepId)
+            .limit(u64::try_from(SkinTempDeviation::BASELINE_NIGHTS)?)
+            .select_only()
+            .select_column(sleep_cycles::Column::SkinTemp)
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+
+        nights.reverse();
+        Ok(nights)
+    }
+
+    pub async fn get_sleep_skin_temp_deviation(
+        &self,
+        sleep_id: NaiveDate,
+    ) -> anyhow::Result<Option<f64>> {
+        let deviation: Option<Option<f64>> = sleep_cycles::Entity::find()
+            .filter(sleep_cycles::Column::SleepId.eq(sleep_id))
+            .select_only()
+            .select_column(sleep_cycles::Column::SkinTempDeviation)
+            .into_tuple()
+            .one(&self.db)
+            .await?;
+
+        Ok(deviation.flatten())
+    }
+
+    pub async fn update_sleep_skin_temp(
+        &self,
+        sleep_id: NaiveDate,
+        skin_temp: f64,
+        deviation: Option<f64>,
+    ) -> anyhow::Result<()> {
+        sleep_cycles::Entity::update_many()
+            .filter(sleep_cycles::Column::SleepId.eq(sleep_id))
+            .col_expr(sleep_cycles::Column::SkinTemp, Expr::value(skin_temp))
+            .col_expr(
+                sleep_cycles::Column::SkinTempDeviation,
+                Expr::value(deviation),
+            )
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use openwhoop_algos::SleepCycle;
+
+    #[tokio::test]
+    async fn last_skin_temp_time_empty() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        assert!(db.last_skin_temp_time().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn update_skin_temp_on_reading_integration() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+
+        for i in 0..3 {
+            db.create_reading(openwhoop_codec::HistoryReading {
+                unix: 1735689600000 + i * 1000,
+                bpm: 60,
+                rr: vec![],
+                imu_data: vec![],
+                sensor_data: None,
+            })
+            .await
+            .unwrap();
+        }
+
+        let history = db
+            .search_history(crate::SearchHistory::default())
+            .await
+            .unwrap();
+        let (first, last) = (history[0].time, history[2].time);
+
+        db.update_skin_temp_on_reading(SkinTempReading {
+            time: first,
+            celsius: 33.5,
+        })
+        .await
+        .unwrap();
+        db.update_skin_temp_on_reading(SkinTempReading {
+            time: last,
+            celsius: 34.5,
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(db.last_skin_temp_time().await.unwrap(), Some(last));
+        assert_eq!(
+            db.get_skin_temps(first, last).await.unwrap(),
+            vec![33.5, 34.5]
+        );
+    }
+
+    #[tokio::test]
+    async fn sleep_skin_temp_baseline() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+
+        let first_end = chrono::NaiveDate::from_ymd_opt(2025, 1, 2)
+            .unwrap()
+            .and_hms_opt(6, 0, 0)
+            .unwrap();
+        for day in 0..3 {
+            let end = first_end + TimeDelta::days(day);
+            db.create_sleep(SleepCycle {
+                id: end.date(),
+                start: end - TimeDelta::hours(8),
+                end,
+                min_bpm: 50,
+                max_bpm: 70,
+                avg_bpm: 60,
+                min_hrv: 30,
+                max_hrv: 80,
+                avg_hrv: 55,
+                score: 100.0,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(db.get_sleeps_without_skin_temp().await.unwrap().len(), 3);
+
+        db.update_sleep_skin_temp(first_end.date(), 33.0, None)
+            .await
+            .unwrap();
+        db.update_sleep_skin_temp((first_end + TimeDelta::days(1)).date(), 33.4, Some(0.4))
+            .await
+            .unwrap();
+
+        let pending = db.get_sleeps_without_skin_temp().await.unwrap();
+        assert_eq!(pending.len(), 1);
+
+        let baseline = db
+            .get_skin_temp_baseline(pending[0].sleep_id)
+            .await
+            .unwrap();
+        assert_eq!(baseline, vec![33.0, 33.4]);
+        assert_eq!(
+            db.get_sleep_skin_temp_deviation((first_end + TimeDelta::days(1)).date())
+                .await
+                .unwrap(),
+            Some(0.4)
+        );
+        assert_eq!(
+            db.get_sleep_skin_temp_deviation(pending[0].sleep_id)
+                .await
+                .unwrap(),
+            None
+        );
+    }
+}
@@ -1,7 +1,10 @@
-use chrono::{NaiveDateTime, TimeDelta};
-use openwhoop_algos::SleepCycle;
-use openwhoop_entities::sleep_cycles;
-use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder};
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+use openwhoop_algos::{RespiratoryRate, SleepCycle, SleepScore, SleepStageTotals};
+use openwhoop_entities::{activities, health_alerts, recovery, sleep_cycles};
+use sea_orm::{
+    ColumnTrait, Condition, DatabaseTransaction, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait, sea_query::Expr,
+};
 
 use crate::{DailyStats, DailyStatsAverage, DatabaseHandler};
 
@@ -22,6 +25,59 @@ impl DatabaseHandler {
             .collect())
     }
 
+    /// Sleeps overlapping `[from, to]`, oldest first.
+    pub async fn get_sleeps_between(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Vec<SleepCycle>> {
+        Ok(sleep_cycles::Entity::find()
+            .filter(sleep_cycles::Column::Start.lte(to))
+            .filter(sleep_cycles::Column::End.gte(from))
+            .order_by_asc(sleep_cycles::Column::Start)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(map_sleep_cycle)
+            .collect())
+    }
+
+    /// Move the sleep `from` to the date `to`. Its stages and activities
+    /// follow through the cascading foreign keys, and the recovery and
+    /// health alerts derived from either night are cleared.
+    pub async fn rename_sleep(&self, from: NaiveDate, to: NaiveDate) -> anyhow::Result<()> {
+        let txn = self.db.begin().await?;
+        sleep_cycles::Entity::update_many()
+            .col_expr(sleep_cycles::Column::SleepId, Expr::value(to))
+            .filter(sleep_cycles::Column::SleepId.eq(from))
+            .exec(&txn)
+            .await?;
+        clear_derived_rows(&txn, from, to).await?;
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// Fold the sleep `from` into the sleep `into`: its activities move over
+    /// and the sleep, with its stages, is deleted. The recovery and health
+    /// alerts derived from either night are cleared.
+    pub async fn merge_sleep_into(&self, from: NaiveDate, into: NaiveDate) -> anyhow::Result<()> {
+        let txn = self.db.begin().await?;
+        activities::Entity::update_many()
+            .col_expr(activities::Column::PeriodId, Expr::value(into))
+            .filter(activities::Column::PeriodId.eq(from))
+            .exec(&txn)
+            .await?;
+        sleep_cycles::Entity::delete_many()
+            .filter(sleep_cycles::Column::SleepId.eq(from))
+            .exec(&txn)
+            .await?;
+        clear_derived_rows(&txn, from, into).await?;
+        txn.commit().await?;
+
+        Ok(())
+    }
+
     pub async fn get_latest_daily_stats(&self) -> anyhow::Result<Option<DailyStats>> {
         let Some(sleep) = self.get_latest_sleep().await? else {
             return Ok(None);
@@ -35,10 +91,18 @@ impl DatabaseHandler {
             return Ok(DailyStatsAverage::MissingDays(7));
         };
 
-        let window_start = latest_sleep.id - TimeDelta::days(6);
+        self.get_7_day_daily_stats_average(latest_sleep.id).await
+    }
+
+    /// Average of the 7 nights ending with the sleep woken up from on `last_day`.
+    pub async fn get_7_day_daily_stats_average(
+        &self,
+        last_day: NaiveDate,
+    ) -> anyhow::Result<DailyStatsAverage> {
+        let window_start = last_day - TimeDelta::days(6);
         let sleeps = sleep_cycles::Entity::find()
             .filter(sleep_cycles::Column::SleepId.gte(window_start))
-            .filter(sleep_cycles::Column::SleepId.lte(latest_sleep.id))
+            .filter(sleep_cycles::Column::SleepId.lte(last_day))
             .order_by_asc(sleep_cycles::Column::SleepId)
             .all(&self.db)
             .await?;
@@ -58,7 +122,7 @@ impl DatabaseHandler {
         if !contiguous_days {
             let distinct_dates = sleeps
                 .iter()
-                .filter(|sleep| sleep.sleep_id >= window_start && sleep.sleep_id <= latest_sleep.id)
+                .filter(|sleep| sleep.sleep_id >= window_start && sleep.sleep_id <= last_day)
                 .count();
             let missing = 7usize.saturating_sub(distinct_dates);
             return Ok(DailyStatsAverage::MissingDays(
@@ -68,6 +132,7 @@ impl DatabaseHandler {
 
         let mut hrv_sum = 0.0;
         let mut rhr_sum = 0.0;
+        let mut resp_rates = Vec::new();
 
         for sleep in sleeps.into_iter().map(map_sleep_cycle) {
             let stats = self.get_daily_stats_for_sleep(sleep).await?;
@@ -77,11 +142,14 @@ impl DatabaseHandler {
             if let Some(rhr) = stats.rhr {
                 rhr_sum += rhr;
             }
+            resp_rates.extend(stats.resp_rate);
         }
 
         Ok(DailyStatsAverage::Average(DailyStats {
             hrv: Some(hrv_sum / 7.0),
             rhr: Some(rhr_sum / 7.0),
+            resp_rate: (!resp_rates.is_empty())
+                .then(|| resp_rates.iter().sum::<f64>() / resp_rates.len() as f64),
         }))
     }
 
@@ -89,10 +157,33 @@ impl DatabaseHandler {
         Ok(DailyStats {
             hrv: Some(f64::from(sleep.avg_hrv)),
             rhr: Some(f64::from(sleep.avg_bpm)),
+            resp_rate: sleep.resp_rate.map(|rate| rate.avg),
         })
     }
 }
 
+/// Delete the rows keyed by the dates of two changed nights, which have no
+/// foreign key to follow the sleep. Recovery is deleted from the earlier
+/// night on, as later nights use it as their baseline and the recovery job
+/// resumes from the latest stored day. Health alerts are all replaced on
+/// their next calculation.
+async fn clear_derived_rows(
+    txn: &DatabaseTransaction,
+    a: NaiveDate,
+    b: NaiveDate,
+) -> anyhow::Result<()> {
+    recovery::Entity::delete_many()
+        .filter(recovery::Column::Date.gte(a.min(b)))
+        .exec(txn)
+        .await?;
+    health_alerts::Entity::delete_many()
+        .filter(health_alerts::Column::Date.is_in([a, b]))
+        .exec(txn)
+        .await?;
+
+    Ok(())
+}
+
 fn map_sleep_cycle(value: sleep_cycles::Model) -> SleepCycle {
     SleepCycle {
         id: value.sleep_id,
@@ -107,6 +198,24 @@ fn map_sleep_cycle(value: sleep_cycles::Model) -> SleepCycle {
         score: value
             .score
             .unwrap_or(SleepCycle::sleep_score(value.start, value.end)),
+        resp_rate: RespiratoryRate::from_columns(
+            value.resp_rate_min,
+            value.resp_rate_avg,
+            value.resp_rate_max,
+        ),
+        stages: SleepStageTotals::from_columns(
+            value.wake_seconds,
+            value.light_seconds,
+            value.deep_seconds,
+            value.rem_seconds,
+        ),
+        score_breakdown: SleepScore::from_columns(
+            value.score_duration,
+            value.score_efficiency,
+            value.score_disturbances,
+            value.score_consistency,
+        ),
+        rr_artifact_rate: value.rr_artifact_rate,
     }
 }
 
@@ -136,6 +245,20 @@ mod tests {
             avg_hrv: 55,
             score: Some(95.0),
             synced: false,
+            skin_temp: None,
+            skin_temp_deviation: None,
+            resp_rate_min: None,
+            resp_rate_avg: None,
+            resp_rate_max: None,
+            wake_seconds: None,
+            light_seconds: None,
+            deep_seconds: None,
+            rem_seconds: None,
+            score_duration: None,
+            score_efficiency: None,
+            score_disturbances: None,
+            score_consistency: None,
+            rr_artifact_rate: None,
         };
 
         let cycle = map_sleep_cycle(model);
@@ -165,6 +288,20 @@ mod tests {
             avg_hrv: 55,
             score: None, // No score stored
             synced: false,
+            skin_temp: None,
+            skin_temp_deviation: None,
+            resp_rate_min: None,
+            resp_rate_avg: None,
+            resp_rate_max: None,
+            wake_seconds: None,
+            light_seconds: None,
+            deep_seconds: None,
+            rem_seconds: None,
+            score_duration: None,
+            score_efficiency: None,
+            score_disturbances: None,
+            score_consistency: None,
+            rr_artifact_rate: None,
         };
 
         let cycle = map_sleep_cycle(model);
@@ -203,6 +340,7 @@ mod tests {
             max_hrv: 80,
             avg_hrv: 55,
             score: 100.0,
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -212,6 +350,78 @@ mod tests {
         assert_eq!(cycles[0].min_bpm, 50);
     }
 
+    #[tokio::test]
+    async fn sleep_cycle_resp_rate_round_trips() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+
+        let end = NaiveDate::from_ymd_opt(2025, 1, 2)
+            .unwrap()
+            .and_hms_opt(6, 0, 0)
+            .unwrap();
+        let resp_rate = RespiratoryRate {
+            min: 12.5,
+            avg: 14.0,
+            max: 16.5,
+        };
+
+        db.create_sleep(SleepCycle {
+            id: end.date(),
+            start: end - TimeDelta::hours(8),
+            end,
+            min_bpm: 50,
+            max_bpm: 70,
+            avg_bpm: 60,
+            min_hrv: 30,
+            max_hrv: 80,
+            avg_hrv: 55,
+            score: 100.0,
+            resp_rate: Some(resp_rate),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let cycles = db.get_sleep_cycles(None).await.unwrap();
+        assert_eq!(cycles[0].resp_rate, Some(resp_rate));
+    }
+
+    #[tokio::test]
+    async fn sleep_cycle_rr_artifact_rate_round_trips() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+
+        let end = NaiveDate::from_ymd_opt(2025, 1, 2)
+            .unwrap()
+            .and_hms_opt(6, 0, 0)
+            .unwrap();
+
+        db.create_sleep(SleepCycle {
+            id: end.date(),
+            start: end - TimeDelta::hours(8),
+            end,
+            min_bpm: 50,
+            max_bpm: 70,
+            avg_bpm: 60,
+            min_hrv: 30,
+            max_hrv: 80,
+            avg_hrv: 55,
+            score: 100.0,
+            resp_rate: None,
+            stages: None,
+            score_breakdown: None,
+            rr_artifact_rate: Some(2.5),
+        })
+        .await
+        .unwrap();
+
+        let cycles = db.get_sleep_cycles(None).await.unwrap();
+        assert_eq!(cycles[0].rr_artifact_rate, Some(2.5));
+        let cycles = db
+            .get_sleeps_between(end - TimeDelta::hours(1), end)
+            .await
+            .unwrap();
+        assert_eq!(cycles[0].rr_artifact_rate, Some(2.5));
+    }
+
     #[tokio::test]
     async fn get_sleep_cycles_with_start_filter() {
         let db = DatabaseHandler::new("sqlite::memory:").await;
@@ -238,6 +448,7 @@ mod tests {
                 max_hrv: 80,
                 avg_hrv: 55,
                 score: 100.0,
+                ..Default::default()
             })
             .await
             .unwrap();
@@ -251,4 +462,181 @@ mod tests {
         let cycles = db.get_sleep_cycles(Some(filter_start)).await.unwrap();
         assert_eq!(cycles.len(), 1); // Only the Jan 3 sleep
     }
+
+    #[tokio::test]
+    async fn rename_and_merge_sleeps_keep_activities() {
+        use openwhoop_types::activities::{ActivityPeriod, ActivityType, SearchActivityPeriods};
+
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+
+        let sleep = |end: NaiveDateTime, hours: i64| SleepCycle {
+            id: end.date(),
+            start: end - TimeDelta::hours(hours),
+            end,
+            min_bpm: 50,
+            max_bpm: 70,
+            avg_bpm: 60,
+            min_hrv: 30,
+            max_hrv: 80,
+            avg_hrv: 55,
+            score: 100.0,
+            ..Default::default()
+        };
+        let first_end = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(23, 30, 0)
+            .unwrap();
+        let first = sleep(first_end, 2);
+        let second = sleep(first_end + TimeDelta::hours(7), 6);
+        db.create_sleep(first).await.unwrap();
+        db.create_sleep(second).await.unwrap();
+        db.create_activity(ActivityPeriod {
+            period_id: first.id,
+            from: first.start - TimeDelta::hours(3),
+            to: Some(first.start - TimeDelta::hours(2)),
+            activity: ActivityType::Nap,
+            strain: None,
+            calories: None,
+        })
+        .await
+        .unwrap();
+
+        let near = db
+            .get_sleeps_between(first.end, first.end + TimeDelta::minutes(30))
+            .await
+            .unwrap();
+        assert_eq!(near.len(), 1);
+        assert_eq!(near[0].id, first.id);
+
+        let renamed = first.id + TimeDelta::days(2);
+        db.rename_sleep(first.id, renamed).await.unwrap();
+        let activities = db
+            .search_activities(SearchActivityPeriods::default())
+            .await
+            .unwrap();
+        assert_eq!(activities[0].period_id, renamed);
+
+        db.merge_sleep_into(renamed, second.id).await.unwrap();
+        let cycles = db.get_sleep_cycles(None).await.unwrap();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].id, second.id);
+        let activities = db
+            .search_activities(SearchActivityPeriods::default())
+            .await
+            .unwrap();
+        assert_eq!(activities[0].period_id, second.id);
+    }
+
+    #[tokio::test]
+    async fn rename_and_merge_sleeps_clear_recovery_and_alerts() {
+        use openwhoop_algos::{HealthAlert, HealthMetric, RecoveryScore};
+
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let day = |day| NaiveDate::from_ymd_opt(2025, 1, day).unwrap();
+
+        for id in [day(2), day(4)] {
+            let end = id.and_hms_opt(6, 0, 0).unwrap();
+            db.create_sleep(SleepCycle {
+                id,
+                start: end - TimeDelta::hours(8),
+                end,
+                min_bpm: 50,
+                max_bpm: 70,
+                avg_bpm: 60,
+                min_hrv: 30,
+                max_hrv: 80,
+                avg_hrv: 55,
+                score: 100.0,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        }
+        for date in 1..=4 {
+            db.create_or_update_recovery(RecoveryScore {
+                date: day(date),
+                score: 50.0,
+                hrv: 50.0,
+                rhr: 50.0,
+                sleep: 50.0,
+                resp_rate: None,
+                skin_temp: None,
+            })
+            .await
+            .unwrap();
+        }
+        let alert = |date| HealthAlert {
+            date,
+            nights: 2,
+            deviations: vec![(HealthMetric::RestingHr, 3.0)],
+        };
+        db.replace_health_alerts(&[alert(day(2)), alert(day(4))])
+            .await
+            .unwrap();
+
+        // Recovery from the renamed night on is calculated again
+        db.rename_sleep(day(2), day(3)).await.unwrap();
+        assert_eq!(
+            db.get_latest_recovery().await.unwrap().map(|r| r.date),
+            Some(day(1))
+        );
+        assert_eq!(
+            db.get_health_alerts(None).await.unwrap(),
+            vec![alert(day(4))]
+        );
+
+        db.merge_sleep_into(day(3), day(4)).await.unwrap();
+        assert!(db.get_health_alerts(None).await.unwrap().is_empty());
+        assert!(db.get_recovery_for_date(day(1)).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn daily_stats_average_needs_seven_nights_before_the_day() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+
+        for day in 1..=8 {
+            let end = NaiveDate::from_ymd_opt(2025, 1, day)
+                .unwrap()
+                .and_hms_opt(6, 0, 0)
+                .unwrap();
+            db.create_sleep(SleepCycle {
+                id: end.date(),
+                start: end - TimeDelta::hours(8),
+                end,
+                min_bpm: 50,
+                max_bpm: 70,
+                avg_bpm: 60,
+                min_hrv: 30,
+                max_hrv: 80,
+                avg_hrv: u16::try_from(day).unwrap() * 10,
+                score: 100.0,
+                resp_rate: (day % 2 == 0).then_some(RespiratoryRate {
+                    min: 13.0,
+                    avg: 14.0,
+                    max: 15.0,
+                }),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        }
+
+        let day = |day| NaiveDate::from_ymd_opt(2025, 1, day).unwrap();
+        assert_eq!(
+            db.get_7_day_daily_stats_average(day(5)).await.unwrap(),
+            DailyStatsAverage::MissingDays(2)
+        );
+        assert_eq!(
+            db.get_7_day_daily_stats_average(day(7)).await.unwrap(),
+            DailyStatsAverage::Average(DailyStats {
+                hrv: Some(40.0),
+                rhr: Some(60.0),
+                resp_rate: Some(14.0),
+            })
+        );
+        assert_eq!(
+            db.get_last_7_day_daily_stats_average().await.unwrap(),
+            db.get_7_day_daily_stats_average(day(8)).await.unwrap()
+        );
+    }
 }
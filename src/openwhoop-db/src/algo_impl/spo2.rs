@@ -0,0 +1,88 @@
+use crate::DatabaseHandler;
+
+use chrono::NaiveDateTime;
+use openwhoop_algos::SpO2Score;
+use openwhoop_entities::heart_rate;
+use sea_orm::{
+    ActiveValue::NotSet, ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect,
+    SelectColumns, Set, Unchanged,
+};
+
+impl DatabaseHandler {
+    pub async fn last_spo2_time(&self) -> anyhow::Result<Option<NaiveDateTime>> {
+        let reading = heart_rate::Entity::find()
+            .filter(heart_rate::Column::Spo2.is_not_null())
+            .order_by_desc(heart_rate::Column::Time)
+            .select_only()
+            .select_column(heart_rate::Column::Time)
+            .into_tuple()
+            .one(&self.db)
+            .await?;
+
+        Ok(reading)
+    }
+
+    pub async fn update_spo2_on_reading(&self, spo2: SpO2Score) -> anyhow::Result<()> {
+        let model = heart_rate::ActiveModel {
+            id: NotSet,
+            bpm: NotSet,
+            time: Unchanged(spo2.time),
+            rr_intervals: NotSet,
+            activity: NotSet,
+            stress: NotSet,
+            stress_artifact_rate: NotSet,
+            spo2: Set(Some(spo2.spo2)),
+            skin_temp: NotSet,
+            imu_data: NotSet,
+            sensor_data: NotSet,
+            synced: NotSet,
+        };
+
+        heart_rate::Entity::update_many()
+            .filter(heart_rate::Column::Time.eq(spo2.time))
+            .set(model)
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn last_spo2_time_empty() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let result = db.last_spo2_time().await.unwrap();
+        assert!(result.is_none());
+    }
+
+    #[tokio::test]
+    async fn update_spo2_on_reading_integration() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+
+        let reading = openwhoop_codec::HistoryReading {
+            unix: 1735689600000,
+            bpm: 72,
+            rr: vec![833],
+            imu_data: vec![],
+            sensor_data: None,
+        };
+        db.create_reading(reading).await.unwrap();
+
+        let history = db
+            .search_history(crate::SearchHistory::default())
+            .await
+            .unwrap();
+        let time = history[0].time;
+
+        let spo2 = SpO2Score { time, spo2: 97.0 };
+        db.update_spo2_on_reading(spo2).await.unwrap();
+
+        let last_spo2 = db.last_spo2_time().await.unwrap();
+        assert!(last_spo2.is_some());
+        assert_eq!(last_spo2.unwrap(), time);
+    }
+}
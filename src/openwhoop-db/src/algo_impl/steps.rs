@@ -0,0 +1,118 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use openwhoop_algos::StepMinute;
+use openwhoop_entities::steps;
+use openwhoop_migration::OnConflict;
+use sea_orm::{
+    ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, SelectColumns, Set,
+    TransactionTrait,
+};
+use uuid::Uuid;
+
+use crate::DatabaseHandler;
+
+impl DatabaseHandler {
+    pub async fn last_step_minute(&self) -> anyhow::Result<Option<NaiveDateTime>> {
+        Ok(steps::Entity::find()
+            .order_by_desc(steps::Column::Minute)
+            .select_only()
+            .select_column(steps::Column::Minute)
+            .into_tuple()
+            .one(&self.db)
+            .await?)
+    }
+
+    /// Store per-minute step counts, replacing earlier counts of the same
+    /// minutes.
+    pub async fn create_step_minutes(&self, minutes: &[StepMinute]) -> anyhow::Result<()> {
+        let models = minutes
+            .iter()
+            .map(|minute| steps::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                minute: Set(minute.minute),
+                steps: Set(i32::from(minute.steps)),
+                estimated: Set(minute.estimated),
+            })
+            .collect::<Vec<_>>();
+
+        let txn = self.db.begin().await?;
+        for chunk in models.chunks(500) {
+            steps::Entity::insert_many(chunk.to_vec())
+                .on_conflict(
+                    OnConflict::column(steps::Column::Minute)
+                        .update_columns([steps::Column::Steps, steps::Column::Estimated])
+                        .to_owned(),
+                )
+                .exec(&txn)
+                .await?;
+        }
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// Steps taken on `date`, or `None` when there is no step data for it.
+    pub async fn get_steps_for_date(&self, date: NaiveDate) -> anyhow::Result<Option<u32>> {
+        let day_start = date.and_hms_opt(0, 0, 0).unwrap();
+        let day_end = date.succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap();
+
+        let minutes: Vec<i32> = steps::Entity::find()
+            .filter(steps::Column::Minute.gte(day_start))
+            .filter(steps::Column::Minute.lt(day_end))
+            .select_only()
+            .select_column(steps::Column::Steps)
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+
+        if minutes.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(
+            minutes
+                .into_iter()
+                .map(|steps| u32::try_from(steps).unwrap_or(0))
+                .sum(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minute(hour: u32, min: u32, steps: u16) -> StepMinute {
+        StepMinute {
+            minute: NaiveDate::from_ymd_opt(2025, 1, 1)
+                .unwrap()
+                .and_hms_opt(hour, min, 0)
+                .unwrap(),
+            steps,
+            estimated: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn step_minutes_upsert_and_daily_total() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let date = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        assert!(db.last_step_minute().await.unwrap().is_none());
+        assert!(db.get_steps_for_date(date).await.unwrap().is_none());
+
+        db.create_step_minutes(&[minute(8, 0, 100), minute(8, 1, 20), minute(23, 59, 0)])
+            .await
+            .unwrap();
+        db.create_step_minutes(&[minute(8, 1, 50)]).await.unwrap();
+
+        assert_eq!(db.get_steps_for_date(date).await.unwrap(), Some(150));
+        assert_eq!(
+            db.last_step_minute().await.unwrap(),
+            Some(minute(23, 59, 0).minute)
+        );
+        assert!(
+            db.get_steps_for_date(date.succ_opt().unwrap())
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -8,6 +8,12 @@ use uuid::Uuid;
 
 use crate::{ActivityHeartRateStats, DatabaseHandler, SearchHistory};
 
+/// Readings above the observed max heart rate that are ignored, so a short
+/// artifact spike does not set it.
+const MAX_HR_SPIKE_READINGS: u64 = 30;
+/// Sleeps whose minimum heart rates are combined into the resting heart rate.
+const RESTING_HR_NIGHTS: u64 = 7;
+
 impl DatabaseHandler {
     pub async fn get_latest_strain(&self) -> anyhow::Result<Option<strain::Model>> {
         Ok(strain::Entity::find()
@@ -54,7 +60,7 @@ impl DatabaseHandler {
                 })
                 .await?;
 
-            let Some(max_hr) = self.get_max_hr_before(from, to).await? else {
+            let Some(max_hr) = self.get_max_hr(from, to).await? else {
                 date = match date.checked_add_days(Days::new(1)) {
                     Some(next) => next,
                     None => break,
@@ -104,7 +110,7 @@ impl DatabaseHandler {
             })
             .await?;
 
-        let Some(max_hr) = self.get_max_hr_before(activity.from, to).await? else {
+        let Some(max_hr) = self.get_max_hr(activity.from, to).await? else {
             return Ok(None);
         };
 
@@ -180,14 +186,14 @@ impl DatabaseHandler {
             .ok_or_else(|| anyhow::anyhow!("strain row missing after upsert"))
     }
 
-    async fn get_first_reading_date(&self) -> anyhow::Result<Option<NaiveDate>> {
+    pub(crate) async fn get_first_reading_date(&self) -> anyhow::Result<Option<NaiveDate>> {
         Ok(self
             .get_boundary_reading_time(true)
             .await?
             .map(|time| time.date()))
     }
 
-    async fn get_latest_reading_date(&self) -> anyhow::Result<Option<NaiveDate>> {
+    pub(crate) async fn get_latest_reading_date(&self) -> anyhow::Result<Option<NaiveDate>> {
         Ok(self
             .get_boundary_reading_time(false)
             .await?
@@ -209,7 +215,10 @@ impl DatabaseHandler {
         Ok(query.into_tuple().one(&self.db).await?)
     }
 
-    async fn get_max_hr_before(
+    /// Highest sustained heart rate recorded before `before`, or up to
+    /// `fallback_to` when there is no earlier data. The top
+    /// [`MAX_HR_SPIKE_READINGS`] readings are skipped as possible artifacts.
+    pub async fn get_max_hr_before(
         &self,
         before: NaiveDateTime,
         fallback_to: NaiveDateTime,
@@ -227,7 +236,7 @@ impl DatabaseHandler {
         from: Option<NaiveDateTime>,
         to: NaiveDateTime,
     ) -> anyhow::Result<Option<i16>> {
-        let max_hr: Option<i16> = heart_rate::Entity::find()
+        let top: Vec<i16> = heart_rate::Entity::find()
             .select_only()
             .filter({
                 let mut condition = sea_orm::Condition::all().add(heart_rate::Column::Time.lt(to));
@@ -238,22 +247,31 @@ impl DatabaseHandler {
             })
             .column(heart_rate::Column::Bpm)
             .order_by_desc(heart_rate::Column::Bpm)
-            .limit(1)
+            .limit(MAX_HR_SPIKE_READINGS + 1)
             .into_tuple()
-            .one(&self.db)
+            .all(&self.db)
             .await?;
 
-        Ok(max_hr)
+        Ok(top.last().copied())
     }
 
-    async fn get_resting_hr_before(&self, before: NaiveDateTime) -> anyhow::Result<Option<u8>> {
-        let latest_sleep = sleep_cycles::Entity::find()
+    /// Median of the minimum heart rates of the last sleeps that ended
+    /// before `before`, so one night with a low artifact does not skew it.
+    pub async fn get_resting_hr_before(&self, before: NaiveDateTime) -> anyhow::Result<Option<u8>> {
+        let mut minimums: Vec<i16> = sleep_cycles::Entity::find()
             .filter(sleep_cycles::Column::End.lt(before))
             .order_by_desc(sleep_cycles::Column::End)
-            .one(&self.db)
+            .limit(RESTING_HR_NIGHTS)
+            .select_only()
+            .column(sleep_cycles::Column::MinBpm)
+            .into_tuple()
+            .all(&self.db)
             .await?;
+        minimums.sort_unstable();
 
-        Ok(latest_sleep.and_then(|sleep| u8::try_from(sleep.min_bpm).ok()))
+        Ok(minimums
+            .get(minimums.len() / 2)
+            .and_then(|&bpm| u8::try_from(bpm).ok()))
     }
 }
 
@@ -278,6 +296,7 @@ mod tests {
             to: Some(from + chrono::TimeDelta::seconds(duration_secs)),
             activity: openwhoop_types::activities::ActivityType::Running,
             strain: None,
+            calories: None,
         }
     }
 
@@ -298,6 +317,7 @@ mod tests {
             max_hrv: 60,
             avg_hrv: 45,
             score: 100.0,
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -308,6 +328,7 @@ mod tests {
             to: None,
             activity: openwhoop_types::activities::ActivityType::Running,
             strain: None,
+            calories: None,
         };
 
         for i in 1..=600 {
@@ -318,6 +339,7 @@ mod tests {
                 rr_intervals: Set("800".to_string()),
                 activity: NotSet,
                 stress: NotSet,
+                stress_artifact_rate: NotSet,
                 spo2: NotSet,
                 skin_temp: NotSet,
                 imu_data: Set(Some(serde_json::to_value(Vec::<u8>::new()).unwrap())),
@@ -354,6 +376,7 @@ mod tests {
             max_hrv: 60,
             avg_hrv: 45,
             score: 100.0,
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -368,6 +391,7 @@ mod tests {
                 rr_intervals: Set("800".to_string()),
                 activity: NotSet,
                 stress: NotSet,
+                stress_artifact_rate: NotSet,
                 spo2: NotSet,
                 skin_temp: NotSet,
                 imu_data: Set(Some(serde_json::to_value(Vec::<u8>::new()).unwrap())),
@@ -402,6 +426,7 @@ mod tests {
                 rr_intervals: Set("800".to_string()),
                 activity: NotSet,
                 stress: NotSet,
+                stress_artifact_rate: NotSet,
                 spo2: NotSet,
                 skin_temp: NotSet,
                 imu_data: Set(Some(serde_json::to_value(Vec::<u8>::new()).unwrap())),
@@ -435,6 +460,7 @@ mod tests {
             to: None,
             activity: openwhoop_types::activities::ActivityType::Running,
             strain: None,
+            calories: None,
         };
 
         for (offset_secs, bpm) in [(1, 95), (2, 105), (3, 125)] {
@@ -445,6 +471,7 @@ mod tests {
                 rr_intervals: Set("800".to_string()),
                 activity: NotSet,
                 stress: NotSet,
+                stress_artifact_rate: NotSet,
                 spo2: NotSet,
                 skin_temp: NotSet,
                 imu_data: Set(Some(serde_json::to_value(Vec::<u8>::new()).unwrap())),
@@ -466,4 +493,55 @@ mod tests {
         assert_eq!(stats.max_hr, 125);
         assert!((stats.avg_hr - 108.333_333_333_333_33).abs() < 1e-9);
     }
+
+    #[tokio::test]
+    async fn max_and_resting_hr_ignore_artifacts() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let date = NaiveDate::from_ymd_opt(2025, 1, 10).unwrap();
+        let from = date.and_hms_opt(10, 0, 0).unwrap();
+
+        // A sustained 150 bpm effort with a few spikes from a loose strap
+        for i in 0..100 {
+            let bpm = if i % 20 == 0 { 250 } else { 150 };
+            heart_rate::ActiveModel {
+                id: NotSet,
+                bpm: Set(bpm),
+                time: Set(from + chrono::TimeDelta::seconds(i)),
+                rr_intervals: Set("400".to_string()),
+                activity: NotSet,
+                stress: NotSet,
+                stress_artifact_rate: NotSet,
+                spo2: NotSet,
+                skin_temp: NotSet,
+                imu_data: NotSet,
+                sensor_data: NotSet,
+                synced: Set(false),
+            }
+            .insert(&db.db)
+            .await
+            .unwrap();
+        }
+        let after = from + chrono::TimeDelta::hours(1);
+        assert_eq!(db.get_max_hr_before(after, after).await.unwrap(), Some(150));
+
+        for (day, min_bpm) in [(1, 50), (2, 52), (3, 31)] {
+            let id = NaiveDate::from_ymd_opt(2025, 1, day).unwrap();
+            db.create_sleep(SleepCycle {
+                id,
+                start: id.and_hms_opt(0, 0, 0).unwrap(),
+                end: id.and_hms_opt(7, 0, 0).unwrap(),
+                min_bpm,
+                max_bpm: 70,
+                avg_bpm: 60,
+                min_hrv: 30,
+                max_hrv: 60,
+                avg_hrv: 45,
+                score: 100.0,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        }
+        assert_eq!(db.get_resting_hr_before(from).await.unwrap(), Some(50));
+    }
 }
@@ -30,6 +30,7 @@ impl DatabaseHandler {
             rr_intervals: NotSet,
             activity: NotSet,
             stress: Set(Some(stress.score)),
+            stress_artifact_rate: Set(stress.artifact_rate),
             spo2: NotSet,
             skin_temp: NotSet,
             imu_data: NotSet,
@@ -77,11 +78,24 @@ mod tests {
             .unwrap();
         let time = history[0].time;
 
-        let stress = StressScore { time, score: 5.5 };
+        let stress = StressScore {
+            time,
+            score: 5.5,
+            artifact_rate: Some(4.0),
+        };
         db.update_stress_on_reading(stress).await.unwrap();
 
         let last_stress = db.last_stress_time().await.unwrap();
         assert!(last_stress.is_some());
         assert_eq!(last_stress.unwrap(), time);
+
+        let stored = heart_rate::Entity::find()
+            .filter(heart_rate::Column::Time.eq(time))
+            .one(&db.db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.stress, Some(5.5));
+        assert_eq!(stored.stress_artifact_rate, Some(4.0));
     }
 }
@@ -0,0 +1,324 @@
+use chrono::{Days, NaiveDate, NaiveDateTime, TimeDelta};
+use openwhoop_algos::{Vo2MaxEstimate, Vo2MaxEstimator};
+use openwhoop_entities::{heart_rate, vo2max};
+use openwhoop_migration::OnConflict;
+use openwhoop_types::activities::{ActivityType, SearchActivityPeriods};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set};
+use uuid::Uuid;
+
+use crate::{DatabaseHandler, SearchHistory};
+
+impl DatabaseHandler {
+    pub async fn get_latest_vo2max(&self) -> anyhow::Result<Option<Vo2MaxEstimate>> {
+        Ok(vo2max::Entity::find()
+            .order_by_desc(vo2max::Column::Week)
+            .one(&self.db)
+            .await?
+            .map(Vo2MaxEstimate::from))
+    }
+
+    /// Weekly estimates for weeks starting on or after `from`, oldest first.
+    pub async fn get_vo2max(&self, from: Option<NaiveDate>) -> anyhow::Result<Vec<Vo2MaxEstimate>> {
+        let mut query = vo2max::Entity::find().order_by_asc(vo2max::Column::Week);
+        if let Some(from) = from {
+            query = query.filter(vo2max::Column::Week.gte(from));
+        }
+
+        Ok(query
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(Vo2MaxEstimate::from)
+            .collect())
+    }
+
+    /// Weekly VO2max from the week of the last stored estimate, which is
+    /// recalculated as its data may have been incomplete. Weeks without
+    /// readings, or without a resting or max heart rate, are skipped.
+    pub async fn calculate_latest_vo2max(&self) -> anyhow::Result<()> {
+        let Some(first_date) = self.get_first_reading_date().await? else {
+            return Ok(());
+        };
+        let Some(last_date) = self.get_latest_reading_date().await? else {
+            return Ok(());
+        };
+
+        let profile = self.get_profile().await?;
+        let mut week = self
+            .get_latest_vo2max()
+            .await?
+            .map(|estimate| estimate.week)
+            .unwrap_or_else(|| Vo2MaxEstimator::week_start(first_date));
+
+        while week <= last_date {
+            let Some(next_week) = week.checked_add_days(Days::new(7)) else {
+                break;
+            };
+            let from = week.and_hms_opt(0, 0, 0).expect("valid start of week");
+            let to = next_week.and_hms_opt(0, 0, 0).expect("valid end of week");
+            if !self.has_readings_between(from, to).await? {
+                week = next_week;
+                continue;
+            }
+
+            let resting_hr = self.get_resting_hr_before(to).await?;
+            let max_hr = self.get_max_hr(to, to).await?;
+            let estimator = resting_hr.zip(max_hr).and_then(|(resting_hr, max_hr)| {
+                Vo2MaxEstimator::new(resting_hr, max_hr, &profile)
+            });
+
+            if let Some(estimator) = estimator {
+                let mut submaximal = Vec::new();
+                for activity in self
+                    .search_activities(SearchActivityPeriods {
+                        from: Some(from - TimeDelta::milliseconds(1)),
+                        to: Some(to),
+                        activity: None,
+                    })
+                    .await?
+                    .into_iter()
+                    .filter(|a| matches!(a.activity, ActivityType::Running | ActivityType::Walking))
+                {
+                    let history = self
+                        .search_history(SearchHistory {
+                            from: Some(activity.from - TimeDelta::milliseconds(1)),
+                            to: activity.to.map(|to| to + TimeDelta::milliseconds(1)),
+                            limit: None,
+                        })
+                        .await?;
+                    submaximal.extend(estimator.submaximal(&history));
+                }
+
+                self.create_or_update_vo2max(estimator.estimate(week, &submaximal))
+                    .await?;
+            }
+
+            week = next_week;
+        }
+
+        Ok(())
+    }
+
+    async fn has_readings_between(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<bool> {
+        let reading: Option<i32> = heart_rate::Entity::find()
+            .select_only()
+            .column(heart_rate::Column::Id)
+            .filter(heart_rate::Column::Time.gte(from))
+            .filter(heart_rate::Column::Time.lt(to))
+            .into_tuple()
+            .one(&self.db)
+            .await?;
+
+        Ok(reading.is_some())
+    }
+
+    async fn create_or_update_vo2max(&self, estimate: Vo2MaxEstimate) -> anyhow::Result<()> {
+        let model = vo2max::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            week: Set(estimate.week),
+            vo2max: Set(estimate.vo2max),
+            uth: Set(estimate.uth),
+            submaximal: Set(estimate.submaximal),
+            submaximal_minutes: Set(i32::try_from(estimate.submaximal_minutes).unwrap_or(i32::MAX)),
+            resting_hr: Set(i32::from(estimate.resting_hr)),
+            max_hr: Set(i32::from(estimate.max_hr)),
+        };
+
+        vo2max::Entity::insert(model)
+            .on_conflict(
+                OnConflict::column(vo2max::Column::Week)
+                    .update_columns([
+                        vo2max::Column::Vo2max,
+                        vo2max::Column::Uth,
+                        vo2max::Column::Submaximal,
+                        vo2max::Column::SubmaximalMinutes,
+                        vo2max::Column::RestingHr,
+                        vo2max::Column::MaxHr,
+                    ])
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openwhoop_algos::{SleepCycle, UserProfile};
+    use openwhoop_codec::ImuSample;
+    use openwhoop_entities::heart_rate;
+    use openwhoop_types::activities::ActivityPeriod;
+    use sea_orm::ActiveValue::NotSet;
+
+    #[tokio::test]
+    async fn weekly_vo2max_uses_runs() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        // Wednesday
+        let date = NaiveDate::from_ymd_opt(2025, 3, 5).unwrap();
+
+        db.create_sleep(SleepCycle {
+            id: date,
+            start: date.pred_opt().unwrap().and_hms_opt(23, 0, 0).unwrap(),
+            end: date.and_hms_opt(6, 0, 0).unwrap(),
+            min_bpm: 50,
+            max_bpm: 70,
+            avg_bpm: 58,
+            min_hrv: 30,
+            max_hrv: 80,
+            avg_hrv: 61,
+            score: 100.0,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        db.set_profile(UserProfile {
+            height_cm: Some(180.0),
+            max_hr: Some(190),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        // 20-minute run at 165 steps/min and 155 bpm
+        let start = date.and_hms_opt(7, 0, 0).unwrap();
+        let readings = (0..20 * 60)
+            .map(|second| {
+                let imu = (0..100)
+                    .map(|i| {
+                        let t = f64::from(second) + f64::from(i) / 100.0;
+                        let phase = (2.0 * std::f64::consts::PI * 165.0 / 60.0 * t).sin();
+                        ImuSample {
+                            acc_x_g: 0.0,
+                            acc_y_g: 0.0,
+                            acc_z_g: 1.0 + 0.5 * phase as f32,
+                            gyr_x_dps: 0.0,
+                            gyr_y_dps: 0.0,
+                            gyr_z_dps: 0.0,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                heart_rate::ActiveModel {
+                    id: NotSet,
+                    bpm: Set(155),
+                    time: Set(start + TimeDelta::seconds(i64::from(second))),
+                    rr_intervals: Set(String::new()),
+                    activity: NotSet,
+                    stress: NotSet,
+                    stress_artifact_rate: NotSet,
+                    spo2: NotSet,
+                    skin_temp: NotSet,
+                    imu_data: Set(Some(serde_json::to_value(imu).unwrap())),
+                    sensor_data: NotSet,
+                    synced: Set(false),
+                }
+            })
+            .collect::<Vec<_>>();
+        for chunk in readings.chunks(500) {
+            heart_rate::Entity::insert_many(chunk.to_vec())
+                .exec(&db.db)
+                .await
+                .unwrap();
+        }
+
+        db.calculate_latest_vo2max().await.unwrap();
+        let uth_only = db.get_latest_vo2max().await.unwrap().unwrap();
+        assert_eq!(uth_only.week, NaiveDate::from_ymd_opt(2025, 3, 3).unwrap());
+        assert_eq!(uth_only.vo2max, uth_only.uth);
+
+        db.create_activity(ActivityPeriod {
+            period_id: date,
+            from: start,
+            to: Some(start + TimeDelta::minutes(20)),
+            activity: ActivityType::Running,
+            strain: None,
+            calories: None,
+        })
+        .await
+        .unwrap();
+        db.calculate_latest_vo2max().await.unwrap();
+
+        let estimates = db.get_vo2max(None).await.unwrap();
+        assert_eq!(estimates.len(), 1);
+        let estimate = estimates[0];
+        assert_eq!((estimate.resting_hr, estimate.max_hr), (50, 190));
+        assert!(estimate.submaximal_minutes >= Vo2MaxEstimator::MIN_SUBMAXIMAL_MINUTES);
+        assert!(estimate.vo2max < estimate.uth);
+        assert!(
+            db.get_vo2max(NaiveDate::from_ymd_opt(2025, 3, 10))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn weeks_without_readings_are_skipped() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let date = NaiveDate::from_ymd_opt(2025, 3, 5).unwrap();
+        db.create_sleep(SleepCycle {
+            id: date,
+            start: date.pred_opt().unwrap().and_hms_opt(23, 0, 0).unwrap(),
+            end: date.and_hms_opt(6, 0, 0).unwrap(),
+            min_bpm: 50,
+            max_bpm: 70,
+            avg_bpm: 58,
+            min_hrv: 30,
+            max_hrv: 80,
+            avg_hrv: 61,
+            score: 100.0,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        // 2000 readings at 120 bpm with one 220 bpm spike in the first week,
+        // nothing in the second and one reading in the third
+        let start = date.and_hms_opt(7, 0, 0).unwrap();
+        let reading = |time, bpm| heart_rate::ActiveModel {
+            id: NotSet,
+            bpm: Set(bpm),
+            time: Set(time),
+            rr_intervals: Set(String::new()),
+            activity: NotSet,
+            stress: NotSet,
+            stress_artifact_rate: NotSet,
+            spo2: NotSet,
+            skin_temp: NotSet,
+            imu_data: NotSet,
+            sensor_data: NotSet,
+            synced: Set(false),
+        };
+        let mut readings = (0..2000)
+            .map(|second| reading(start + TimeDelta::seconds(second), 120))
+            .collect::<Vec<_>>();
+        readings.push(reading(start + TimeDelta::hours(1), 220));
+        readings.push(reading(start + TimeDelta::days(14), 100));
+        for chunk in readings.chunks(500) {
+            heart_rate::Entity::insert_many(chunk.to_vec())
+                .exec(&db.db)
+                .await
+                .unwrap();
+        }
+
+        db.calculate_latest_vo2max().await.unwrap();
+
+        let estimates = db.get_vo2max(None).await.unwrap();
+        assert_eq!(
+            estimates.iter().map(|e| e.week).collect::<Vec<_>>(),
+            [
+                NaiveDate::from_ymd_opt(2025, 3, 3).unwrap(),
+                NaiveDate::from_ymd_opt(2025, 3, 17).unwrap()
+            ]
+        );
+        // The spike is skipped as an artifact
+        assert_eq!(estimates[0].max_hr, 120);
+        assert_eq!(estimates[1].max_hr, 120);
+    }
+}
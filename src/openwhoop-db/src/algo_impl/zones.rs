@@ -0,0 +1,316 @@
+use chrono::{Days, NaiveDate, NaiveDateTime};
+use openwhoop_algos::{HeartRateZones, ZoneBreakdown};
+use openwhoop_entities::{activities, heart_rate_zones};
+use openwhoop_migration::OnConflict;
+use openwhoop_types::activities::ActivityPeriod;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set, sea_query::Expr};
+use uuid::Uuid;
+
+use crate::{DatabaseHandler, SearchHistory, type_impl::activities::map_activity_period};
+
+impl DatabaseHandler {
+    pub async fn get_latest_zones(&self) -> anyhow::Result<Option<heart_rate_zones::Model>> {
+        Ok(heart_rate_zones::Entity::find()
+            .order_by_desc(heart_rate_zones::Column::Date)
+            .one(&self.db)
+            .await?)
+    }
+
+    pub async fn get_zones_for_date(
+        &self,
+        date: NaiveDate,
+    ) -> anyhow::Result<Option<ZoneBreakdown>> {
+        Ok(heart_rate_zones::Entity::find()
+            .filter(heart_rate_zones::Column::Date.eq(date))
+            .one(&self.db)
+            .await?
+            .map(ZoneBreakdown::from))
+    }
+
+    /// Zones for the period from `from` to `to`, on the profile's basis with
+    /// the heart rate limits of that time. `None` without a heart rate
+    /// baseline.
+    async fn heart_rate_zones(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Option<HeartRateZones>> {
+        let basis = self.get_profile().await?.zone_basis.unwrap_or_default();
+        let Some(max_hr) = self.get_max_hr(from, to).await? else {
+            return Ok(None);
+        };
+        let Some(resting_hr) = self.get_resting_hr_before(to).await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(HeartRateZones::new(max_hr, resting_hr, basis)))
+    }
+
+    /// Time in each heart rate zone for the readings matching `options`.
+    /// `None` when there are no readings or no heart rate baseline.
+    pub async fn get_zone_breakdown(
+        &self,
+        options: SearchHistory,
+    ) -> anyhow::Result<Option<ZoneBreakdown>> {
+        let history = self.search_history(options).await?;
+        let (Some(first), Some(last)) = (history.first(), history.last()) else {
+            return Ok(None);
+        };
+
+        Ok(self
+            .heart_rate_zones(first.time, last.time)
+            .await?
+            .and_then(|zones| zones.breakdown(&history)))
+    }
+
+    /// Daily zones from the day after the last stored one, recalculating the
+    /// previous day as its data may have been incomplete.
+    pub async fn calculate_latest_zones(&self) -> anyhow::Result<()> {
+        let Some(first_date) = self.get_first_reading_date().await? else {
+            return Ok(());
+        };
+        let Some(last_date) = self.get_latest_reading_date().await? else {
+            return Ok(());
+        };
+
+        let next_unsaved_date = self
+            .get_latest_zones()
+            .await?
+            .map(|row| row.date.checked_add_days(Days::new(1)).unwrap_or(row.date))
+            .unwrap_or(first_date);
+        let recalc_from = last_date
+            .checked_sub_days(Days::new(1))
+            .unwrap_or(last_date);
+
+        let mut date = next_unsaved_date.min(recalc_from).max(first_date);
+        while date <= last_date {
+            let from = date.and_hms_opt(0, 0, 0).expect("valid start of day");
+            let to = date
+                .checked_add_days(Days::new(1))
+                .and_then(|next| next.and_hms_opt(0, 0, 0))
+                .expect("valid next day");
+
+            if let Some(zones) = self.heart_rate_zones(from, to).await? {
+                let history = self
+                    .search_history(SearchHistory {
+                        from: Some(from - chrono::TimeDelta::milliseconds(1)),
+                        to: Some(to),
+                        limit: None,
+                    })
+                    .await?;
+                if let Some(breakdown) = zones.breakdown(&history) {
+                    self.create_or_update_zones(date, breakdown).await?;
+                }
+            }
+
+            date = match date.checked_add_days(Days::new(1)) {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        Ok(())
+    }
+
+    pub async fn calculate_zones_for_activity(
+        &self,
+        activity: ActivityPeriod,
+    ) -> anyhow::Result<Option<ZoneBreakdown>> {
+        let to = match activity.to {
+            Some(to) => to,
+            None => match self.get_latest_reading_time().await? {
+                Some(to) => to,
+                None => return Ok(None),
+            },
+        };
+
+        let Some(zones) = self.heart_rate_zones(activity.from, to).await? else {
+            return Ok(None);
+        };
+
+        let history = self
+            .search_history(SearchHistory {
+                from: Some(activity.from - chrono::TimeDelta::milliseconds(1)),
+                to: activity.to,
+                limit: None,
+            })
+            .await?;
+        if history.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(zones.breakdown(&history))
+    }
+
+    pub async fn set_activity_zones(
+        &self,
+        start: NaiveDateTime,
+        zones: ZoneBreakdown,
+    ) -> anyhow::Result<()> {
+        let [zone_1, zone_2, zone_3, zone_4, zone_5] = zones.minutes;
+        activities::Entity::update_many()
+            .col_expr(activities::Column::Zone1, Expr::value(zone_1))
+            .col_expr(activities::Column::Zone2, Expr::value(zone_2))
+            .col_expr(activities::Column::Zone3, Expr::value(zone_3))
+            .col_expr(activities::Column::Zone4, Expr::value(zone_4))
+            .col_expr(activities::Column::Zone5, Expr::value(zone_5))
+            .filter(activities::Column::Start.eq(start))
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Zones for finished activities stored before zones were calculated.
+    pub async fn calculate_missing_activity_zones(&self) -> anyhow::Result<()> {
+        let activities = activities::Entity::find()
+            .filter(activities::Column::Zone1.is_null())
+            .filter(activities::Column::End.is_not_null())
+            .order_by_asc(activities::Column::Start)
+            .all(&self.db)
+            .await?;
+
+        for activity in activities {
+            let start = activity.start;
+            if let Some(zones) = self
+                .calculate_zones_for_activity(map_activity_period(activity))
+                .await?
+            {
+                self.set_activity_zones(start, zones).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn create_or_update_zones(
+        &self,
+        date: NaiveDate,
+        zones: ZoneBreakdown,
+    ) -> anyhow::Result<()> {
+        let [zone_1, zone_2, zone_3, zone_4, zone_5] = zones.minutes;
+        let model = heart_rate_zones::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            date: Set(date),
+            zone_1: Set(zone_1),
+            zone_2: Set(zone_2),
+            zone_3: Set(zone_3),
+            zone_4: Set(zone_4),
+            zone_5: Set(zone_5),
+        };
+
+        heart_rate_zones::Entity::insert(model)
+            .on_conflict(
+                OnConflict::column(heart_rate_zones::Column::Date)
+                    .update_columns([
+                        heart_rate_zones::Column::Zone1,
+                        heart_rate_zones::Column::Zone2,
+                        heart_rate_zones::Column::Zone3,
+                        heart_rate_zones::Column::Zone4,
+                        heart_rate_zones::Column::Zone5,
+                    ])
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openwhoop_algos::{SleepCycle, UserProfile, ZoneBasis};
+    use openwhoop_entities::heart_rate;
+    use openwhoop_types::activities::ActivityType;
+    use sea_orm::{ActiveModelTrait, ActiveValue::NotSet};
+
+    #[tokio::test]
+    async fn zones_cover_ranges_days_and_activities() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let date = NaiveDate::from_ymd_opt(2025, 6, 15).unwrap();
+
+        db.create_sleep(SleepCycle {
+            id: date,
+            start: date.pred_opt().unwrap().and_hms_opt(23, 0, 0).unwrap(),
+            end: date.and_hms_opt(7, 0, 0).unwrap(),
+            min_bpm: 50,
+            max_bpm: 70,
+            avg_bpm: 60,
+            min_hrv: 30,
+            max_hrv: 60,
+            avg_hrv: 45,
+            score: 100.0,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        // max 200, resting 50: 130 bpm is zone 1 on HRR and zone 2 on HRmax
+        let start = date.and_hms_opt(10, 0, 0).unwrap();
+        for (i, bpm) in std::iter::repeat_n(130, 600)
+            .chain(std::iter::repeat_n(160, 300))
+            .enumerate()
+        {
+            heart_rate::ActiveModel {
+                id: NotSet,
+                bpm: Set(bpm),
+                time: Set(start + chrono::TimeDelta::seconds(i64::try_from(i).unwrap())),
+                rr_intervals: Set(String::new()),
+                activity: NotSet,
+                stress: NotSet,
+                stress_artifact_rate: NotSet,
+                spo2: NotSet,
+                skin_temp: NotSet,
+                imu_data: NotSet,
+                sensor_data: NotSet,
+                synced: Set(false),
+            }
+            .insert(&db.db)
+            .await
+            .unwrap();
+        }
+        db.set_profile(UserProfile {
+            max_hr: Some(200),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let rounded = |zones: ZoneBreakdown| zones.minutes.map(|minutes| minutes.round());
+        let all = db
+            .get_zone_breakdown(SearchHistory::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(rounded(all), [10.0, 0.0, 5.0, 0.0, 0.0]);
+
+        let activity = ActivityPeriod {
+            period_id: date,
+            from: start,
+            to: Some(start + chrono::TimeDelta::minutes(15)),
+            activity: ActivityType::Running,
+            strain: None,
+            calories: None,
+        };
+        db.create_activity(activity).await.unwrap();
+        db.calculate_missing_activity_zones().await.unwrap();
+        let stored = db.get_activities_since(start).await.unwrap();
+        assert_eq!(
+            ZoneBreakdown::from_activity(&stored[0]).map(rounded),
+            Some([10.0, 0.0, 5.0, 0.0, 0.0])
+        );
+
+        db.set_profile(UserProfile {
+            max_hr: Some(200),
+            zone_basis: Some(ZoneBasis::MaxHeartRate),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        db.calculate_latest_zones().await.unwrap();
+        let day = db.get_zones_for_date(date).await.unwrap().unwrap();
+        assert_eq!(rounded(day), [0.0, 10.0, 0.0, 5.0, 0.0]);
+    }
+}
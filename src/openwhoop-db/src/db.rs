@@ -1,14 +1,14 @@
-use chrono::{Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike};
-use openwhoop_entities::{packets, sleep_cycles, strain};
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use openwhoop_entities::{calories, packets, sleep_cycles, strain};
 use openwhoop_migration::{Migrator, MigratorTrait, OnConflict};
 use openwhoop_types::activities::SearchActivityPeriods;
 use sea_orm::{
     ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, ConnectOptions, Database,
-    DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+    DatabaseConnection, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
 };
 use uuid::Uuid;
 
-use openwhoop_algos::SleepCycle;
+use openwhoop_algos::{RecoveryScore, SleepCycle, SleepStage, SleepStageTotals, ZoneBreakdown};
 use openwhoop_codec::{HistoryReading, constants::WhoopGeneration};
 
 #[derive(Clone)]
@@ -21,8 +21,12 @@ pub struct DailyInfo {
     pub date: NaiveDate,
     pub sleep: Option<SleepCycle>,
     pub strain: Option<strain::Model>,
+    pub recovery: Option<RecoveryScore>,
     pub activities: Vec<openwhoop_types::activities::ActivityPeriod>,
     pub stress: Option<DailyStressInfo>,
+    pub steps: Option<u32>,
+    pub calories: Option<calories::Model>,
+    pub zones: Option<ZoneBreakdown>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -35,6 +39,7 @@ pub struct DailyStressInfo {
 pub struct DailyStats {
     pub hrv: Option<f64>,
     pub rhr: Option<f64>,
+    pub resp_rate: Option<f64>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -76,10 +81,13 @@ impl DatabaseHandler {
         Self { db }
     }
 
+    /// Store a raw packet captured at `time` from the strap `device_id`.
     pub async fn create_packet(
         &self,
         char: Uuid,
         generation: WhoopGeneration,
+        time: Option<NaiveDateTime>,
+        device_id: Option<&str>,
         data: Vec<u8>,
     ) -> anyhow::Result<openwhoop_entities::packets::Model> {
         let packet = openwhoop_entities::packets::ActiveModel {
@@ -87,6 +95,8 @@ impl DatabaseHandler {
             uuid: Set(char),
             generation: Set(generation.to_string()),
             bytes: Set(data),
+            time: Set(time),
+            device_id: Set(device_id.map(str::to_owned)),
         };
 
         let packet = packet.insert(&self.db).await?;
@@ -109,6 +119,7 @@ impl DatabaseHandler {
             rr_intervals: Set(rr_to_string(reading.rr)),
             activity: NotSet,
             stress: NotSet,
+            stress_artifact_rate: NotSet,
             spo2: NotSet,
             skin_temp: NotSet,
             imu_data: Set(Some(serde_json::to_value(reading.imu_data)?)),
@@ -150,6 +161,7 @@ impl DatabaseHandler {
                     rr_intervals: Set(rr_to_string(r.rr)),
                     activity: NotSet,
                     stress: NotSet,
+                    stress_artifact_rate: NotSet,
                     spo2: NotSet,
                     skin_temp: NotSet,
                     imu_data: Set(Some(serde_json::to_value(r.imu_data)?)),
@@ -160,8 +172,8 @@ impl DatabaseHandler {
             .collect::<anyhow::Result<Vec<_>>>()?;
 
         // SQLite limits to 999 SQL variables per statement.
-        // heart_rate has 11 columns, so max 90 rows per batch.
-        for chunk in payloads.chunks(90) {
+        // heart_rate has 12 columns, so max 83 rows per batch.
+        for chunk in payloads.chunks(83) {
             openwhoop_entities::heart_rate::Entity::insert_many(chunk.to_vec())
                 .on_conflict(
                     OnConflict::column(openwhoop_entities::heart_rate::Column::Time)
@@ -196,6 +208,19 @@ impl DatabaseHandler {
             .map(SleepCycle::from))
     }
 
+    /// Last sleep that ended at or before `before`.
+    pub async fn get_sleep_before(
+        &self,
+        before: NaiveDateTime,
+    ) -> anyhow::Result<Option<SleepCycle>> {
+        Ok(sleep_cycles::Entity::find()
+            .filter(sleep_cycles::Column::End.lte(before))
+            .order_by_desc(sleep_cycles::Column::End)
+            .one(&self.db)
+            .await?
+            .map(SleepCycle::from))
+    }
+
     pub async fn get_sleep_for_date(&self, date: NaiveDate) -> anyhow::Result<Option<SleepCycle>> {
         Ok(sleep_cycles::Entity::find()
             .filter(sleep_cycles::Column::SleepId.eq(date))
@@ -217,8 +242,9 @@ impl DatabaseHandler {
     pub async fn get_daily_info(&self, date: NaiveDate) -> anyhow::Result<DailyInfo> {
         Ok(DailyInfo {
             date,
-            sleep: self.get_sleep_for_date(date - Duration::days(1)).await?,
+            sleep: self.get_sleep_for_date(date).await?,
             strain: self.get_strain_for_date(date).await?,
+            recovery: self.get_recovery_for_date(date).await?,
             activities: self
                 .search_activities(SearchActivityPeriods {
                     from: Some(date.and_hms_opt(0, 0, 0).unwrap() - chrono::TimeDelta::seconds(1)),
@@ -230,6 +256,9 @@ impl DatabaseHandler {
                 })
                 .await?,
             stress: self.get_daily_stress_info(date).await?,
+            steps: self.get_steps_for_date(date).await?,
+            calories: self.get_calories_for_date(date).await?,
+            zones: self.get_zones_for_date(date).await?,
         })
     }
 
@@ -305,6 +334,17 @@ impl DatabaseHandler {
     }
 
     pub async fn create_sleep(&self, sleep: SleepCycle) -> anyhow::Result<()> {
+        let txn = self.db.begin().await?;
+
+        // Skin temperature covers the whole night, so it is calculated
+        // again once the night's bounds change
+        let bounds_changed = sleep_cycles::Entity::find()
+            .filter(sleep_cycles::Column::SleepId.eq(sleep.id))
+            .one(&txn)
+            .await?
+            .is_some_and(|stored| stored.start != sleep.start || stored.end != sleep.end);
+        let cleared = |value| if bounds_changed { Set(value) } else { NotSet };
+
         let model = sleep_cycles::ActiveModel {
             id: Set(Uuid::new_v4()),
             sleep_id: Set(sleep.id),
@@ -318,31 +358,71 @@ impl DatabaseHandler {
             avg_hrv: Set(sleep.avg_hrv.into()),
             score: Set(sleep.score.into()),
             synced: NotSet,
+            skin_temp: cleared(None),
+            skin_temp_deviation: cleared(None),
+            resp_rate_min: Set(sleep.resp_rate.map(|r| r.min)),
+            resp_rate_avg: Set(sleep.resp_rate.map(|r| r.avg)),
+            resp_rate_max: Set(sleep.resp_rate.map(|r| r.max)),
+            wake_seconds: Set(stage_seconds(sleep.stages, SleepStage::Wake)),
+            light_seconds: Set(stage_seconds(sleep.stages, SleepStage::Light)),
+            deep_seconds: Set(stage_seconds(sleep.stages, SleepStage::Deep)),
+            rem_seconds: Set(stage_seconds(sleep.stages, SleepStage::Rem)),
+            score_duration: Set(sleep.score_breakdown.map(|s| s.duration)),
+            score_efficiency: Set(sleep.score_breakdown.and_then(|s| s.efficiency)),
+            score_disturbances: Set(sleep.score_breakdown.and_then(|s| s.disturbances)),
+            score_consistency: Set(sleep.score_breakdown.and_then(|s| s.consistency)),
+            rr_artifact_rate: Set(sleep.rr_artifact_rate),
         };
 
+        let mut columns = vec![
+            sleep_cycles::Column::Start,
+            sleep_cycles::Column::End,
+            sleep_cycles::Column::MinBpm,
+            sleep_cycles::Column::MaxBpm,
+            sleep_cycles::Column::AvgBpm,
+            sleep_cycles::Column::MinHrv,
+            sleep_cycles::Column::MaxHrv,
+            sleep_cycles::Column::AvgHrv,
+            sleep_cycles::Column::RespRateMin,
+            sleep_cycles::Column::RespRateAvg,
+            sleep_cycles::Column::RespRateMax,
+            sleep_cycles::Column::WakeSeconds,
+            sleep_cycles::Column::LightSeconds,
+            sleep_cycles::Column::DeepSeconds,
+            sleep_cycles::Column::RemSeconds,
+            sleep_cycles::Column::Score,
+            sleep_cycles::Column::ScoreDuration,
+            sleep_cycles::Column::ScoreEfficiency,
+            sleep_cycles::Column::ScoreDisturbances,
+            sleep_cycles::Column::ScoreConsistency,
+            sleep_cycles::Column::RrArtifactRate,
+        ];
+        if bounds_changed {
+            columns.extend([
+                sleep_cycles::Column::SkinTemp,
+                sleep_cycles::Column::SkinTempDeviation,
+            ]);
+        }
+
         let _r = sleep_cycles::Entity::insert(model)
             .on_conflict(
                 OnConflict::column(sleep_cycles::Column::SleepId)
-                    .update_columns([
-                        sleep_cycles::Column::Start,
-                        sleep_cycles::Column::End,
-                        sleep_cycles::Column::MinBpm,
-                        sleep_cycles::Column::MaxBpm,
-                        sleep_cycles::Column::AvgBpm,
-                        sleep_cycles::Column::MinHrv,
-                        sleep_cycles::Column::MaxHrv,
-                        sleep_cycles::Column::AvgHrv,
-                    ])
+                    .update_columns(columns)
                     .to_owned(),
             )
-            .exec(&self.db)
+            .exec(&txn)
             .await?;
+        txn.commit().await?;
 
         Ok(())
     }
 }
 
-fn timestamp_to_local(unix: u64) -> anyhow::Result<NaiveDateTime> {
+fn stage_seconds(stages: Option<SleepStageTotals>, stage: SleepStage) -> Option<i32> {
+    stages.and_then(|stages| i32::try_from(stages.get(stage).num_seconds()).ok())
+}
+
+pub(crate) fn timestamp_to_local(unix: u64) -> anyhow::Result<NaiveDateTime> {
     let millis = i64::try_from(unix)?;
     let dt = Local
         .timestamp_millis_opt(millis)
@@ -366,9 +446,19 @@ mod tests {
         let db = DatabaseHandler::new("sqlite::memory:").await;
         let uuid = Uuid::new_v4();
         let data = vec![0xAA, 0xBB, 0xCC];
+        let time = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
 
         let packet = db
-            .create_packet(uuid, WhoopGeneration::Gen5, data.clone())
+            .create_packet(
+                uuid,
+                WhoopGeneration::Gen5,
+                Some(time),
+                Some("strap"),
+                data.clone(),
+            )
             .await
             .unwrap();
         assert_eq!(packet.uuid, uuid);
@@ -379,6 +469,8 @@ mod tests {
         assert_eq!(packets.len(), 1);
         assert_eq!(packets[0].uuid, uuid);
         assert_eq!(packets[0].generation, WhoopGeneration::Gen5.to_string());
+        assert_eq!(packets[0].time, Some(time));
+        assert_eq!(packets[0].device_id.as_deref(), Some("strap"));
     }
 
     #[tokio::test]
@@ -451,6 +543,7 @@ mod tests {
             max_hrv: 80,
             avg_hrv: 55,
             score: 100.0,
+            ..Default::default()
         };
 
         db.create_sleep(sleep).await.unwrap();
@@ -462,6 +555,54 @@ mod tests {
         assert_eq!(latest.avg_bpm, 60);
     }
 
+    #[tokio::test]
+    async fn create_sleep_resets_skin_temp_when_bounds_change() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+
+        let end = chrono::NaiveDate::from_ymd_opt(2025, 1, 2)
+            .unwrap()
+            .and_hms_opt(4, 0, 0)
+            .unwrap();
+        let sleep = SleepCycle {
+            id: end.date(),
+            start: end - chrono::TimeDelta::hours(5),
+            end,
+            min_bpm: 50,
+            max_bpm: 70,
+            avg_bpm: 60,
+            min_hrv: 30,
+            max_hrv: 80,
+            avg_hrv: 55,
+            score: 100.0,
+            ..Default::default()
+        };
+        db.create_sleep(sleep).await.unwrap();
+        db.update_sleep_skin_temp(sleep.id, 33.5, Some(0.2))
+            .await
+            .unwrap();
+
+        // Same night stored again: the skin temperature still applies
+        db.create_sleep(sleep).await.unwrap();
+        assert!(db.get_sleeps_without_skin_temp().await.unwrap().is_empty());
+        assert_eq!(
+            db.get_sleep_skin_temp_deviation(sleep.id).await.unwrap(),
+            Some(0.2)
+        );
+
+        // Night extended in place: it is calculated again
+        db.create_sleep(SleepCycle {
+            end: end + chrono::TimeDelta::hours(3),
+            ..sleep
+        })
+        .await
+        .unwrap();
+        assert_eq!(db.get_sleeps_without_skin_temp().await.unwrap().len(), 1);
+        assert_eq!(
+            db.get_sleep_skin_temp_deviation(sleep.id).await.unwrap(),
+            None
+        );
+    }
+
     #[tokio::test]
     async fn get_sleep_for_date_returns_matching_sleep() {
         let db = DatabaseHandler::new("sqlite::memory:").await;
@@ -486,6 +627,7 @@ mod tests {
             max_hrv: 80,
             avg_hrv: 55,
             score: 100.0,
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -493,6 +635,51 @@ mod tests {
         let sleep = db.get_sleep_for_date(end.date()).await.unwrap().unwrap();
         assert_eq!(sleep.id, end.date());
         assert_eq!(sleep.min_bpm, 50);
+
+        assert!(db.get_sleep_before(start).await.unwrap().is_none());
+        assert_eq!(
+            db.get_sleep_before(end + chrono::TimeDelta::hours(8))
+                .await
+                .unwrap()
+                .unwrap()
+                .id,
+            end.date()
+        );
+    }
+
+    #[tokio::test]
+    async fn get_daily_info_pairs_sleep_with_its_recovery() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+
+        // Nights woken up from on Jan 2 and Jan 3
+        for day in [2, 3] {
+            let end = chrono::NaiveDate::from_ymd_opt(2025, 1, day)
+                .unwrap()
+                .and_hms_opt(6, 0, 0)
+                .unwrap();
+            db.create_sleep(SleepCycle {
+                id: end.date(),
+                start: end - chrono::TimeDelta::hours(8),
+                end,
+                min_bpm: 50,
+                max_bpm: 70,
+                avg_bpm: 60,
+                min_hrv: 30,
+                max_hrv: 80,
+                avg_hrv: 55,
+                score: 100.0,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        }
+
+        let date = chrono::NaiveDate::from_ymd_opt(2025, 1, 3).unwrap();
+        let info = db.get_daily_info(date).await.unwrap();
+        assert_eq!(info.sleep.unwrap().id, date);
+
+        let first = db.get_daily_info(date.pred_opt().unwrap()).await.unwrap();
+        assert_eq!(first.sleep.unwrap().id, date.pred_opt().unwrap());
     }
 
     #[tokio::test]
@@ -514,6 +701,7 @@ mod tests {
             max_hrv: 80,
             avg_hrv: 55,
             score: 100.0,
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -527,6 +715,17 @@ mod tests {
         .await
         .unwrap();
 
+        let recovery = RecoveryScore {
+            date,
+            score: 72.0,
+            hrv: 70.0,
+            rhr: 60.0,
+            sleep: 100.0,
+            resp_rate: None,
+            skin_temp: None,
+        };
+        db.create_or_update_recovery(recovery).await.unwrap();
+
         for (time, stress) in [
             (date.and_hms_opt(11, 51, 10).unwrap(), 1.0),
             (date.and_hms_opt(11, 51, 40).unwrap(), 3.0),
@@ -542,6 +741,7 @@ mod tests {
                 rr_intervals: Set("850".to_string()),
                 activity: NotSet,
                 stress: Set(Some(stress)),
+                stress_artifact_rate: NotSet,
                 spo2: NotSet,
                 skin_temp: NotSet,
                 imu_data: Set(Some(serde_json::to_value(Vec::<u8>::new()).unwrap())),
@@ -559,17 +759,30 @@ mod tests {
             to: Some(date.and_hms_opt(11, 0, 0).unwrap()),
             activity: openwhoop_types::activities::ActivityType::Activity,
             strain: Some(8.5),
+            calories: None,
         })
         .await
         .unwrap();
 
+        db.create_step_minutes(&[openwhoop_algos::StepMinute {
+            minute: date.and_hms_opt(9, 30, 0).unwrap(),
+            steps: 112,
+            estimated: false,
+        }])
+        .await
+        .unwrap();
+
         let info = db.get_daily_info(date).await.unwrap();
         assert_eq!(info.date, date);
         assert_eq!(info.sleep.unwrap().id, date);
         assert_eq!(info.strain.unwrap().strain, 12.5);
+        assert_eq!(info.recovery, Some(recovery));
         assert_eq!(info.activities.len(), 1);
         assert_eq!(info.activities[0].from, date.and_hms_opt(10, 0, 0).unwrap());
         assert_eq!(info.activities[0].strain, Some(8.5));
+        assert_eq!(info.steps, Some(112));
+        assert!(info.calories.is_none());
+        assert!(info.zones.is_none());
         let stress = info.stress.unwrap();
         assert_eq!(stress.latest.time, date.and_hms_opt(12, 0, 45).unwrap());
         assert_eq!(stress.latest.stress, Some(11.0));
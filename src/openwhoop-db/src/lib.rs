@@ -5,4 +5,5 @@ mod algo_impl;
 pub mod sync;
 mod type_impl;
 
+pub use type_impl::console_logs::SearchConsoleLogs;
 pub use type_impl::history::SearchHistory;
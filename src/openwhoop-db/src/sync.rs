@@ -11,12 +11,12 @@ use sea_orm::{
 };
 
 // SQLite limits to 999 SQL variables, so batch sizes must respect:
-// heart_rate: 10 Set columns -> max 99 rows
+// heart_rate: 11 Set columns -> max 90 rows
 // sleep_cycles: 11 Set columns -> max 90 rows
-// activities: 4 Set columns -> max 249 rows
+// activities: 14 Set columns -> max 71 rows
 const HEART_RATE_BATCH: u64 = 90;
 const SLEEP_CYCLES_BATCH: u64 = 80;
-const ACTIVITIES_BATCH: u64 = 160;
+const ACTIVITIES_BATCH: u64 = 60;
 
 pub struct DatabaseSync<'a> {
     local: &'a DatabaseConnection,
@@ -146,6 +146,20 @@ impl<'a> DatabaseSync<'a> {
                     avg_hrv: Set(m.avg_hrv),
                     score: Set(m.score),
                     synced: Set(true),
+                    skin_temp: Set(m.skin_temp),
+                    skin_temp_deviation: Set(m.skin_temp_deviation),
+                    resp_rate_min: Set(m.resp_rate_min),
+                    resp_rate_avg: Set(m.resp_rate_avg),
+                    resp_rate_max: Set(m.resp_rate_max),
+                    wake_seconds: Set(m.wake_seconds),
+                    light_seconds: Set(m.light_seconds),
+                    deep_seconds: Set(m.deep_seconds),
+                    rem_seconds: Set(m.rem_seconds),
+                    score_duration: Set(m.score_duration),
+                    score_efficiency: Set(m.score_efficiency),
+                    score_disturbances: Set(m.score_disturbances),
+                    score_consistency: Set(m.score_consistency),
+                    rr_artifact_rate: Set(m.rr_artifact_rate),
                 })
                 .collect();
 
@@ -163,11 +177,33 @@ impl<'a> DatabaseSync<'a> {
                             sleep_cycles::Column::MinHrv,
                             sleep_cycles::Column::MaxHrv,
                             sleep_cycles::Column::AvgHrv,
+                            sleep_cycles::Column::RespRateMin,
+                            sleep_cycles::Column::RespRateAvg,
+                            sleep_cycles::Column::RespRateMax,
+                            sleep_cycles::Column::WakeSeconds,
+                            sleep_cycles::Column::LightSeconds,
+                            sleep_cycles::Column::DeepSeconds,
+                            sleep_cycles::Column::RemSeconds,
+                            sleep_cycles::Column::ScoreDuration,
+                            sleep_cycles::Column::ScoreEfficiency,
+                            sleep_cycles::Column::ScoreDisturbances,
+                            sleep_cycles::Column::ScoreConsistency,
+                            sleep_cycles::Column::RrArtifactRate,
                         ])
                         .value(
                             sleep_cycles::Column::Score,
                             Expr::cust("COALESCE(excluded.score, sleep_cycles.score)"),
                         )
+                        .value(
+                            sleep_cycles::Column::SkinTemp,
+                            Expr::cust("COALESCE(excluded.skin_temp, sleep_cycles.skin_temp)"),
+                        )
+                        .value(
+                            sleep_cycles::Column::SkinTempDeviation,
+                            Expr::cust(
+                                "COALESCE(excluded.skin_temp_deviation, sleep_cycles.skin_temp_deviation)",
+                            ),
+                        )
                         .update_column(sleep_cycles::Column::Synced)
                         .to_owned(),
                 )
@@ -245,7 +281,15 @@ impl<'a> DatabaseSync<'a> {
                     end: Set(m.end),
                     activity: Set(m.activity),
                     strain: Set(m.strain),
+                    calories: Set(m.calories),
                     synced: Set(true),
+                    confidence: Set(m.confidence),
+                    confirmed: Set(m.confirmed),
+                    zone_1: Set(m.zone_1),
+                    zone_2: Set(m.zone_2),
+                    zone_3: Set(m.zone_3),
+                    zone_4: Set(m.zone_4),
+                    zone_5: Set(m.zone_5),
                 })
                 .collect();
 
@@ -258,8 +302,16 @@ impl<'a> DatabaseSync<'a> {
                             activities::Column::End,
                             activities::Column::Activity,
                             activities::Column::Strain,
+                            activities::Column::Calories,
                             activities::Column::PeriodId,
                             activities::Column::Synced,
+                            activities::Column::Confidence,
+                            activities::Column::Confirmed,
+                            activities::Column::Zone1,
+                            activities::Column::Zone2,
+                            activities::Column::Zone3,
+                            activities::Column::Zone4,
+                            activities::Column::Zone5,
                         ])
                         .to_owned(),
                 )
@@ -337,6 +389,7 @@ impl<'a> DatabaseSync<'a> {
                     rr_intervals: Set(m.rr_intervals),
                     activity: Set(m.activity),
                     stress: Set(m.stress),
+                    stress_artifact_rate: Set(m.stress_artifact_rate),
                     spo2: NotSet,
                     skin_temp: NotSet,
                     imu_data: Set(m.imu_data),
@@ -359,6 +412,12 @@ impl<'a> DatabaseSync<'a> {
                             heart_rate::Column::Stress,
                             Expr::cust("COALESCE(excluded.stress, heart_rate.stress)"),
                         )
+                        .value(
+                            heart_rate::Column::StressArtifactRate,
+                            Expr::cust(
+                                "COALESCE(excluded.stress_artifact_rate, heart_rate.stress_artifact_rate)",
+                            ),
+                        )
                         .value(
                             heart_rate::Column::ImuData,
                             Expr::cust("COALESCE(excluded.imu_data, heart_rate.imu_data)"),
@@ -474,6 +533,7 @@ mod tests {
             max_hrv: 80,
             avg_hrv: 55,
             score: 100.0,
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -1,10 +1,12 @@
 use std::str::FromStr;
 
+use chrono::NaiveDateTime;
+use openwhoop_algos::ActivityClassification;
 use openwhoop_entities::{activities, sleep_cycles};
 use openwhoop_types::activities::{ActivityPeriod, ActivityType, SearchActivityPeriods};
 use sea_orm::{
     ColumnTrait, Condition, EntityTrait, NotSet, QueryFilter, QueryOrder, Set,
-    sea_query::OnConflict,
+    sea_query::{Expr, OnConflict},
 };
 
 use crate::DatabaseHandler;
@@ -30,7 +32,15 @@ impl DatabaseHandler {
             end: Set(activity.to),
             activity: Set(activity.activity.to_string()),
             strain: Set(activity.strain),
+            calories: Set(activity.calories),
             synced: NotSet,
+            confidence: NotSet,
+            confirmed: NotSet,
+            zone_1: NotSet,
+            zone_2: NotSet,
+            zone_3: NotSet,
+            zone_4: NotSet,
+            zone_5: NotSet,
         };
 
         activities::Entity::insert(model)
@@ -39,6 +49,7 @@ impl DatabaseHandler {
                     .update_column(activities::Column::End)
                     .update_column(activities::Column::Activity)
                     .update_column(activities::Column::Strain)
+                    .update_column(activities::Column::Calories)
                     .to_owned(),
             )
             .exec(&self.db)
@@ -96,6 +107,76 @@ impl DatabaseHandler {
             .map(map_activity_period))
     }
 
+    /// Activities starting at or after `from`, oldest first, with their
+    /// classification.
+    pub async fn get_activities_since(
+        &self,
+        from: NaiveDateTime,
+    ) -> anyhow::Result<Vec<activities::Model>> {
+        Ok(activities::Entity::find()
+            .filter(activities::Column::Start.gte(from))
+            .order_by_asc(activities::Column::Start)
+            .all(&self.db)
+            .await?)
+    }
+
+    /// End of the last activity found by workout detection.
+    pub async fn get_last_detected_activity_end(&self) -> anyhow::Result<Option<NaiveDateTime>> {
+        Ok(activities::Entity::find()
+            .filter(detected_condition())
+            .filter(activities::Column::End.is_not_null())
+            .order_by_desc(activities::Column::End)
+            .one(&self.db)
+            .await?
+            .and_then(|activity| activity.end))
+    }
+
+    /// Store the classifier's label for the detected activity starting at
+    /// `start`, unless the user already confirmed one.
+    pub async fn classify_activity(
+        &self,
+        start: NaiveDateTime,
+        classification: ActivityClassification,
+    ) -> anyhow::Result<()> {
+        activities::Entity::update_many()
+            .col_expr(
+                activities::Column::Activity,
+                Expr::value(classification.activity.to_string()),
+            )
+            .col_expr(
+                activities::Column::Confidence,
+                Expr::value(classification.confidence),
+            )
+            .filter(activities::Column::Start.eq(start))
+            .filter(activities::Column::Confirmed.eq(false))
+            .filter(detected_condition())
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Confirm the label of the activity starting at `start`, replacing it
+    /// with `activity` when given. Returns `false` if there is no such
+    /// activity.
+    pub async fn label_activity(
+        &self,
+        start: NaiveDateTime,
+        activity: Option<ActivityType>,
+    ) -> anyhow::Result<bool> {
+        let mut update = activities::Entity::update_many()
+            .col_expr(activities::Column::Confirmed, Expr::value(true))
+            .filter(activities::Column::Start.eq(start));
+        if let Some(activity) = activity {
+            update = update.col_expr(
+                activities::Column::Activity,
+                Expr::value(activity.to_string()),
+            );
+        }
+
+        Ok(update.exec(&self.db).await?.rows_affected > 0)
+    }
+
     pub async fn get_unfinished_activity(&self) -> anyhow::Result<Option<ActivityPeriod>> {
         Ok(activities::Entity::find()
             .filter(activities::Column::End.is_null())
@@ -106,16 +187,25 @@ impl DatabaseHandler {
     }
 }
 
-fn map_activity_period(value: activities::Model) -> ActivityPeriod {
+pub(crate) fn map_activity_period(value: activities::Model) -> ActivityPeriod {
     ActivityPeriod {
         period_id: value.period_id,
         from: value.start,
         to: value.end,
         activity: ActivityType::from_str(value.activity.as_str()).unwrap(),
         strain: value.strain,
+        calories: value.calories,
     }
 }
 
+/// Activities found by workout detection: generic ones and ones labelled by
+/// the classifier.
+fn detected_condition() -> Condition {
+    Condition::any()
+        .add(activities::Column::Confidence.is_not_null())
+        .add(activities::Column::Activity.eq(ActivityType::Activity.to_string()))
+}
+
 fn search_activity_periods_query(query: SearchActivityPeriods) -> Condition {
     Condition::all()
         .add_option(query.from.map(|from| activities::Column::Start.gt(from)))
@@ -167,6 +257,7 @@ mod tests {
             to: Some(base.and_hms_opt(hour + 1, 0, 0).unwrap()),
             activity: ActivityType::Running,
             strain: None,
+            calories: None,
         }
     }
 
@@ -187,11 +278,20 @@ mod tests {
             ),
             activity: "Running".to_string(),
             strain: Some(7.25),
+            calories: Some(310.0),
             synced: false,
+            confidence: None,
+            confirmed: false,
+            zone_1: None,
+            zone_2: None,
+            zone_3: None,
+            zone_4: None,
+            zone_5: None,
         };
         let period = map_activity_period(model);
         assert!(matches!(period.activity, ActivityType::Running));
         assert_eq!(period.strain, Some(7.25));
+        assert_eq!(period.calories, Some(310.0));
     }
 
     #[test]
@@ -226,6 +326,7 @@ mod tests {
             max_hrv: 80,
             avg_hrv: 55,
             score: 100.0,
+            ..Default::default()
         };
         db.create_sleep(sleep).await.unwrap();
 
@@ -265,6 +366,7 @@ mod tests {
             max_hrv: 80,
             avg_hrv: 55,
             score: 100.0,
+            ..Default::default()
         };
         db.create_sleep(sleep).await.unwrap();
 
@@ -294,6 +396,7 @@ mod tests {
             max_hrv: 80,
             avg_hrv: 55,
             score: 100.0,
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -304,6 +407,7 @@ mod tests {
             to: Some(sleep_date.and_hms_opt(9, 0, 0).unwrap()),
             activity: ActivityType::Running,
             strain: None,
+            calories: None,
         })
         .await
         .unwrap();
@@ -314,6 +418,7 @@ mod tests {
             to: Some(sleep_date.and_hms_opt(9, 30, 0).unwrap()),
             activity: ActivityType::Cycling,
             strain: None,
+            calories: None,
         })
         .await
         .unwrap();
@@ -345,6 +450,7 @@ mod tests {
             max_hrv: 80,
             avg_hrv: 55,
             score: 100.0,
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -355,6 +461,7 @@ mod tests {
             to: Some(sleep_date.and_hms_opt(6, 30, 0).unwrap()),
             activity: ActivityType::Running,
             strain: None,
+            calories: None,
         })
         .await
         .unwrap();
@@ -383,6 +490,7 @@ mod tests {
             max_hrv: 80,
             avg_hrv: 55,
             score: 100.0,
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -393,6 +501,7 @@ mod tests {
             to: None,
             activity: ActivityType::Running,
             strain: None,
+            calories: None,
         };
 
         db.create_activity(active).await.unwrap();
@@ -420,6 +529,7 @@ mod tests {
             max_hrv: 80,
             avg_hrv: 55,
             score: 100.0,
+            ..Default::default()
         })
         .await
         .unwrap();
@@ -430,10 +540,77 @@ mod tests {
             to: Some(now - chrono::TimeDelta::hours(1)),
             activity: ActivityType::Running,
             strain: None,
+            calories: None,
         })
         .await
         .unwrap();
 
         assert!(db.get_unfinished_activity().await.unwrap().is_none());
     }
+
+    #[tokio::test]
+    async fn classify_and_label_activity() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+
+        let sleep_date = NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        db.create_sleep(openwhoop_algos::SleepCycle {
+            id: sleep_date,
+            start: sleep_date.and_hms_opt(22, 0, 0).unwrap(),
+            end: NaiveDate::from_ymd_opt(2025, 1, 2)
+                .unwrap()
+                .and_hms_opt(6, 0, 0)
+                .unwrap(),
+            min_bpm: 50,
+            max_bpm: 70,
+            avg_bpm: 60,
+            min_hrv: 30,
+            max_hrv: 80,
+            avg_hrv: 55,
+            score: 100.0,
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        db.create_activity(make_activity(8)).await.unwrap();
+        assert!(db.get_last_detected_activity_end().await.unwrap().is_none());
+
+        let detected = ActivityPeriod {
+            activity: ActivityType::Activity,
+            ..make_activity(10)
+        };
+        db.create_activity(detected).await.unwrap();
+        assert_eq!(
+            db.get_last_detected_activity_end().await.unwrap(),
+            detected.to
+        );
+
+        let running = ActivityClassification {
+            activity: ActivityType::Running,
+            confidence: 0.9,
+        };
+        db.classify_activity(detected.from, running).await.unwrap();
+        assert_eq!(
+            db.get_last_detected_activity_end().await.unwrap(),
+            detected.to
+        );
+        let stored = db.get_activities_since(detected.from).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].activity, "Running");
+        assert_eq!(stored[0].confidence, Some(0.9));
+        assert!(!stored[0].confirmed);
+
+        assert!(
+            db.label_activity(detected.from, Some(ActivityType::Cycling))
+                .await
+                .unwrap()
+        );
+        db.classify_activity(detected.from, running).await.unwrap();
+        let stored = db.get_activities_since(detected.from).await.unwrap();
+        assert_eq!(stored[0].activity, "Cycling");
+        assert!(stored[0].confirmed);
+
+        let missing = sleep_date.and_hms_opt(12, 0, 0).unwrap();
+        assert!(!db.label_activity(missing, None).await.unwrap());
+    }
 }
@@ -0,0 +1,102 @@
+use chrono::NaiveDateTime;
+use openwhoop_codec::{BatteryPackInfo, ExtendedBatteryInfo};
+use openwhoop_entities::battery_history;
+use sea_orm::{ActiveModelTrait, EntityTrait, QueryOrder, Set};
+use uuid::Uuid;
+
+use crate::DatabaseHandler;
+
+impl DatabaseHandler {
+    /// Append an extended battery reading (and battery pack status, when
+    /// available) to `battery_history`.
+    pub async fn record_battery_reading(
+        &self,
+        device_id: Option<&str>,
+        time: NaiveDateTime,
+        battery: &ExtendedBatteryInfo,
+        pack: Option<&BatteryPackInfo>,
+    ) -> anyhow::Result<battery_history::Model> {
+        let model = battery_history::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            device_id: Set(device_id.map(str::to_owned)),
+            time: Set(time),
+            level: Set(i16::from(battery.level)),
+            voltage_mv: Set(Some(i32::from(battery.voltage_mv))),
+            temperature: Set(Some(battery.temperature_c)),
+            charge_state: Set(Some(battery.charge_state.as_str().to_owned())),
+            cycle_count: Set(Some(i32::from(battery.cycle_count))),
+            pack_connected: Set(pack.map(|pack| pack.connected)),
+            pack_level: Set(pack.map(|pack| i16::from(pack.level))),
+            pack_voltage_mv: Set(pack.map(|pack| i32::from(pack.voltage_mv))),
+            pack_charge_state: Set(pack.map(|pack| pack.charge_state.as_str().to_owned())),
+        };
+
+        Ok(model.insert(&self.db).await?)
+    }
+
+    pub async fn get_battery_history(&self) -> anyhow::Result<Vec<battery_history::Model>> {
+        Ok(battery_history::Entity::find()
+            .order_by_asc(battery_history::Column::Time)
+            .all(&self.db)
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeDelta};
+    use openwhoop_codec::ChargeState;
+
+    #[tokio::test]
+    async fn record_battery_reading_appends_rows_in_time_order() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let time = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let battery = ExtendedBatteryInfo {
+            level: 87,
+            voltage_mv: 4012,
+            temperature_c: 31.2,
+            charge_state: ChargeState::Charging,
+            cycle_count: 143,
+        };
+        let pack = BatteryPackInfo {
+            connected: true,
+            level: 64,
+            voltage_mv: 3850,
+            charge_state: ChargeState::NotCharging,
+        };
+
+        db.record_battery_reading(
+            Some("AA:BB:CC:DD:EE:FF"),
+            time + TimeDelta::hours(1),
+            &battery,
+            None,
+        )
+        .await
+        .unwrap();
+        db.record_battery_reading(Some("AA:BB:CC:DD:EE:FF"), time, &battery, Some(&pack))
+            .await
+            .unwrap();
+
+        let history = db.get_battery_history().await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].time, time);
+        assert_eq!(history[0].level, 87);
+        assert_eq!(history[0].voltage_mv, Some(4012));
+        assert_eq!(history[0].temperature, Some(31.2));
+        assert_eq!(history[0].charge_state.as_deref(), Some("charging"));
+        assert_eq!(history[0].cycle_count, Some(143));
+        assert_eq!(history[0].pack_connected, Some(true));
+        assert_eq!(history[0].pack_level, Some(64));
+        assert_eq!(history[0].pack_voltage_mv, Some(3850));
+        assert_eq!(
+            history[0].pack_charge_state.as_deref(),
+            Some("not_charging")
+        );
+        assert_eq!(history[1].pack_connected, None);
+        assert_eq!(history[1].pack_voltage_mv, None);
+    }
+}
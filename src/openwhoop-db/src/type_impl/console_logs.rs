@@ -0,0 +1,191 @@
+use chrono::NaiveDateTime;
+use openwhoop_codec::ConsoleLogEvent;
+use openwhoop_entities::console_logs;
+use sea_orm::{
+    ColumnTrait, Condition, EntityTrait, NotSet, QueryFilter, QueryOrder, QuerySelect, Set,
+    sea_query::OnConflict,
+};
+
+use crate::{DatabaseHandler, db::timestamp_to_local};
+
+#[derive(Default, Debug)]
+pub struct SearchConsoleLogs {
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+    /// Substring the message must contain
+    pub grep: Option<String>,
+    /// Structured event kind, e.g. `reset`
+    pub kind: Option<String>,
+    pub limit: Option<u64>,
+}
+
+impl SearchConsoleLogs {
+    fn conditions(self) -> Condition {
+        Condition::all()
+            .add_option(self.from.map(|from| console_logs::Column::Time.gte(from)))
+            .add_option(self.to.map(|to| console_logs::Column::Time.lt(to)))
+            .add_option(
+                self.grep
+                    .map(|grep| console_logs::Column::Message.contains(grep)),
+            )
+            .add_option(self.kind.map(|kind| console_logs::Column::Kind.eq(kind)))
+    }
+}
+
+impl DatabaseHandler {
+    /// Store a strap console log line, tagging it with the structured event
+    /// it matches. Re-running packets does not duplicate a strap's lines.
+    pub async fn create_console_log(
+        &self,
+        device_id: Option<&str>,
+        unix: u32,
+        message: &str,
+    ) -> anyhow::Result<()> {
+        let message = message.trim();
+        if message.is_empty() {
+            return Ok(());
+        }
+
+        let event = ConsoleLogEvent::parse(message);
+        let model = console_logs::ActiveModel {
+            id: NotSet,
+            device_id: Set(device_id.unwrap_or_default().to_owned()),
+            time: Set(timestamp_to_local(u64::from(unix) * 1000)?),
+            message: Set(message.to_owned()),
+            kind: Set(event.as_ref().map(|event| event.kind().to_owned())),
+            detail: Set(event
+                .as_ref()
+                .and_then(|event| event.detail())
+                .map(str::to_owned)),
+        };
+
+        console_logs::Entity::insert(model)
+            .on_conflict(
+                OnConflict::columns([
+                    console_logs::Column::DeviceId,
+                    console_logs::Column::Time,
+                    console_logs::Column::Message,
+                ])
+                .update_columns([console_logs::Column::Kind, console_logs::Column::Detail])
+                .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn search_console_logs(
+        &self,
+        options: SearchConsoleLogs,
+    ) -> anyhow::Result<Vec<console_logs::Model>> {
+        let limit = options.limit;
+        Ok(console_logs::Entity::find()
+            .filter(options.conditions())
+            .limit(limit)
+            .order_by_asc(console_logs::Column::Time)
+            .order_by_asc(console_logs::Column::Id)
+            .all(&self.db)
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNIX: u32 = 1_735_732_800;
+
+    #[tokio::test]
+    async fn create_console_log_classifies_and_deduplicates() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+
+        db.create_console_log(None, UNIX, "Boot reason: WDT\n")
+            .await
+            .unwrap();
+        db.create_console_log(None, UNIX, "Boot reason: WDT")
+            .await
+            .unwrap();
+        db.create_console_log(None, UNIX + 60, "BLE connected")
+            .await
+            .unwrap();
+        db.create_console_log(None, UNIX + 120, "  ").await.unwrap();
+
+        let logs = db
+            .search_console_logs(SearchConsoleLogs::default())
+            .await
+            .unwrap();
+        assert_eq!(logs.len(), 2);
+        assert_eq!(logs[0].message, "Boot reason: WDT");
+        assert_eq!(logs[0].kind.as_deref(), Some("reset"));
+        assert_eq!(logs[0].detail.as_deref(), Some("WDT"));
+        assert_eq!(logs[1].kind, None);
+    }
+
+    #[tokio::test]
+    async fn console_logs_are_deduplicated_per_strap() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+
+        for device_id in [Some("a"), Some("a"), Some("b"), None] {
+            db.create_console_log(device_id, UNIX, "Boot reason: WDT")
+                .await
+                .unwrap();
+        }
+
+        let logs = db
+            .search_console_logs(SearchConsoleLogs::default())
+            .await
+            .unwrap();
+        let mut devices = logs
+            .iter()
+            .map(|log| log.device_id.as_str())
+            .collect::<Vec<_>>();
+        devices.sort_unstable();
+        assert_eq!(devices, vec!["", "a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn search_console_logs_filters_by_time_grep_and_kind() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        db.create_console_log(None, UNIX, "Boot reason: WDT")
+            .await
+            .unwrap();
+        db.create_console_log(None, UNIX + 60, "AFE read timeout")
+            .await
+            .unwrap();
+        db.create_console_log(None, UNIX + 120, "AFE init ok")
+            .await
+            .unwrap();
+
+        let afe = db
+            .search_console_logs(SearchConsoleLogs {
+                grep: Some("AFE".to_owned()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(afe.len(), 2);
+
+        let faults = db
+            .search_console_logs(SearchConsoleLogs {
+                kind: Some("sensor_fault".to_owned()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(faults.len(), 1);
+        assert_eq!(faults[0].message, "AFE read timeout");
+
+        let from = timestamp_to_local(u64::from(UNIX + 60) * 1000).unwrap();
+        let windowed = db
+            .search_console_logs(SearchConsoleLogs {
+                from: Some(from),
+                to: Some(from + chrono::TimeDelta::seconds(30)),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(windowed.len(), 1);
+        assert_eq!(windowed[0].message, "AFE read timeout");
+    }
+}
@@ -0,0 +1,110 @@
+use chrono::{NaiveDateTime, TimeDelta};
+use openwhoop_codec::constants::WhoopGeneration;
+use openwhoop_entities::devices;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set, sea_query::OnConflict};
+use uuid::Uuid;
+
+use crate::DatabaseHandler;
+
+impl DatabaseHandler {
+    pub async fn get_device(&self, device_id: &str) -> anyhow::Result<Option<devices::Model>> {
+        Ok(devices::Entity::find()
+            .filter(devices::Column::DeviceId.eq(device_id))
+            .one(&self.db)
+            .await?)
+    }
+
+    pub async fn get_devices(&self) -> anyhow::Result<Vec<devices::Model>> {
+        Ok(devices::Entity::find()
+            .order_by_desc(devices::Column::LastSeen)
+            .all(&self.db)
+            .await?)
+    }
+
+    /// Record the strap clock offset (strap time minus system time) observed
+    /// at `checked_at`, registering the device if it is not known yet.
+    pub async fn record_clock_offset(
+        &self,
+        device_id: &str,
+        generation: WhoopGeneration,
+        offset: TimeDelta,
+        checked_at: NaiveDateTime,
+    ) -> anyhow::Result<devices::Model> {
+        let model = devices::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            device_id: Set(device_id.to_owned()),
+            generation: Set(generation.to_string()),
+            last_seen: Set(checked_at),
+            clock_offset_ms: Set(Some(offset.num_milliseconds())),
+            clock_checked_at: Set(Some(checked_at)),
+        };
+
+        devices::Entity::insert(model)
+            .on_conflict(
+                OnConflict::column(devices::Column::DeviceId)
+                    .update_columns([
+                        devices::Column::Generation,
+                        devices::Column::LastSeen,
+                        devices::Column::ClockOffsetMs,
+                        devices::Column::ClockCheckedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+
+        self.get_device(device_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("device row missing after upsert"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[tokio::test]
+    async fn get_device_unknown_returns_none() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        assert!(db.get_device("AA:BB:CC:DD:EE:FF").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn record_clock_offset_upserts_by_device_id() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let checked_at = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+
+        let first = db
+            .record_clock_offset(
+                "AA:BB:CC:DD:EE:FF",
+                WhoopGeneration::Gen4,
+                TimeDelta::milliseconds(-1500),
+                checked_at,
+            )
+            .await
+            .unwrap();
+        assert_eq!(first.clock_offset_ms, Some(-1500));
+        assert_eq!(first.generation, "WHOOP 4.0");
+
+        let second = db
+            .record_clock_offset(
+                "AA:BB:CC:DD:EE:FF",
+                WhoopGeneration::Gen4,
+                TimeDelta::milliseconds(250),
+                checked_at + TimeDelta::hours(1),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(second.id, first.id);
+        assert_eq!(second.clock_offset_ms, Some(250));
+        assert_eq!(
+            second.clock_checked_at,
+            Some(checked_at + TimeDelta::hours(1))
+        );
+    }
+}
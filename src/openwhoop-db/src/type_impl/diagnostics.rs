@@ -0,0 +1,144 @@
+use chrono::NaiveDateTime;
+use openwhoop_codec::{MemfaultMessage, constants::WhoopGeneration};
+use openwhoop_entities::diagnostics;
+use openwhoop_migration::OnConflict;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder, Set};
+use uuid::Uuid;
+
+use crate::DatabaseHandler;
+
+impl DatabaseHandler {
+    /// Store a reassembled Memfault message received at `time` from the
+    /// strap `device_id`. A message already stored for the strap, e.g. when
+    /// stored packets are re-run, is kept as it is.
+    pub async fn create_diagnostic(
+        &self,
+        generation: WhoopGeneration,
+        device_id: Option<&str>,
+        time: NaiveDateTime,
+        message: MemfaultMessage,
+    ) -> anyhow::Result<diagnostics::Model> {
+        let device_id = device_id.unwrap_or_default().to_owned();
+        let chunk_hash = message.hash();
+        let model = diagnostics::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            time: Set(time),
+            generation: Set(generation.to_string()),
+            chunks: Set(i32::try_from(message.chunks)?),
+            data: Set(message.data),
+            device_id: Set(device_id.clone()),
+            chunk_hash: Set(chunk_hash.clone()),
+        };
+
+        diagnostics::Entity::insert(model)
+            .on_conflict(
+                OnConflict::columns([
+                    diagnostics::Column::DeviceId,
+                    diagnostics::Column::ChunkHash,
+                ])
+                .do_nothing()
+                .to_owned(),
+            )
+            .do_nothing()
+            .exec(&self.db)
+            .await?;
+
+        diagnostics::Entity::find()
+            .filter(diagnostics::Column::DeviceId.eq(device_id))
+            .filter(diagnostics::Column::ChunkHash.eq(chunk_hash))
+            .one(&self.db)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("diagnostic row missing after insert"))
+    }
+
+    pub async fn get_diagnostics(
+        &self,
+        since: Option<NaiveDateTime>,
+    ) -> anyhow::Result<Vec<diagnostics::Model>> {
+        let mut query = diagnostics::Entity::find();
+        if let Some(since) = since {
+            query = query.filter(diagnostics::Column::Time.gte(since));
+        }
+
+        Ok(query
+            .order_by_asc(diagnostics::Column::Time)
+            .all(&self.db)
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeDelta};
+
+    #[tokio::test]
+    async fn get_diagnostics_filters_by_time() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let time = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+
+        for (offset, data) in [(0, vec![1, 2]), (2, vec![3, 4, 5])] {
+            db.create_diagnostic(
+                WhoopGeneration::Gen4,
+                None,
+                time + TimeDelta::hours(offset),
+                MemfaultMessage {
+                    data,
+                    chunks: 1,
+                    expected_len: None,
+                },
+            )
+            .await
+            .unwrap();
+        }
+
+        assert_eq!(db.get_diagnostics(None).await.unwrap().len(), 2);
+
+        let recent = db
+            .get_diagnostics(Some(time + TimeDelta::hours(1)))
+            .await
+            .unwrap();
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].data, vec![3, 4, 5]);
+        assert_eq!(recent[0].generation, "WHOOP 4.0");
+    }
+
+    #[tokio::test]
+    async fn create_diagnostic_skips_messages_already_stored_for_the_strap() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let time = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let message = MemfaultMessage {
+            data: vec![1, 2, 3],
+            chunks: 2,
+            expected_len: Some(3),
+        };
+
+        let first = db
+            .create_diagnostic(WhoopGeneration::Gen5, Some("strap"), time, message.clone())
+            .await
+            .unwrap();
+        let replayed = db
+            .create_diagnostic(
+                WhoopGeneration::Gen5,
+                Some("strap"),
+                time + TimeDelta::hours(1),
+                message.clone(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(replayed.id, first.id);
+        assert_eq!(replayed.time, time);
+
+        // The same message from another strap is stored
+        db.create_diagnostic(WhoopGeneration::Gen5, Some("other"), time, message)
+            .await
+            .unwrap();
+        assert_eq!(db.get_diagnostics(None).await.unwrap().len(), 2);
+    }
+}
@@ -1,4 +1,5 @@
 use chrono::NaiveDateTime;
+use openwhoop_algos::helpers::time_math::truncate_to_minute;
 use openwhoop_codec::{ParsedHistoryReading, SensorData};
 use openwhoop_entities::heart_rate;
 use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
@@ -57,11 +58,33 @@ impl DatabaseHandler {
         Ok(history)
     }
 
+    /// Minutes of `[from, to]` with at least one reading, in order.
+    pub async fn get_recorded_minutes(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<Vec<NaiveDateTime>> {
+        let times: Vec<NaiveDateTime> = heart_rate::Entity::find()
+            .select_only()
+            .column(heart_rate::Column::Time)
+            .filter(heart_rate::Column::Time.between(from, to))
+            .order_by_asc(heart_rate::Column::Time)
+            .into_tuple()
+            .all(&self.db)
+            .await?;
+
+        let mut minutes = times
+            .into_iter()
+            .map(truncate_to_minute)
+            .collect::<Vec<_>>();
+        minutes.dedup();
+        Ok(minutes)
+    }
+
     fn parse_reading(model: heart_rate::Model) -> ParsedHistoryReading {
-        let gravity = model.sensor_data.as_ref().and_then(|data| {
-            let sensor: SensorData = serde_json::from_value(data.clone()).ok()?;
-            Some(sensor.accel_gravity)
-        });
+        let sensor_data: Option<SensorData> = model
+            .sensor_data
+            .and_then(|data| serde_json::from_value(data).ok());
 
         ParsedHistoryReading {
             time: model.time,
@@ -78,7 +101,8 @@ impl DatabaseHandler {
                     Default::default()
                 }
             },
-            gravity,
+            gravity: sensor_data.as_ref().map(|sensor| sensor.accel_gravity),
+            sensor_data,
         }
     }
 }
@@ -101,6 +125,7 @@ mod tests {
             rr_intervals: "833,850".to_string(),
             activity: Some(500_000_000),
             stress: Some(3.5),
+            stress_artifact_rate: None,
             spo2: None,
             skin_temp: None,
             imu_data: None,
@@ -128,6 +153,7 @@ mod tests {
             rr_intervals: "".to_string(),
             activity: Some(0),
             stress: None,
+            stress_artifact_rate: None,
             spo2: None,
             skin_temp: None,
             imu_data: None,
@@ -164,6 +190,7 @@ mod tests {
             rr_intervals: "800".to_string(),
             activity: Some(500_000_000),
             stress: None,
+            stress_artifact_rate: None,
             spo2: None,
             skin_temp: None,
             imu_data: Some(serde_json::to_value(&imu_samples).unwrap()),
@@ -208,4 +235,30 @@ mod tests {
             .unwrap();
         assert_eq!(history.len(), 2);
     }
+
+    #[tokio::test]
+    async fn recorded_minutes_are_deduplicated() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+
+        // Three readings in the first minute, one two minutes later
+        for seconds in [0, 20, 40, 150] {
+            db.create_reading(openwhoop_codec::HistoryReading {
+                unix: 1735689600000 + seconds * 1000,
+                bpm: 70,
+                rr: vec![850],
+                imu_data: vec![],
+                sensor_data: None,
+            })
+            .await
+            .unwrap();
+        }
+        let history = db.search_history(SearchHistory::default()).await.unwrap();
+        let first = history[0].time;
+
+        let minutes = db
+            .get_recorded_minutes(first, first + chrono::TimeDelta::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(minutes, vec![first, first + chrono::TimeDelta::minutes(2)]);
+    }
 }
@@ -1,2 +1,8 @@
-mod activities;
+pub(crate) mod activities;
+mod battery;
+pub(crate) mod console_logs;
+mod devices;
+mod diagnostics;
 pub(crate) mod history;
+mod sleep_stages;
+mod wear;
@@ -0,0 +1,152 @@
+use chrono::NaiveDate;
+use openwhoop_algos::{SleepEpoch, SleepStage};
+use openwhoop_entities::sleep_stages;
+use sea_orm::{ColumnTrait, EntityTrait, NotSet, QueryFilter, QueryOrder, Set, TransactionTrait};
+
+use crate::DatabaseHandler;
+
+impl DatabaseHandler {
+    /// Store the hypnogram of a sleep, replacing any earlier staging of it.
+    pub async fn replace_sleep_stages(
+        &self,
+        sleep_id: NaiveDate,
+        epochs: &[SleepEpoch],
+    ) -> anyhow::Result<()> {
+        let txn = self.db.begin().await?;
+
+        sleep_stages::Entity::delete_many()
+            .filter(sleep_stages::Column::SleepId.eq(sleep_id))
+            .exec(&txn)
+            .await?;
+
+        let models = epochs
+            .iter()
+            .map(|epoch| sleep_stages::ActiveModel {
+                id: NotSet,
+                sleep_id: Set(sleep_id),
+                start: Set(epoch.start),
+                stage: Set(epoch.stage.as_str().to_owned()),
+            })
+            .collect::<Vec<_>>();
+
+        for chunk in models.chunks(500) {
+            sleep_stages::Entity::insert_many(chunk.to_vec())
+                .exec(&txn)
+                .await?;
+        }
+
+        txn.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_sleep_stages(&self, sleep_id: NaiveDate) -> anyhow::Result<Vec<SleepEpoch>> {
+        sleep_stages::Entity::find()
+            .filter(sleep_stages::Column::SleepId.eq(sleep_id))
+            .order_by_asc(sleep_stages::Column::Start)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|model| {
+                Ok(SleepEpoch {
+                    start: model.start,
+                    stage: model.stage.parse::<SleepStage>()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Hypnograms of the sleeps woken up from on `from..=to`, oldest first.
+    pub async fn get_sleep_stages_between(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> anyhow::Result<Vec<SleepEpoch>> {
+        sleep_stages::Entity::find()
+            .filter(sleep_stages::Column::SleepId.gte(from))
+            .filter(sleep_stages::Column::SleepId.lte(to))
+            .order_by_asc(sleep_stages::Column::Start)
+            .all(&self.db)
+            .await?
+            .into_iter()
+            .map(|model| {
+                Ok(SleepEpoch {
+                    start: model.start,
+                    stage: model.stage.parse::<SleepStage>()?,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use openwhoop_algos::{SleepCycle, SleepStageTotals, SleepStager};
+
+    #[tokio::test]
+    async fn replace_sleep_stages_round_trips() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+
+        let end = NaiveDate::from_ymd_opt(2025, 1, 2)
+            .unwrap()
+            .and_hms_opt(6, 0, 0)
+            .unwrap();
+        let start = end - TimeDelta::hours(8);
+        let epochs = [SleepStage::Light, SleepStage::Deep, SleepStage::Rem]
+            .into_iter()
+            .enumerate()
+            .map(|(i, stage)| SleepEpoch {
+                start: start + SleepStager::EPOCH * i as i32,
+                stage,
+            })
+            .collect::<Vec<_>>();
+
+        let sleep = SleepCycle {
+            id: end.date(),
+            start,
+            end,
+            min_bpm: 50,
+            max_bpm: 70,
+            avg_bpm: 60,
+            min_hrv: 30,
+            max_hrv: 80,
+            avg_hrv: 55,
+            score: 100.0,
+            ..Default::default()
+        }
+        .with_stages(&epochs);
+        db.create_sleep(sleep).await.unwrap();
+
+        db.replace_sleep_stages(sleep.id, &epochs[..1])
+            .await
+            .unwrap();
+        db.replace_sleep_stages(sleep.id, &epochs).await.unwrap();
+
+        assert_eq!(db.get_sleep_stages(sleep.id).await.unwrap(), epochs);
+        assert_eq!(
+            db.get_sleep_stages_between(sleep.id, sleep.id)
+                .await
+                .unwrap(),
+            epochs
+        );
+        let next = sleep.id.succ_opt().unwrap();
+        assert!(
+            db.get_sleep_stages_between(next, next)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        let stored = db.get_sleep_cycles(None).await.unwrap();
+        assert_eq!(
+            stored[0].stages,
+            Some(SleepStageTotals {
+                wake: TimeDelta::zero(),
+                light: TimeDelta::seconds(30),
+                deep: TimeDelta::seconds(30),
+                rem: TimeDelta::seconds(30),
+            })
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use chrono::NaiveDateTime;
+use openwhoop_codec::BodyLocationInfo;
+use openwhoop_entities::wear_locations;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder, Set,
+};
+use uuid::Uuid;
+
+use crate::DatabaseHandler;
+
+impl DatabaseHandler {
+    /// Record that the strap was worn as `info` reports for the readings
+    /// between `start` and `end`. Readings downloaded with the same placement
+    /// as the latest window extend it; a change in placement opens a new one.
+    pub async fn record_wear_location(
+        &self,
+        device_id: Option<&str>,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+        info: &BodyLocationInfo,
+    ) -> anyhow::Result<wear_locations::Model> {
+        let location = info.location.as_str();
+        let wrist = info.wrist.map(|wrist| wrist.as_str().to_owned());
+
+        let device_filter = match device_id {
+            Some(device_id) => wear_locations::Column::DeviceId.eq(device_id),
+            None => wear_locations::Column::DeviceId.is_null(),
+        };
+        let latest = wear_locations::Entity::find()
+            .filter(device_filter)
+            .order_by_desc(wear_locations::Column::End)
+            .one(&self.db)
+            .await?;
+
+        if let Some(latest) = latest.filter(|latest| {
+            latest.location == location && latest.wrist == wrist && latest.on_body == info.on_body
+        }) {
+            if start >= latest.start && end <= latest.end {
+                return Ok(latest);
+            }
+
+            let window_start = latest.start.min(start);
+            let window_end = latest.end.max(end);
+            let mut model = latest.into_active_model();
+            model.start = Set(window_start);
+            model.end = Set(window_end);
+            return Ok(model.update(&self.db).await?);
+        }
+
+        let model = wear_locations::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            device_id: Set(device_id.map(str::to_owned)),
+            start: Set(start),
+            end: Set(end),
+            location: Set(location.to_owned()),
+            wrist: Set(wrist),
+            on_body: Set(info.on_body),
+        };
+
+        Ok(model.insert(&self.db).await?)
+    }
+
+    /// Wear location of `device_id` in effect at `time`: the latest window
+    /// that started at or before it. Placement is assumed unchanged until the
+    /// next window. Without a device, windows of every strap are considered.
+    pub async fn get_wear_location_at(
+        &self,
+        device_id: Option<&str>,
+        time: NaiveDateTime,
+    ) -> anyhow::Result<Option<wear_locations::Model>> {
+        Ok(wear_locations::Entity::find()
+            .filter(Condition::all().add_option(
+                device_id.map(|device_id| wear_locations::Column::DeviceId.eq(device_id)),
+            ))
+            .filter(wear_locations::Column::Start.lte(time))
+            .order_by_desc(wear_locations::Column::Start)
+            .one(&self.db)
+            .await?)
+    }
+
+    /// Wear windows of `device_id` that started at or before `time`, oldest
+    /// first, so readings up to `time` can each be matched to the placement
+    /// they were recorded with. Without a device, windows of every strap are
+    /// returned.
+    pub async fn get_wear_locations_before(
+        &self,
+        device_id: Option<&str>,
+        time: NaiveDateTime,
+    ) -> anyhow::Result<Vec<wear_locations::Model>> {
+        Ok(wear_locations::Entity::find()
+            .filter(Condition::all().add_option(
+                device_id.map(|device_id| wear_locations::Column::DeviceId.eq(device_id)),
+            ))
+            .filter(wear_locations::Column::Start.lte(time))
+            .order_by_asc(wear_locations::Column::Start)
+            .all(&self.db)
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeDelta};
+    use openwhoop_codec::{BodyLocation, Wrist};
+
+    fn info(location: BodyLocation, wrist: Option<Wrist>) -> BodyLocationInfo {
+        BodyLocationInfo {
+            location,
+            wrist,
+            on_body: true,
+        }
+    }
+
+    #[tokio::test]
+    async fn record_wear_location_extends_window_until_placement_changes() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let t0 = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(8, 0, 0)
+            .unwrap();
+        let wrist = info(BodyLocation::Wrist, Some(Wrist::Left));
+        let bicep = info(BodyLocation::Bicep, None);
+
+        let first = db
+            .record_wear_location(Some("strap"), t0, t0 + TimeDelta::hours(2), &wrist)
+            .await
+            .unwrap();
+        let extended = db
+            .record_wear_location(
+                Some("strap"),
+                t0 + TimeDelta::hours(2),
+                t0 + TimeDelta::hours(4),
+                &wrist,
+            )
+            .await
+            .unwrap();
+        assert_eq!(extended.id, first.id);
+        assert_eq!(extended.start, t0);
+        assert_eq!(extended.end, t0 + TimeDelta::hours(4));
+
+        let moved = db
+            .record_wear_location(
+                Some("strap"),
+                t0 + TimeDelta::hours(6),
+                t0 + TimeDelta::hours(8),
+                &bicep,
+            )
+            .await
+            .unwrap();
+        assert_ne!(moved.id, first.id);
+        assert_eq!(moved.location, "bicep");
+        assert_eq!(moved.wrist, None);
+
+        let at = db
+            .get_wear_location_at(Some("strap"), t0 + TimeDelta::hours(5))
+            .await
+            .unwrap()
+            .expect("window should cover the time");
+        assert_eq!(at.id, first.id);
+        assert_eq!(at.wrist.as_deref(), Some("left"));
+
+        let later = db
+            .get_wear_location_at(Some("strap"), t0 + TimeDelta::hours(9))
+            .await
+            .unwrap()
+            .expect("latest placement should carry forward");
+        assert_eq!(later.id, moved.id);
+
+        assert!(
+            db.get_wear_location_at(Some("strap"), t0 - TimeDelta::hours(1))
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        let windows = db
+            .get_wear_locations_before(Some("strap"), t0 + TimeDelta::hours(7))
+            .await
+            .unwrap();
+        assert_eq!(
+            windows.iter().map(|w| w.id).collect::<Vec<_>>(),
+            vec![first.id, moved.id]
+        );
+    }
+
+    #[tokio::test]
+    async fn wear_locations_are_kept_per_strap() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let t0 = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(8, 0, 0)
+            .unwrap();
+        let t1 = t0 + TimeDelta::hours(2);
+
+        let wrist = db
+            .record_wear_location(Some("a"), t0, t1, &info(BodyLocation::Wrist, None))
+            .await
+            .unwrap();
+        let bicep = db
+            .record_wear_location(Some("b"), t0, t1, &info(BodyLocation::Bicep, None))
+            .await
+            .unwrap();
+        assert_ne!(wrist.id, bicep.id);
+
+        let a = db
+            .get_wear_location_at(Some("a"), t1)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(a.location, "wrist");
+        let b = db
+            .get_wear_location_at(Some("b"), t1)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(b.location, "bicep");
+        assert!(
+            db.get_wear_location_at(Some("c"), t1)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(
+            db.get_wear_locations_before(None, t1).await.unwrap().len(),
+            2
+        );
+    }
+}
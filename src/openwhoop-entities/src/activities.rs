@@ -14,7 +14,22 @@ pub struct Model {
     pub activity: String,
     #[sea_orm(column_type = "Double", nullable)]
     pub strain: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub calories: Option<f64>,
     pub synced: bool,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub confidence: Option<f64>,
+    pub confirmed: bool,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub zone_1: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub zone_2: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub zone_3: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub zone_4: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub zone_5: Option<f64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
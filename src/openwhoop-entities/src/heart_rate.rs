@@ -16,6 +16,8 @@ pub struct Model {
     #[sea_orm(column_type = "Double", nullable)]
     pub stress: Option<f64>,
     #[sea_orm(column_type = "Double", nullable)]
+    pub stress_artifact_rate: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
     pub spo2: Option<f64>,
     #[sea_orm(column_type = "Double", nullable)]
     pub skin_temp: Option<f64>,
@@ -3,7 +3,22 @@
 pub mod prelude;
 
 pub mod activities;
+pub mod battery_history;
+pub mod calories;
+pub mod console_logs;
+pub mod devices;
+pub mod diagnostics;
+pub mod health_alerts;
 pub mod heart_rate;
+pub mod heart_rate_zones;
+pub mod hrv_samples;
+pub mod job_progress;
 pub mod packets;
+pub mod profile;
+pub mod recovery;
 pub mod sleep_cycles;
+pub mod sleep_stages;
+pub mod steps;
 pub mod strain;
+pub mod vo2max;
+pub mod wear_locations;
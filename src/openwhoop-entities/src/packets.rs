@@ -11,6 +11,8 @@ pub struct Model {
     pub generation: String,
     #[sea_orm(column_type = "Binary(1)")]
     pub bytes: Vec<u8>,
+    pub time: Option<DateTime>,
+    pub device_id: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
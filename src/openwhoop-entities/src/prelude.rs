@@ -1,7 +1,22 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.0
 
 pub use super::activities::Entity as Activities;
+pub use super::battery_history::Entity as BatteryHistory;
+pub use super::calories::Entity as Calories;
+pub use super::console_logs::Entity as ConsoleLogs;
+pub use super::devices::Entity as Devices;
+pub use super::diagnostics::Entity as Diagnostics;
+pub use super::health_alerts::Entity as HealthAlerts;
 pub use super::heart_rate::Entity as HeartRate;
+pub use super::heart_rate_zones::Entity as HeartRateZones;
+pub use super::hrv_samples::Entity as HrvSamples;
+pub use super::job_progress::Entity as JobProgress;
 pub use super::packets::Entity as Packets;
+pub use super::profile::Entity as Profile;
+pub use super::recovery::Entity as Recovery;
 pub use super::sleep_cycles::Entity as SleepCycles;
+pub use super::sleep_stages::Entity as SleepStages;
+pub use super::steps::Entity as Steps;
 pub use super::strain::Entity as Strain;
+pub use super::vo2max::Entity as Vo2max;
+pub use super::wear_locations::Entity as WearLocations;
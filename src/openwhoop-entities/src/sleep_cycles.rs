@@ -20,12 +20,38 @@ pub struct Model {
     #[sea_orm(column_type = "Double", nullable)]
     pub score: Option<f64>,
     pub synced: bool,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub skin_temp: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub skin_temp_deviation: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub resp_rate_min: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub resp_rate_avg: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub resp_rate_max: Option<f64>,
+    pub wake_seconds: Option<i32>,
+    pub light_seconds: Option<i32>,
+    pub deep_seconds: Option<i32>,
+    pub rem_seconds: Option<i32>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub score_duration: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub score_efficiency: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub score_disturbances: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub score_consistency: Option<f64>,
+    #[sea_orm(column_type = "Double", nullable)]
+    pub rr_artifact_rate: Option<f64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::activities::Entity")]
     Activities,
+    #[sea_orm(has_many = "super::sleep_stages::Entity")]
+    SleepStages,
 }
 
 impl Related<super::activities::Entity> for Entity {
@@ -34,4 +60,10 @@ impl Related<super::activities::Entity> for Entity {
     }
 }
 
+impl Related<super::sleep_stages::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::SleepStages.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -16,6 +16,25 @@ mod m20260508_000000_strain;
 mod m20260508_000001_activity_strain;
 mod m20260514_000000_packets_generation;
 mod m20260516_000000_activity_nullable_end;
+mod m20261018_000000_devices;
+mod m20261018_000001_battery_history;
+mod m20261018_000002_wear_locations;
+mod m20261018_000003_console_logs;
+mod m20261018_000004_diagnostics;
+mod m20261018_000005_sleep_skin_temp;
+mod m20261018_000006_sleep_resp_rate;
+mod m20261018_000007_sleep_stages;
+mod m20261018_000008_sleep_score_components;
+mod m20261018_000009_recovery;
+mod m20261018_000010_activity_classification;
+mod m20261018_000011_steps;
+mod m20261018_000012_profile;
+mod m20261018_000013_calories;
+mod m20261018_000014_heart_rate_zones;
+mod m20261018_000015_rr_artifacts;
+mod m20261018_000016_hrv_samples;
+mod m20261018_000017_health_alerts;
+mod m20261018_000018_vo2max;
 
 pub struct Migrator;
 
@@ -39,6 +58,25 @@ impl MigratorTrait for Migrator {
             Box::new(m20260508_000001_activity_strain::Migration),
             Box::new(m20260514_000000_packets_generation::Migration),
             Box::new(m20260516_000000_activity_nullable_end::Migration),
+            Box::new(m20261018_000000_devices::Migration),
+            Box::new(m20261018_000001_battery_history::Migration),
+            Box::new(m20261018_000002_wear_locations::Migration),
+            Box::new(m20261018_000003_console_logs::Migration),
+            Box::new(m20261018_000004_diagnostics::Migration),
+            Box::new(m20261018_000005_sleep_skin_temp::Migration),
+            Box::new(m20261018_000006_sleep_resp_rate::Migration),
+            Box::new(m20261018_000007_sleep_stages::Migration),
+            Box::new(m20261018_000008_sleep_score_components::Migration),
+            Box::new(m20261018_000009_recovery::Migration),
+            Box::new(m20261018_000010_activity_classification::Migration),
+            Box::new(m20261018_000011_steps::Migration),
+            Box::new(m20261018_000012_profile::Migration),
+            Box::new(m20261018_000013_calories::Migration),
+            Box::new(m20261018_000014_heart_rate_zones::Migration),
+            Box::new(m20261018_000015_rr_artifacts::Migration),
+            Box::new(m20261018_000016_hrv_samples::Migration),
+            Box::new(m20261018_000017_health_alerts::Migration),
+            Box::new(m20261018_000018_vo2max::Migration),
         ]
     }
 }
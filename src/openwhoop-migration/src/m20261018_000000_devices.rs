@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Devices::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Devices::Id).uuid().not_null().primary_key())
+                    .col(
+                        ColumnDef::new(Devices::DeviceId)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Devices::Generation).string().not_null())
+                    .col(ColumnDef::new(Devices::LastSeen).date_time().not_null())
+                    .col(ColumnDef::new(Devices::ClockOffsetMs).big_integer().null())
+                    .col(ColumnDef::new(Devices::ClockCheckedAt).date_time().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Devices::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Devices {
+    Table,
+    Id,
+    DeviceId,
+    Generation,
+    LastSeen,
+    ClockOffsetMs,
+    ClockCheckedAt,
+}
@@ -0,0 +1,88 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(BatteryHistory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(BatteryHistory::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(BatteryHistory::DeviceId).string().null())
+                    .col(ColumnDef::new(BatteryHistory::Time).date_time().not_null())
+                    .col(
+                        ColumnDef::new(BatteryHistory::Level)
+                            .small_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(BatteryHistory::VoltageMv).integer().null())
+                    .col(ColumnDef::new(BatteryHistory::Temperature).double().null())
+                    .col(ColumnDef::new(BatteryHistory::ChargeState).string().null())
+                    .col(ColumnDef::new(BatteryHistory::CycleCount).integer().null())
+                    .col(
+                        ColumnDef::new(BatteryHistory::PackConnected)
+                            .boolean()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(BatteryHistory::PackLevel)
+                            .small_integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(BatteryHistory::PackVoltageMv)
+                            .integer()
+                            .null(),
+                    )
+                    .col(
+                        ColumnDef::new(BatteryHistory::PackChargeState)
+                            .string()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_battery_history_time")
+                    .table(BatteryHistory::Table)
+                    .col(BatteryHistory::Time)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(BatteryHistory::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum BatteryHistory {
+    Table,
+    Id,
+    DeviceId,
+    Time,
+    Level,
+    VoltageMv,
+    Temperature,
+    ChargeState,
+    CycleCount,
+    PackConnected,
+    PackLevel,
+    PackVoltageMv,
+    PackChargeState,
+}
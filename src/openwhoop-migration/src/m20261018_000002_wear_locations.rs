@@ -0,0 +1,58 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(WearLocations::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(WearLocations::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(WearLocations::DeviceId).string().null())
+                    .col(ColumnDef::new(WearLocations::Start).date_time().not_null())
+                    .col(ColumnDef::new(WearLocations::End).date_time().not_null())
+                    .col(ColumnDef::new(WearLocations::Location).string().not_null())
+                    .col(ColumnDef::new(WearLocations::Wrist).string().null())
+                    .col(ColumnDef::new(WearLocations::OnBody).boolean().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_wear_locations_start")
+                    .table(WearLocations::Table)
+                    .col(WearLocations::Start)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(WearLocations::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum WearLocations {
+    Table,
+    Id,
+    DeviceId,
+    Start,
+    End,
+    Location,
+    Wrist,
+    OnBody,
+}
@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(ConsoleLogs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(ConsoleLogs::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(ConsoleLogs::DeviceId)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(ColumnDef::new(ConsoleLogs::Time).date_time().not_null())
+                    .col(ColumnDef::new(ConsoleLogs::Message).text().not_null())
+                    .col(ColumnDef::new(ConsoleLogs::Kind).string().null())
+                    .col(ColumnDef::new(ConsoleLogs::Detail).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_console_logs_device_id_time_message")
+                    .table(ConsoleLogs::Table)
+                    .col(ConsoleLogs::DeviceId)
+                    .col(ConsoleLogs::Time)
+                    .col(ConsoleLogs::Message)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ConsoleLogs::Table).to_owned())
+            .await
+    }
+}
+
+/// Lines are unique per strap (empty when unknown), time and message.
+#[derive(Iden)]
+enum ConsoleLogs {
+    Table,
+    Id,
+    DeviceId,
+    Time,
+    Message,
+    Kind,
+    Detail,
+}
@@ -0,0 +1,105 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Diagnostics::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Diagnostics::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Diagnostics::DeviceId)
+                            .string()
+                            .not_null()
+                            .default(""),
+                    )
+                    .col(ColumnDef::new(Diagnostics::Time).date_time().not_null())
+                    .col(ColumnDef::new(Diagnostics::Generation).string().not_null())
+                    .col(ColumnDef::new(Diagnostics::Chunks).integer().not_null())
+                    .col(ColumnDef::new(Diagnostics::Data).binary().not_null())
+                    .col(ColumnDef::new(Diagnostics::ChunkHash).string().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_diagnostics_device_id_chunk_hash")
+                    .table(Diagnostics::Table)
+                    .col(Diagnostics::DeviceId)
+                    .col(Diagnostics::ChunkHash)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Packets::Table)
+                    .add_column(ColumnDef::new(Packets::Time).date_time().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Packets::Table)
+                    .add_column(ColumnDef::new(Packets::DeviceId).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [Packets::DeviceId, Packets::Time] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Packets::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .drop_table(Table::drop().table(Diagnostics::Table).to_owned())
+            .await
+    }
+}
+
+/// Diagnostic messages are unique per strap (empty when unknown) and hash of
+/// their data, so re-running stored packets doesn't store them again.
+#[derive(Iden)]
+enum Diagnostics {
+    Table,
+    Id,
+    DeviceId,
+    Time,
+    Generation,
+    Chunks,
+    Data,
+    ChunkHash,
+}
+
+/// When and from which strap a packet was captured, so diagnostics can be
+/// timed by capture; both unknown for packets stored before.
+#[derive(Iden)]
+enum Packets {
+    Table,
+    Time,
+    DeviceId,
+}
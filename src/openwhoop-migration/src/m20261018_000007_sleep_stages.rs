@@ -0,0 +1,106 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20250127_195808_sleep_cycles::SleepCycles;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const STAGE_COLUMNS: [StageTotals; 4] = [
+    StageTotals::WakeSeconds,
+    StageTotals::LightSeconds,
+    StageTotals::DeepSeconds,
+    StageTotals::RemSeconds,
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(SleepStages::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(SleepStages::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(SleepStages::SleepId).date().not_null())
+                    .col(ColumnDef::new(SleepStages::Start).date_time().not_null())
+                    .col(ColumnDef::new(SleepStages::Stage).string_len(16).not_null())
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_sleep_stages_sleep_cycles")
+                            .from(SleepStages::Table, SleepStages::SleepId)
+                            .to(SleepCycles::Table, SleepCycles::SleepId)
+                            .on_delete(ForeignKeyAction::Cascade)
+                            .on_update(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_sleep_stages_sleep_id_start")
+                    .table(SleepStages::Table)
+                    .col(SleepStages::SleepId)
+                    .col(SleepStages::Start)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        for column in STAGE_COLUMNS {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(SleepCycles::Table)
+                        .add_column(ColumnDef::new(column).integer().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in STAGE_COLUMNS.into_iter().rev() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(SleepCycles::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .drop_table(Table::drop().table(SleepStages::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum SleepStages {
+    Table,
+    Id,
+    SleepId,
+    Start,
+    Stage,
+}
+
+/// Per-stage totals on `sleep_cycles`, in seconds.
+#[allow(clippy::enum_variant_names)]
+#[derive(DeriveIden, Clone, Copy)]
+enum StageTotals {
+    WakeSeconds,
+    LightSeconds,
+    DeepSeconds,
+    RemSeconds,
+}
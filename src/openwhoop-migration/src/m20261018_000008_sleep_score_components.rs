@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const COLUMNS: [SleepCycles; 4] = [
+    SleepCycles::ScoreDuration,
+    SleepCycles::ScoreEfficiency,
+    SleepCycles::ScoreDisturbances,
+    SleepCycles::ScoreConsistency,
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in COLUMNS {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(SleepCycles::Table)
+                        .add_column(ColumnDef::new(column).double().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in COLUMNS.into_iter().rev() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(SleepCycles::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Sleep score components, each 0-100.
+#[derive(DeriveIden, Clone, Copy)]
+enum SleepCycles {
+    Table,
+    ScoreDuration,
+    ScoreEfficiency,
+    ScoreDisturbances,
+    ScoreConsistency,
+}
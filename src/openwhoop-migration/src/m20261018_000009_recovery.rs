@@ -0,0 +1,50 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Recovery::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Recovery::Id).uuid().not_null().primary_key())
+                    .col(
+                        ColumnDef::new(Recovery::Date)
+                            .date()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Recovery::Score).double().not_null())
+                    .col(ColumnDef::new(Recovery::HrvScore).double().not_null())
+                    .col(ColumnDef::new(Recovery::RhrScore).double().not_null())
+                    .col(ColumnDef::new(Recovery::SleepScore).double().not_null())
+                    .col(ColumnDef::new(Recovery::RespRateScore).double().null())
+                    .col(ColumnDef::new(Recovery::SkinTempScore).double().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Recovery::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum Recovery {
+    Table,
+    Id,
+    Date,
+    Score,
+    HrvScore,
+    RhrScore,
+    SleepScore,
+    RespRateScore,
+    SkinTempScore,
+}
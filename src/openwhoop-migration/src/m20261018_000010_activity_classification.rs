@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20250202_085524_activities::Activities;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Activities::Table)
+                    .add_column(ColumnDef::new(Classification::Confidence).double().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Activities::Table)
+                    .add_column(
+                        ColumnDef::new(Classification::Confirmed)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [Classification::Confirmed, Classification::Confidence] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Activities::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Classifier confidence for detected activities, and whether the user
+/// confirmed or overrode the label.
+#[derive(DeriveIden)]
+enum Classification {
+    Confidence,
+    Confirmed,
+}
@@ -0,0 +1,48 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Steps::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Steps::Id).uuid().not_null().primary_key())
+                    .col(
+                        ColumnDef::new(Steps::Minute)
+                            .date_time()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Steps::Steps).integer().not_null())
+                    .col(
+                        ColumnDef::new(Steps::Estimated)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Steps::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+#[allow(clippy::enum_variant_names)]
+enum Steps {
+    Table,
+    Id,
+    Minute,
+    Steps,
+    Estimated,
+}
@@ -0,0 +1,47 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Profile::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Profile::Id)
+                            .integer()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Profile::BirthDate).date().null())
+                    .col(ColumnDef::new(Profile::Sex).string().null())
+                    .col(ColumnDef::new(Profile::WeightKg).double().null())
+                    .col(ColumnDef::new(Profile::HeightCm).double().null())
+                    .col(ColumnDef::new(Profile::MaxHr).integer().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Profile::Table).to_owned())
+            .await
+    }
+}
+
+/// Single-row table with the user's profile.
+#[derive(Iden)]
+enum Profile {
+    Table,
+    Id,
+    BirthDate,
+    Sex,
+    WeightKg,
+    HeightCm,
+    MaxHr,
+}
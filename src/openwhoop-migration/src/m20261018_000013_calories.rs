@@ -0,0 +1,65 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20250202_085524_activities::Activities;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Activities::Table)
+                    .add_column(ColumnDef::new(Calories::Calories).double().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(Calories::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Calories::Id).uuid().not_null().primary_key())
+                    .col(
+                        ColumnDef::new(Calories::Date)
+                            .date()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Calories::Total).double().not_null())
+                    .col(ColumnDef::new(Calories::Active).double().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Calories::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Activities::Table)
+                    .drop_column(Calories::Calories)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Daily energy expenditure in kcal, and the per-activity `calories` column.
+#[derive(Iden)]
+#[allow(clippy::enum_variant_names)]
+enum Calories {
+    Table,
+    Id,
+    Date,
+    Total,
+    Active,
+    Calories,
+}
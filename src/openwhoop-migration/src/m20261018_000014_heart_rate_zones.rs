@@ -0,0 +1,114 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20250202_085524_activities::Activities;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const ZONES: [HeartRateZones; 5] = [
+    HeartRateZones::Zone1,
+    HeartRateZones::Zone2,
+    HeartRateZones::Zone3,
+    HeartRateZones::Zone4,
+    HeartRateZones::Zone5,
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for zone in ZONES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Activities::Table)
+                        .add_column(ColumnDef::new(zone).double().null())
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Profile::Table)
+                    .add_column(ColumnDef::new(Profile::ZoneBasis).string().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        let mut table = Table::create()
+            .table(HeartRateZones::Table)
+            .if_not_exists()
+            .col(
+                ColumnDef::new(HeartRateZones::Id)
+                    .uuid()
+                    .not_null()
+                    .primary_key(),
+            )
+            .col(
+                ColumnDef::new(HeartRateZones::Date)
+                    .date()
+                    .not_null()
+                    .unique_key(),
+            )
+            .to_owned();
+        for zone in ZONES {
+            table.col(ColumnDef::new(zone).double().not_null());
+        }
+
+        manager.create_table(table).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(HeartRateZones::Table).to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Profile::Table)
+                    .drop_column(Profile::ZoneBasis)
+                    .to_owned(),
+            )
+            .await?;
+
+        for zone in ZONES {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Activities::Table)
+                        .drop_column(zone)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Daily minutes in each heart rate zone, and the same columns on
+/// `activities`.
+#[derive(Iden, Clone, Copy)]
+enum HeartRateZones {
+    Table,
+    Id,
+    Date,
+    #[iden = "zone_1"]
+    Zone1,
+    #[iden = "zone_2"]
+    Zone2,
+    #[iden = "zone_3"]
+    Zone3,
+    #[iden = "zone_4"]
+    Zone4,
+    #[iden = "zone_5"]
+    Zone5,
+}
+
+#[derive(Iden)]
+enum Profile {
+    Table,
+    ZoneBasis,
+}
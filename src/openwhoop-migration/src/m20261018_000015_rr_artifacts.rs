@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SleepCycles::Table)
+                    .add_column(ColumnDef::new(SleepCycles::RrArtifactRate).double().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartRate::Table)
+                    .add_column(
+                        ColumnDef::new(HeartRate::StressArtifactRate)
+                            .double()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(HeartRate::Table)
+                    .drop_column(HeartRate::StressArtifactRate)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SleepCycles::Table)
+                    .drop_column(SleepCycles::RrArtifactRate)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Share of the night's RR intervals corrected as artifacts, in percent.
+#[derive(Iden)]
+enum SleepCycles {
+    Table,
+    RrArtifactRate,
+}
+
+/// Share of the RR intervals behind the stress score that were corrected as
+/// artifacts, in percent.
+#[derive(Iden)]
+enum HeartRate {
+    Table,
+    StressArtifactRate,
+}
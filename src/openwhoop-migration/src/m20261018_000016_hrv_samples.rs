@@ -0,0 +1,91 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(HrvSamples::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(HrvSamples::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(HrvSamples::Start)
+                            .date_time()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(HrvSamples::Beats).integer().not_null())
+                    .col(ColumnDef::new(HrvSamples::MeanNn).double().not_null())
+                    .col(ColumnDef::new(HrvSamples::Sdnn).double().not_null())
+                    .col(ColumnDef::new(HrvSamples::Rmssd).double().not_null())
+                    .col(ColumnDef::new(HrvSamples::ArtifactRate).double().not_null())
+                    .col(ColumnDef::new(HrvSamples::Coverage).double().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(JobProgress::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(JobProgress::Job)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(JobProgress::ProcessedUntil)
+                            .date_time()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(JobProgress::Table).to_owned())
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(HrvSamples::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum HrvSamples {
+    Table,
+    Id,
+    /// Start of the 5-minute window.
+    Start,
+    Beats,
+    MeanNn,
+    Sdnn,
+    Rmssd,
+    /// Share of the window's RR intervals corrected as artifacts, in percent.
+    ArtifactRate,
+    /// Share of the window covered by RR intervals, in percent.
+    Coverage,
+}
+
+/// How far a backfill job has scanned the history, for jobs whose output
+/// alone can't tell (e.g. stretches that yield nothing to store).
+#[derive(Iden)]
+enum JobProgress {
+    Table,
+    Job,
+    ProcessedUntil,
+}
@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(HealthAlerts::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(HealthAlerts::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(HealthAlerts::Date)
+                            .date()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(HealthAlerts::Nights).integer().not_null())
+                    .col(ColumnDef::new(HealthAlerts::RhrZ).double().null())
+                    .col(ColumnDef::new(HealthAlerts::HrvZ).double().null())
+                    .col(ColumnDef::new(HealthAlerts::SkinTempZ).double().null())
+                    .col(ColumnDef::new(HealthAlerts::RespRateZ).double().null())
+                    .col(ColumnDef::new(HealthAlerts::Spo2Z).double().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(HealthAlerts::Table).to_owned())
+            .await
+    }
+}
+
+/// Early warnings of illness or overreaching. The z-score columns are set
+/// for the metrics that contributed to the alert.
+#[derive(Iden)]
+enum HealthAlerts {
+    Table,
+    Id,
+    Date,
+    /// Consecutive nights off baseline up to `date`.
+    Nights,
+    RhrZ,
+    HrvZ,
+    SkinTempZ,
+    RespRateZ,
+    #[iden = "spo2_z"]
+    Spo2Z,
+}
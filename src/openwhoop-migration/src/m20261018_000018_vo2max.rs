@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Vo2max::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(Vo2max::Id).uuid().not_null().primary_key())
+                    .col(ColumnDef::new(Vo2max::Week).date().not_null().unique_key())
+                    .col(ColumnDef::new(Vo2max::Vo2max).double().not_null())
+                    .col(ColumnDef::new(Vo2max::Uth).double().not_null())
+                    .col(ColumnDef::new(Vo2max::Submaximal).double().null())
+                    .col(
+                        ColumnDef::new(Vo2max::SubmaximalMinutes)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(Vo2max::RestingHr).integer().not_null())
+                    .col(ColumnDef::new(Vo2max::MaxHr).integer().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Vo2max::Table).to_owned())
+            .await
+    }
+}
+
+/// Weekly VO2max estimates, ml/kg/min.
+#[derive(Iden)]
+#[allow(clippy::enum_variant_names)]
+enum Vo2max {
+    Table,
+    Id,
+    /// Monday the week starts on.
+    Week,
+    Vo2max,
+    /// Estimate from the max to resting heart rate ratio alone.
+    Uth,
+    /// Estimate from walking and running minutes, when there were enough.
+    Submaximal,
+    SubmaximalMinutes,
+    RestingHr,
+    MaxHr,
+}
@@ -10,6 +10,8 @@ pub struct ActivityPeriod {
     pub to: Option<NaiveDateTime>,
     pub activity: ActivityType,
     pub strain: Option<f64>,
+    /// Energy spent during the activity, in kcal
+    pub calories: Option<f64>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -24,7 +26,7 @@ pub enum Category {
     Restorative,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, Copy, EnumIter)]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, EnumIter)]
 pub enum ActivityType {
     #[serde(rename = "Activity")]
     Activity = -1,
@@ -1,8 +1,11 @@
 use anyhow::anyhow;
-use chrono::{Local, Utc};
+use chrono::{DateTime, Local, TimeDelta, Utc};
 use futures::StreamExt;
 use openwhoop_algos::StressCalculator;
-use openwhoop_codec::{ParsedHistoryReading, WhoopData, WhoopPacket, constants::WhoopGeneration};
+use openwhoop_codec::{
+    BatteryPackInfo, BodyLocationInfo, ExtendedBatteryInfo, ParsedHistoryReading, WhoopData,
+    WhoopPacket, Wrist, constants::WhoopGeneration,
+};
 use openwhoop_entities::packets::Model;
 use std::{
     collections::VecDeque,
@@ -60,6 +63,7 @@ impl HistorySyncConfig {
 }
 
 const REALTIME_STREAM_TIMEOUT: Duration = Duration::from_secs(30);
+const COMMAND_RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
 const MIN_REALTIME_STRESS_SAMPLES: usize = 8;
 const BATTERY_SERVICE_UUID: Uuid = uuid!("0000180f-0000-1000-8000-00805f9b34fb");
 const BATTERY_LEVEL_CHARACTERISTIC_UUID: Uuid = uuid!("00002a19-0000-1000-8000-00805f9b34fb");
@@ -85,6 +89,7 @@ impl RealtimeStressWindow {
             rr: Vec::new(),
             imu_data: None,
             gravity: None,
+            sensor_data: None,
         });
 
         while self.readings.len() > StressCalculator::MIN_READING_PERIOD {
@@ -108,6 +113,30 @@ fn format_realtime_time(unix: u32) -> String {
         .unwrap_or_else(|| unix.to_string())
 }
 
+/// Strap RTC compared against system time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockDrift {
+    pub strap_time: DateTime<Utc>,
+    pub system_time: DateTime<Utc>,
+    /// Strap time minus system time; positive when the strap runs ahead.
+    pub offset: TimeDelta,
+}
+
+impl ClockDrift {
+    fn new(strap_unix: u32, sent_at: DateTime<Utc>, received_at: DateTime<Utc>) -> Self {
+        let strap_time =
+            DateTime::from_timestamp(i64::from(strap_unix), 0).unwrap_or(DateTime::UNIX_EPOCH);
+        // The strap samples its clock somewhere between request and response.
+        let system_time = sent_at + (received_at - sent_at) / 2;
+
+        Self {
+            strap_time,
+            system_time,
+            offset: strap_time - system_time,
+        }
+    }
+}
+
 pub type WhoopDevice = WhoopDeviceWith<BtleplugTransport>;
 
 pub struct WhoopDeviceWith<T> {
@@ -116,6 +145,7 @@ pub struct WhoopDeviceWith<T> {
     debug_packets: bool,
     generation: WhoopGeneration,
     seq: u8,
+    device_id: Option<String>,
 }
 
 impl WhoopDeviceWith<BtleplugTransport> {
@@ -151,9 +181,18 @@ where
             debug_packets,
             generation,
             seq: 0,
+            device_id: None,
         }
     }
 
+    /// Identifier used for this strap in the device registry.
+    pub fn with_device_id(mut self, device_id: impl Into<String>) -> Self {
+        let device_id = device_id.into();
+        self.whoop.device_id = Some(device_id.clone());
+        self.device_id = Some(device_id);
+        self
+    }
+
     pub async fn connect(&mut self) -> anyhow::Result<()> {
         self.transport.connect().await?;
         self.whoop.packet = None;
@@ -219,6 +258,13 @@ where
         should_exit: Arc<AtomicBool>,
         config: HistorySyncConfig,
     ) -> anyhow::Result<()> {
+        if let Err(err) = self.record_clock_drift().await {
+            warn!("Clock drift check before history sync failed: {err}");
+        }
+        if let Err(err) = self.record_wear_location().await {
+            warn!("Body location check before history sync failed: {err}");
+        }
+
         match self.generation {
             WhoopGeneration::Gen4 => self.sync_history_gen4(should_exit).await,
             WhoopGeneration::Gen5 => self.sync_history_gen5(should_exit, config).await,
@@ -327,6 +373,8 @@ where
                 uuid: notification.uuid,
                 generation: self.generation.to_string(),
                 bytes: notification.value,
+                time: Some(Local::now().naive_local()),
+                device_id: self.device_id.clone(),
             }),
         }
     }
@@ -550,6 +598,137 @@ where
         }
     }
 
+    /// Send `packet` and wait for its command response, decoded by `decode`.
+    /// Gen4 responses arrive as dedicated `WhoopData` variants, Gen5 ones as a
+    /// raw `CommandResponse` matched by sequence number.
+    async fn request_command_response<R>(
+        &mut self,
+        packet: WhoopPacket,
+        what: &str,
+        decode: impl Fn(WhoopData, u8) -> Option<R>,
+    ) -> anyhow::Result<R> {
+        let mut notifications = self.transport.notifications().await?;
+        let seq = self.send_command_with_seq(packet).await?;
+
+        let deadline = Instant::now() + COMMAND_RESPONSE_TIMEOUT;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let notification = match timeout(remaining, notifications.next()).await {
+                Ok(Some(notification)) => notification,
+                Ok(None) => return Err(anyhow!("stream ended unexpectedly")),
+                Err(_) => return Err(anyhow!("timed out waiting for {what} response")),
+            };
+
+            if notification.uuid != self.generation.cmd_from_strap() {
+                continue;
+            }
+
+            let packet = match self.generation {
+                WhoopGeneration::Gen4 => WhoopPacket::from_data(notification.value),
+                WhoopGeneration::Gen5 => WhoopPacket::from_data_maverick(notification.value),
+                WhoopGeneration::Placeholder => {
+                    return Err(anyhow!(
+                        "WhoopGeneration::Placeholder cannot parse {what} packets"
+                    ));
+                }
+            };
+            let Ok(packet) = packet else {
+                continue;
+            };
+
+            if let Some(result) = WhoopData::from_packet(packet, self.generation)
+                .ok()
+                .and_then(|data| decode(data, seq))
+            {
+                return Ok(result);
+            }
+        }
+    }
+
+    /// Read the strap RTC as unix seconds via `GetClock`.
+    pub async fn get_clock(&mut self) -> anyhow::Result<u32> {
+        self.request_command_response(WhoopPacket::get_clock(), "clock", |data, seq| match data {
+            WhoopData::ClockInfo { unix } => Some(unix),
+            WhoopData::CommandResponse(resp) if resp.origin_seq == seq => resp.get_clock_response(),
+            _ => None,
+        })
+        .await
+    }
+
+    /// Compare the strap RTC against system time.
+    pub async fn get_clock_drift(&mut self) -> anyhow::Result<ClockDrift> {
+        let sent_at = Utc::now();
+        let strap_unix = self.get_clock().await?;
+        Ok(ClockDrift::new(strap_unix, sent_at, Utc::now()))
+    }
+
+    /// Set the strap RTC to the current system time.
+    pub async fn set_clock(&mut self) -> anyhow::Result<()> {
+        self.send_command(WhoopPacket::set_time()?).await
+    }
+
+    /// Measure clock drift and store the offset in the device registry.
+    pub async fn record_clock_drift(&mut self) -> anyhow::Result<ClockDrift> {
+        let drift = self.get_clock_drift().await?;
+        info!(
+            "Strap clock offset: {:.3}s",
+            drift.offset.num_milliseconds() as f64 / 1000.0
+        );
+
+        match self.device_id.as_deref() {
+            Some(device_id) => {
+                self.whoop
+                    .database
+                    .record_clock_offset(
+                        device_id,
+                        self.generation,
+                        drift.offset,
+                        drift.system_time.with_timezone(&Local).naive_local(),
+                    )
+                    .await?;
+            }
+            None => debug!("No device id set; clock offset not recorded"),
+        }
+
+        Ok(drift)
+    }
+
+    /// Query where the strap thinks it is worn and whether it has skin contact.
+    pub async fn get_body_location(&mut self) -> anyhow::Result<BodyLocationInfo> {
+        self.request_command_response(
+            WhoopPacket::get_body_location(),
+            "body location",
+            |data, seq| match data {
+                WhoopData::BodyLocation(info) => Some(info),
+                WhoopData::CommandResponse(resp) if resp.origin_seq == seq => {
+                    resp.body_location_response()
+                }
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    pub async fn select_wrist(&mut self, wrist: Wrist) -> anyhow::Result<()> {
+        self.send_command(WhoopPacket::select_wrist(wrist)).await
+    }
+
+    /// Query the body location; readings downloaded afterwards are stored
+    /// with it as their wear window.
+    pub async fn record_wear_location(&mut self) -> anyhow::Result<BodyLocationInfo> {
+        let info = self.get_body_location().await?;
+        info!(
+            "Strap worn on: {}{}",
+            info.location,
+            info.wrist
+                .map(|wrist| format!(" ({wrist})"))
+                .unwrap_or_default()
+        );
+
+        self.whoop.wear_location = Some(info);
+        Ok(info)
+    }
+
     pub async fn get_battery_level(&mut self) -> anyhow::Result<u8> {
         let value = self
             .transport
@@ -565,6 +744,63 @@ where
             )),
         }
     }
+
+    /// Battery diagnostics, decoded for Gen4 straps only: the Gen5 response
+    /// layout hasn't been checked against a capture yet.
+    pub async fn get_extended_battery_info(&mut self) -> anyhow::Result<ExtendedBatteryInfo> {
+        self.ensure_battery_diagnostics_decoded()?;
+        self.request_command_response(
+            WhoopPacket::get_extended_battery_info(),
+            "extended battery",
+            |data, _| match data {
+                WhoopData::ExtendedBatteryInfo(info) => Some(info),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    /// Battery pack status, decoded for Gen4 straps only, as for
+    /// [`Self::get_extended_battery_info`].
+    pub async fn get_battery_pack_info(&mut self) -> anyhow::Result<BatteryPackInfo> {
+        self.ensure_battery_diagnostics_decoded()?;
+        self.request_command_response(
+            WhoopPacket::get_battery_pack_info(),
+            "battery pack",
+            |data, _| match data {
+                WhoopData::BatteryPackInfo(info) => Some(info),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    fn ensure_battery_diagnostics_decoded(&self) -> anyhow::Result<()> {
+        match self.generation {
+            WhoopGeneration::Gen4 => Ok(()),
+            generation => Err(anyhow!(
+                "extended battery diagnostics aren't decoded for {generation} straps yet"
+            )),
+        }
+    }
+
+    /// Store an extended battery reading in `battery_history`.
+    pub async fn record_battery_reading(
+        &self,
+        battery: &ExtendedBatteryInfo,
+        pack: Option<&BatteryPackInfo>,
+    ) -> anyhow::Result<()> {
+        self.whoop
+            .database
+            .record_battery_reading(
+                self.device_id.as_deref(),
+                Local::now().naive_local(),
+                battery,
+                pack,
+            )
+            .await?;
+        Ok(())
+    }
 }
 
 fn should_retry_gen5_history_after_full_reconnect(err: &anyhow::Error) -> bool {
@@ -584,6 +820,7 @@ mod tests {
     use super::*;
     use crate::ble::{BleNotificationStream, BleWriteType};
     use anyhow::anyhow;
+    use chrono::TimeZone;
     use futures::{StreamExt, channel::mpsc};
     use openwhoop_codec::{
         WhoopPacket,
@@ -622,6 +859,19 @@ mod tests {
         assert_eq!(window.readings.len(), StressCalculator::MIN_READING_PERIOD);
     }
 
+    #[test]
+    fn clock_drift_uses_midpoint_of_round_trip() {
+        let sent_at = DateTime::from_timestamp(1_700_000_010, 0).unwrap();
+        let received_at = sent_at + TimeDelta::seconds(2);
+
+        let drift = ClockDrift::new(1_700_000_000, sent_at, received_at);
+
+        assert_eq!(drift.system_time, sent_at + TimeDelta::seconds(1));
+        assert_eq!(drift.offset, TimeDelta::seconds(-11));
+    }
+
+    const MOCK_STRAP_UNIX: u32 = 1_700_000_000;
+
     #[derive(Clone, Copy)]
     enum DisconnectScenario {
         BeforeFirstPacket,
@@ -782,6 +1032,14 @@ mod tests {
             }
 
             match CommandNumber::from_u8(packet.cmd) {
+                Some(CommandNumber::GetClock) => {
+                    let mut body = vec![0];
+                    body.extend_from_slice(&MOCK_STRAP_UNIX.to_le_bytes());
+                    self.emit_cmd_response(packet.cmd, packet.seq, 1, body)?;
+                }
+                Some(CommandNumber::GetBodyLocationAndStatus) => {
+                    self.emit_cmd_response(packet.cmd, packet.seq, 1, vec![0, 1, 1, 1])?;
+                }
                 Some(CommandNumber::GetDataRange) => {
                     self.emit_cmd_response(packet.cmd, packet.seq, 1, Vec::new())?;
                 }
@@ -831,10 +1089,6 @@ mod tests {
                 }
                 Some(CommandNumber::HistoricalDataResult) => {
                     self.emit_cmd_response(packet.cmd, packet.seq, 1, Vec::new())?;
-                    match self.scenario {
-                        DisconnectScenario::StallThenRetrySucceeds => {}
-                        _ => {}
-                    }
                 }
                 _ => {}
             }
@@ -884,6 +1138,8 @@ mod tests {
         assert_eq!(
             transport.writes(),
             vec![
+                CommandNumber::GetClock.as_u8(),
+                CommandNumber::GetBodyLocationAndStatus.as_u8(),
                 CommandNumber::GetDataRange.as_u8(),
                 CommandNumber::SendHistoricalData.as_u8()
             ]
@@ -920,6 +1176,8 @@ mod tests {
         assert_eq!(
             transport.writes(),
             vec![
+                CommandNumber::GetClock.as_u8(),
+                CommandNumber::GetBodyLocationAndStatus.as_u8(),
                 CommandNumber::GetDataRange.as_u8(),
                 CommandNumber::SendHistoricalData.as_u8()
             ]
@@ -980,6 +1238,8 @@ mod tests {
         assert_eq!(
             transport.writes(),
             vec![
+                CommandNumber::GetClock.as_u8(),
+                CommandNumber::GetBodyLocationAndStatus.as_u8(),
                 CommandNumber::GetDataRange.as_u8(),
                 CommandNumber::SendHistoricalData.as_u8(),
                 CommandNumber::AbortHistoricalTransmits.as_u8(),
@@ -999,7 +1259,7 @@ mod tests {
         let err = device
             .sync_history(
                 Arc::new(AtomicBool::new(false)),
-                HistorySyncConfig::from_secs(30, 1),
+                HistorySyncConfig::from_secs(30, 1).with_exit_on_failure(true),
             )
             .await
             .expect_err("history sync should fail after stalling twice");
@@ -1011,6 +1271,8 @@ mod tests {
         assert_eq!(
             transport.writes(),
             vec![
+                CommandNumber::GetClock.as_u8(),
+                CommandNumber::GetBodyLocationAndStatus.as_u8(),
                 CommandNumber::GetDataRange.as_u8(),
                 CommandNumber::SendHistoricalData.as_u8(),
                 CommandNumber::AbortHistoricalTransmits.as_u8(),
@@ -1053,6 +1315,8 @@ mod tests {
         assert_eq!(
             transport.writes(),
             vec![
+                CommandNumber::GetClock.as_u8(),
+                CommandNumber::GetBodyLocationAndStatus.as_u8(),
                 CommandNumber::GetDataRange.as_u8(),
                 CommandNumber::SendHistoricalData.as_u8(),
                 CommandNumber::AbortHistoricalTransmits.as_u8(),
@@ -1088,6 +1352,8 @@ mod tests {
         assert_eq!(
             transport.writes(),
             vec![
+                CommandNumber::GetClock.as_u8(),
+                CommandNumber::GetBodyLocationAndStatus.as_u8(),
                 CommandNumber::GetDataRange.as_u8(),
                 CommandNumber::SendHistoricalData.as_u8(),
                 CommandNumber::AbortHistoricalTransmits.as_u8(),
@@ -1097,4 +1363,81 @@ mod tests {
             ]
         );
     }
+
+    #[tokio::test]
+    async fn sync_history_records_clock_offset_in_device_registry() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let db_check = db.clone();
+        let transport = MockTransport::new(DisconnectScenario::StallThenRetrySucceeds);
+        let mut device =
+            WhoopDeviceWith::from_transport(transport.clone(), db, false, WhoopGeneration::Gen5)
+                .with_device_id("AA:BB:CC:DD:EE:FF");
+
+        device
+            .sync_history(
+                Arc::new(AtomicBool::new(false)),
+                HistorySyncConfig::from_secs(30, 1),
+            )
+            .await
+            .expect("history sync should succeed");
+
+        let registered = db_check
+            .get_device("AA:BB:CC:DD:EE:FF")
+            .await
+            .unwrap()
+            .expect("device should be registered after sync");
+        assert_eq!(registered.generation, "WHOOP 5.0");
+        assert!(registered.clock_offset_ms.is_some_and(|offset| offset < 0));
+        assert!(registered.clock_checked_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn sync_history_records_wear_location_window() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let db_check = db.clone();
+        let transport = MockTransport::new(DisconnectScenario::StallThenRetrySucceeds);
+        let mut device =
+            WhoopDeviceWith::from_transport(transport, db, false, WhoopGeneration::Gen5)
+                .with_device_id("AA:BB:CC:DD:EE:FF");
+
+        device
+            .sync_history(
+                Arc::new(AtomicBool::new(false)),
+                HistorySyncConfig::from_secs(30, 1),
+            )
+            .await
+            .expect("history sync should succeed");
+
+        let reading_time = Local.timestamp_opt(1_700_000_000, 0).unwrap().naive_local();
+        let window = db_check
+            .get_wear_location_at(Some("AA:BB:CC:DD:EE:FF"), reading_time)
+            .await
+            .unwrap()
+            .expect("wear location should be recorded for the downloaded readings");
+        assert_eq!(window.start, reading_time);
+        assert_eq!(window.end, reading_time);
+        assert_eq!(window.device_id.as_deref(), Some("AA:BB:CC:DD:EE:FF"));
+        assert_eq!(window.location, "wrist");
+        assert_eq!(window.wrist.as_deref(), Some("right"));
+        assert!(window.on_body);
+    }
+
+    #[tokio::test]
+    async fn extended_battery_info_is_not_requested_from_gen5_straps() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let transport = MockTransport::new(DisconnectScenario::StallThenRetrySucceeds);
+        let mut device =
+            WhoopDeviceWith::from_transport(transport.clone(), db, false, WhoopGeneration::Gen5);
+
+        let err = device
+            .get_extended_battery_info()
+            .await
+            .expect_err("Gen5 extended battery info isn't decoded");
+        assert!(err.to_string().contains("WHOOP 5.0"));
+        device
+            .get_battery_pack_info()
+            .await
+            .expect_err("Gen5 battery pack info isn't decoded");
+        assert!(transport.writes().is_empty());
+    }
 }
@@ -0,0 +1,172 @@
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use chrono::{Local, NaiveDateTime};
+use openwhoop_db::{DatabaseHandler, SearchConsoleLogs};
+use serde_json::json;
+use zip::{ZipWriter, write::SimpleFileOptions};
+
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Bundle stored Memfault messages, the device registry and console logs
+/// since `since` into a zip archive under `output_dir`. Nothing leaves the
+/// machine; the archive is only written locally.
+pub async fn export_diagnostics(
+    db: &DatabaseHandler,
+    output_dir: &Path,
+    since: NaiveDateTime,
+) -> anyhow::Result<PathBuf> {
+    let diagnostics = db.get_diagnostics(None).await?;
+    let devices = db.get_devices().await?;
+    let logs = db
+        .search_console_logs(SearchConsoleLogs {
+            from: Some(since),
+            ..Default::default()
+        })
+        .await?;
+
+    std::fs::create_dir_all(output_dir)
+        .with_context(|| format!("failed to create {}", output_dir.display()))?;
+    let path = output_dir.join(format!(
+        "openwhoop-diagnostics-{}.zip",
+        Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    let file =
+        File::create(&path).with_context(|| format!("failed to create {}", path.display()))?;
+
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default();
+
+    for diagnostic in &diagnostics {
+        let name = format!(
+            "memfault/{}-{}.bin",
+            diagnostic.time.format("%Y%m%d-%H%M%S"),
+            diagnostic.id.simple()
+        );
+        zip.start_file(name, options)?;
+        zip.write_all(&diagnostic.data)?;
+    }
+
+    let devices = devices
+        .iter()
+        .map(|device| {
+            json!({
+                "device_id": device.device_id,
+                "generation": device.generation,
+                "last_seen": device.last_seen.format(TIME_FORMAT).to_string(),
+                "clock_offset_ms": device.clock_offset_ms,
+                "clock_checked_at": device
+                    .clock_checked_at
+                    .map(|time| time.format(TIME_FORMAT).to_string()),
+            })
+        })
+        .collect::<Vec<_>>();
+    zip.start_file("devices.json", options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&devices)?)?;
+
+    zip.start_file("console_logs.txt", options)?;
+    for log in &logs {
+        writeln!(
+            zip,
+            "{} [{}] {}",
+            log.time.format(TIME_FORMAT),
+            log.kind.as_deref().unwrap_or("-"),
+            log.message
+        )?;
+    }
+
+    let manifest = json!({
+        "exported_at": Local::now().naive_local().format(TIME_FORMAT).to_string(),
+        "console_logs_since": since.format(TIME_FORMAT).to_string(),
+        "memfault_messages": diagnostics
+            .iter()
+            .map(|diagnostic| json!({
+                "time": diagnostic.time.format(TIME_FORMAT).to_string(),
+                "generation": diagnostic.generation,
+                "chunks": diagnostic.chunks,
+                "bytes": diagnostic.data.len(),
+            }))
+            .collect::<Vec<_>>(),
+        "console_logs": logs.len(),
+    });
+    zip.start_file("manifest.json", options)?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest)?)?;
+
+    zip.finish()?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use openwhoop_codec::{MemfaultMessage, constants::WhoopGeneration};
+    use std::io::Read;
+
+    #[tokio::test]
+    async fn export_diagnostics_bundles_memfault_devices_and_logs() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let now = Local::now().naive_local();
+
+        db.create_diagnostic(
+            WhoopGeneration::Gen5,
+            Some("AA:BB:CC:DD:EE:FF"),
+            now,
+            MemfaultMessage {
+                data: vec![0xde, 0xad],
+                chunks: 2,
+                expected_len: Some(2),
+            },
+        )
+        .await
+        .unwrap();
+        db.record_clock_offset(
+            "AA:BB:CC:DD:EE:FF",
+            WhoopGeneration::Gen5,
+            TimeDelta::milliseconds(-800),
+            now,
+        )
+        .await
+        .unwrap();
+        let recent = u32::try_from(Local::now().timestamp()).unwrap();
+        let old = recent - 30 * 24 * 60 * 60;
+        db.create_console_log(None, old, "Boot reason: POR")
+            .await
+            .unwrap();
+        db.create_console_log(None, recent, "Boot reason: WDT")
+            .await
+            .unwrap();
+
+        let dir = std::env::temp_dir().join(format!("openwhoop-export-{}", uuid::Uuid::new_v4()));
+        let path = export_diagnostics(&db, &dir, now - TimeDelta::days(1))
+            .await
+            .unwrap();
+
+        let mut archive = zip::ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let names = archive.file_names().map(str::to_owned).collect::<Vec<_>>();
+        assert!(names.iter().any(|name| name.starts_with("memfault/")));
+
+        let mut devices = String::new();
+        archive
+            .by_name("devices.json")
+            .unwrap()
+            .read_to_string(&mut devices)
+            .unwrap();
+        assert!(devices.contains("AA:BB:CC:DD:EE:FF"));
+
+        let mut logs = String::new();
+        archive
+            .by_name("console_logs.txt")
+            .unwrap()
+            .read_to_string(&mut logs)
+            .unwrap();
+        assert!(logs.contains("[reset] Boot reason: WDT"));
+        assert!(!logs.contains("POR"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -8,13 +8,15 @@ pub mod db {
 pub mod ble;
 
 mod device;
-pub use device::{HistorySyncConfig, WhoopDevice, WhoopDeviceWith};
+pub use device::{ClockDrift, HistorySyncConfig, WhoopDevice, WhoopDeviceWith};
 
 mod openwhoop;
 pub use openwhoop::OpenWhoop;
 
 pub mod api;
 
+pub mod diagnostics;
+
 pub mod algo {
     pub use openwhoop_algos::*;
 }
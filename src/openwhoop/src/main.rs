@@ -2,6 +2,7 @@
 extern crate log;
 
 use std::{
+    collections::HashMap,
     env, fs, io,
     path::{Path, PathBuf},
     str::FromStr,
@@ -17,20 +18,25 @@ use btleplug::{
     api::{BDAddr, Central, Manager as _, Peripheral as _, ScanFilter},
     platform::{Adapter, Manager, Peripheral},
 };
-use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc};
 use clap::{CommandFactory, Parser, Subcommand};
 use clap_complete::{Shell, generate};
 use dotenv::dotenv;
-use openwhoop::api;
 use openwhoop::{
     HistorySyncConfig, OpenWhoop, WhoopDevice,
-    algo::{ExerciseMetrics, SleepConsistencyAnalyzer},
-    db::DatabaseHandler,
+    algo::{
+        AlertThresholds, CircadianAnalyzer, ExerciseMetrics, HeartRateRhythm, HeartRateZones,
+        RespiratoryRate, Sex, SleepConsistencyAnalyzer, Vo2MaxEstimator, ZoneBasis, ZoneBreakdown,
+    },
+    db::{DatabaseHandler, SearchConsoleLogs, SearchHistory},
     types::activities::{ActivityType, SearchActivityPeriods},
 };
-use openwhoop_algos::SleepCycle;
+use openwhoop::{api, diagnostics};
+use openwhoop_algos::{
+    SleepCycle, SleepStager, SyntheticNight, SyntheticScenario, helpers::format_hm::FormatHM,
+};
 use openwhoop_codec::{
-    WhoopPacket,
+    WhoopPacket, Wrist,
     constants::{ALL_WHOOP_SERVICES, WhoopGeneration},
 };
 use openwhoop_entities::{heart_rate, packets};
@@ -115,6 +121,127 @@ pub enum OpenWhoopCommand {
     ///
     CalculateStress,
     ///
+    /// Calculate HRV over 5-minute windows of historical data
+    ///
+    CalculateHrv,
+    ///
+    /// Calculate SpO2 from raw red/IR sensor data
+    ///
+    CalculateSpo2,
+    ///
+    /// Convert raw skin temperature and compute nightly deviation
+    ///
+    CalculateSkinTemp,
+    ///
+    /// Estimate the respiratory rate of stored sleeps that have none
+    ///
+    CalculateRespRate,
+    ///
+    /// Print tonight's sleep need and recommended bedtime
+    ///
+    SleepNeed {
+        #[arg(long, default_value = "07:00:00", help = "Wake time, e.g. 07:00:00")]
+        wake: NaiveTime,
+    },
+    ///
+    /// Calculate daily recovery and print the last days
+    ///
+    Recovery {
+        #[arg(long, default_value_t = 7, help = "Print the last N days")]
+        days: i64,
+    },
+    ///
+    /// Flag nights off the personal baseline as early warnings of illness or
+    /// overreaching and print the alerts
+    ///
+    HealthAlerts {
+        #[arg(long, default_value_t = 30, help = "Print alerts of the last N days")]
+        days: i64,
+        #[arg(
+            long,
+            default_value_t = 2.0,
+            help = "Smallest z-score for a metric to deviate"
+        )]
+        z_score: f64,
+        #[arg(
+            long,
+            default_value_t = 2,
+            help = "Metrics that have to deviate on a night"
+        )]
+        min_metrics: usize,
+        #[arg(
+            long,
+            default_value_t = 2,
+            help = "Deviating nights in a row before an alert"
+        )]
+        min_nights: usize,
+    },
+    ///
+    /// Count steps from accelerometer data and print daily totals
+    ///
+    Steps {
+        #[arg(long, default_value_t = 7, help = "Print the last N days")]
+        days: i64,
+    },
+    ///
+    /// Estimate calories burned from heart rate and print daily totals
+    ///
+    Calories {
+        #[arg(long, default_value_t = 7, help = "Print the last N days")]
+        days: i64,
+    },
+    ///
+    /// Estimate weekly VO2max and print the cardio fitness trend
+    ///
+    Fitness {
+        #[arg(long, default_value_t = 12, help = "Print the last N weeks")]
+        weeks: i64,
+    },
+    ///
+    /// Print HRV metrics for a time range, by default the last sleep
+    ///
+    Hrv {
+        #[arg(long, value_parser = parse_cli_datetime, help = "Start time, e.g. 2025-01-31 or \"2025-01-31 22:00\"")]
+        from: Option<NaiveDateTime>,
+        #[arg(long, value_parser = parse_cli_datetime, help = "End time (exclusive)")]
+        to: Option<NaiveDateTime>,
+        #[arg(long, help = "Print as JSON")]
+        json: bool,
+    },
+    ///
+    /// Calculate time in heart rate zones and print daily totals
+    ///
+    Zones {
+        #[arg(long, default_value_t = 7, help = "Print the last N days")]
+        days: i64,
+    },
+    ///
+    /// List activities with their detected type and confidence
+    ///
+    Activities {
+        #[arg(long, default_value_t = 7, help = "List the last N days")]
+        days: i64,
+    },
+    ///
+    /// Confirm the detected type of an activity or override it
+    ///
+    LabelActivity {
+        #[arg(value_parser = parse_cli_datetime, help = "Activity start, as listed by `activities`")]
+        start: NaiveDateTime,
+        #[arg(
+            value_parser = parse_activity_type,
+            help = "New type, e.g. Running; omit to confirm the detected one"
+        )]
+        activity: Option<ActivityType>,
+    },
+    ///
+    /// Show or update the user profile (age, sex, weight, height, max heart rate)
+    ///
+    Profile {
+        #[clap(subcommand)]
+        action: ProfileCommand,
+    },
+    ///
     /// Set alarm
     ///
     SetAlarm {
@@ -153,9 +280,77 @@ pub enum OpenWhoopCommand {
     ///
     /// Get current device battery level
     ///
+    #[command(visible_alias = "battery")]
     GetBattery {
         #[arg(long, env)]
         whoop: DeviceId,
+        #[arg(
+            long,
+            help = "Report voltage, temperature, charge state, cycle count and battery pack status"
+        )]
+        extended: bool,
+        #[arg(
+            long,
+            requires = "extended",
+            help = "Store the extended reading in the battery_history table"
+        )]
+        log: bool,
+    },
+    ///
+    /// Print stored strap console logs
+    ///
+    Logs {
+        #[arg(long, value_parser = parse_cli_datetime, help = "Start time, e.g. 2025-01-31 or \"2025-01-31 22:00\"")]
+        from: Option<NaiveDateTime>,
+        #[arg(long, value_parser = parse_cli_datetime, help = "End time (exclusive)")]
+        to: Option<NaiveDateTime>,
+        #[arg(long, help = "Only show messages containing this text")]
+        grep: Option<String>,
+        #[arg(
+            long,
+            help = "Only show structured events: reset, sensor_fault, battery_warning"
+        )]
+        kind: Option<String>,
+        #[arg(long)]
+        limit: Option<u64>,
+    },
+    ///
+    /// Bundle Memfault diagnostics, device registry and recent console logs into a zip
+    ///
+    ExportDiagnostics {
+        #[arg(long, default_value = ".")]
+        output: String,
+        #[arg(
+            long,
+            default_value_t = 7,
+            help = "Include console logs from the last N days"
+        )]
+        days: i64,
+    },
+    ///
+    /// Report where the strap thinks it is worn and its wear status
+    ///
+    BodyLocation {
+        #[arg(long, env)]
+        whoop: DeviceId,
+    },
+    ///
+    /// Tell the strap which wrist it is worn on
+    ///
+    SelectWrist {
+        #[arg(long, env)]
+        whoop: DeviceId,
+        #[arg(help = "left or right")]
+        wrist: Wrist,
+    },
+    ///
+    /// Read the strap clock, report drift against system time and correct it
+    ///
+    Clock {
+        #[arg(long, env)]
+        whoop: DeviceId,
+        #[arg(long, help = "Only report the drift; do not set the strap clock")]
+        dry_run: bool,
     },
     ///
     /// Copy packets from one database into another
@@ -228,7 +423,7 @@ pub enum OpenWhoopCommand {
         output_dir: String,
     },
     ///
-    /// Generate a fresh SQLite database with synthetic heart-rate and sleep data
+    /// Generate a fresh SQLite database with synthetic heart-rate and staged sleep data
     ///
     GenerateTestDb {
         #[arg(help = "Number of past days to generate")]
@@ -243,6 +438,42 @@ pub enum OpenWhoopCommand {
         sleep_start: NaiveTime,
         #[arg(long, default_value = "07:00:00")]
         sleep_end: NaiveTime,
+        #[arg(
+            long,
+            default_value_t = SyntheticScenario::Healthy,
+            help = "Course of the nightly vitals: healthy, or illness or overreaching in the last nights"
+        )]
+        scenario: SyntheticScenario,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProfileCommand {
+    ///
+    /// Print the profile and the heart rate limits derived from it
+    ///
+    Show,
+    ///
+    /// Update profile fields; fields not given are kept
+    ///
+    Set {
+        #[arg(long, help = "Birth date, e.g. 1990-05-31")]
+        birth_date: Option<NaiveDate>,
+        #[arg(long, help = "male or female")]
+        sex: Option<Sex>,
+        #[arg(long, help = "Weight in kg")]
+        weight: Option<f64>,
+        #[arg(long, help = "Height in cm")]
+        height: Option<f64>,
+        #[arg(long, help = "Known max heart rate, overrides the age prediction")]
+        max_hr: Option<u8>,
+        #[arg(long, conflicts_with = "max_hr", help = "Use the age prediction again")]
+        clear_max_hr: bool,
+        #[arg(
+            long,
+            help = "Heart rate zones as % of heart rate reserve (hrr) or max heart rate (hrmax)"
+        )]
+        zone_basis: Option<ZoneBasis>,
     },
 }
 
@@ -463,6 +694,19 @@ async fn download_firmware(
     Ok(())
 }
 
+fn parse_cli_datetime(value: &str) -> Result<NaiveDateTime, String> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|date| date.and_time(NaiveTime::MIN))
+        })
+        .map_err(|_| format!("invalid time {value:?}, expected YYYY-MM-DD [HH:MM[:SS]]"))
+}
+
+fn parse_activity_type(value: &str) -> Result<ActivityType, String> {
+    ActivityType::from_str(value).map_err(|_| format!("unknown activity type {value:?}"))
+}
+
 fn sqlite_database_url(path: &Path) -> String {
     format!("sqlite://{}?mode=rwc", path.display())
 }
@@ -551,6 +795,7 @@ async fn generate_test_db(
     sleep_hr: u16,
     sleep_start: NaiveTime,
     sleep_end: NaiveTime,
+    scenario: SyntheticScenario,
 ) -> anyhow::Result<()> {
     anyhow::ensure!(days > 0, "days must be greater than 0");
     anyhow::ensure!(sleep_hr > 0, "sleep_hr must be greater than 0");
@@ -585,6 +830,13 @@ async fn generate_test_db(
     let start_time = start_date.and_hms_opt(0, 0, 0).expect("valid midnight");
 
     let mut readings = Vec::new();
+    let mut nights = HashMap::new();
+    let night_hr = u8::try_from(sleep_hr).unwrap_or(u8::MAX);
+    let nights_total = usize::try_from(days).unwrap_or(usize::MAX);
+    let vitals = |day: chrono::NaiveDate| {
+        let night = usize::try_from((day - start_date).num_days()).unwrap_or(0);
+        scenario.vitals(night, nights_total, night_hr)
+    };
 
     for step in 0..(i64::from(days) * 24 * 60 * 60) {
         let time = start_time + TimeDelta::seconds(step);
@@ -593,6 +845,43 @@ async fn generate_test_db(
         }
 
         let sleep_day = is_sleep_time(time, sleep_start, sleep_end);
+        if let Some(day) = sleep_day {
+            let (start, end) = sleep_window_for_day(day, sleep_start, sleep_end);
+            let night = nights
+                .entry(day)
+                .or_insert_with(|| SyntheticNight::generate(start, end, vitals(day).sleep_hr));
+            let reading = usize::try_from((time - start).num_seconds())
+                .ok()
+                .and_then(|index| night.readings.get(index));
+
+            if let Some(reading) = reading {
+                readings.push(heart_rate::ActiveModel {
+                    id: NotSet,
+                    bpm: Set(i16::from(reading.bpm)),
+                    time: Set(time),
+                    rr_intervals: Set(reading
+                        .rr
+                        .iter()
+                        .map(u16::to_string)
+                        .collect::<Vec<_>>()
+                        .join(",")),
+                    activity: NotSet,
+                    stress: NotSet,
+                    stress_artifact_rate: NotSet,
+                    spo2: Set(Some(vitals(day).spo2)),
+                    skin_temp: NotSet,
+                    imu_data: NotSet,
+                    sensor_data: Set(reading
+                        .sensor_data
+                        .as_ref()
+                        .map(serde_json::to_value)
+                        .transpose()?),
+                    synced: Set(false),
+                });
+                continue;
+            }
+        }
+
         let bpm = synthetic_bpm(
             if sleep_day.is_some() {
                 sleep_hr
@@ -610,6 +899,7 @@ async fn generate_test_db(
             rr_intervals: Set(rr_from_bpm(bpm)),
             activity: NotSet,
             stress: NotSet,
+            stress_artifact_rate: NotSet,
             spo2: NotSet,
             skin_temp: NotSet,
             imu_data: NotSet,
@@ -631,19 +921,38 @@ async fn generate_test_db(
             break;
         }
 
-        db.create_sleep(SleepCycle {
-            id: sleep_day,
-            start,
-            end: if end > now { now } else { end },
-            min_bpm: sleep_hr.saturating_sub(4) as u8,
-            max_bpm: sleep_hr.saturating_add(4) as u8,
-            avg_bpm: sleep_hr as u8,
-            min_hrv: 48,
-            max_hrv: 82,
-            avg_hrv: 64,
-            score: 100.0,
-        })
+        let end = if end > now { now } else { end };
+        let vitals = vitals(sleep_day);
+        let epochs = nights
+            .get(&sleep_day)
+            .map(|night| SleepStager::stage(&night.readings, start, end))
+            .unwrap_or_default();
+
+        db.create_sleep(
+            SleepCycle {
+                id: sleep_day,
+                start,
+                end,
+                min_bpm: vitals.sleep_hr.saturating_sub(4),
+                max_bpm: vitals.sleep_hr.saturating_add(4),
+                avg_bpm: vitals.sleep_hr,
+                min_hrv: vitals.hrv.saturating_sub(16),
+                max_hrv: vitals.hrv.saturating_add(18),
+                avg_hrv: vitals.hrv,
+                score: 100.0,
+                resp_rate: Some(RespiratoryRate {
+                    min: vitals.resp_rate - 1.5,
+                    avg: vitals.resp_rate,
+                    max: vitals.resp_rate + 1.5,
+                }),
+                ..Default::default()
+            }
+            .with_stages(&epochs),
+        )
         .await?;
+        db.replace_sleep_stages(sleep_day, &epochs).await?;
+        db.update_sleep_skin_temp(sleep_day, vitals.skin_temp, None)
+            .await?;
     }
 
     println!(
@@ -844,6 +1153,7 @@ impl OpenWhoopCli {
                 sleep_hr,
                 sleep_start,
                 sleep_end,
+                scenario,
             } => {
                 return generate_test_db(
                     output.clone(),
@@ -852,6 +1162,7 @@ impl OpenWhoopCli {
                     sleep_hr,
                     sleep_start,
                     sleep_end,
+                    scenario,
                 )
                 .await;
             }
@@ -873,6 +1184,7 @@ impl OpenWhoopCli {
                 history_timeout_secs,
                 history_idle_timeout_secs,
             } => {
+                let device_id = whoop.to_string();
                 let (peripheral, generation) = scan_command(&adapter, Some(whoop)).await?;
                 let mut whoop = WhoopDevice::new(
                     peripheral,
@@ -880,7 +1192,8 @@ impl OpenWhoopCli {
                     db_handler,
                     self.debug_packets,
                     generation,
-                );
+                )
+                .with_device_id(device_id);
 
                 let should_exit = Arc::new(AtomicBool::new(false));
 
@@ -958,21 +1271,81 @@ impl OpenWhoopCli {
                     .collect::<Vec<_>>();
 
                 last_week.reverse();
+                let all_time_resp_rate =
+                    RespiratoryRate::mean(sleep_records.iter().filter_map(|s| s.resp_rate));
+                let week_resp_rate =
+                    RespiratoryRate::mean(last_week.iter().filter_map(|s| s.resp_rate));
+
+                let epochs = whoop
+                    .database
+                    .get_sleep_stages_between(
+                        sleep_records[0].id,
+                        sleep_records[sleep_records.len() - 1].id,
+                    )
+                    .await?;
+                let week_epochs = epochs
+                    .iter()
+                    .filter(|epoch| epoch.start >= last_week[0].start)
+                    .copied()
+                    .collect::<Vec<_>>();
+                let recorded = whoop
+                    .database
+                    .get_recorded_minutes(
+                        sleep_records[0].start,
+                        sleep_records[sleep_records.len() - 1].end,
+                    )
+                    .await?;
+                let all_time_circadian =
+                    CircadianAnalyzer::analyze(&sleep_records, &epochs, &recorded);
+                let week_circadian =
+                    CircadianAnalyzer::analyze(&last_week, &week_epochs, &recorded);
+
                 let analyzer = SleepConsistencyAnalyzer::new(sleep_records);
                 let metrics = analyzer.calculate_consistency_metrics()?;
                 println!("All time: \n{}", metrics);
-                let analyzer = SleepConsistencyAnalyzer::new(last_week);
+                if let Some(resp_rate) = all_time_resp_rate {
+                    println!("Respiratory rate: {}", resp_rate);
+                }
+                println!("{}", all_time_circadian);
+                let analyzer = SleepConsistencyAnalyzer::new(last_week.clone());
                 let metrics = analyzer.calculate_consistency_metrics()?;
                 println!("\nWeek: \n{}", metrics);
+                if let Some(resp_rate) = week_resp_rate {
+                    println!("Respiratory rate: {}", resp_rate);
+                }
+                println!("{}", week_circadian);
+                if let Some(last) = last_week.last() {
+                    let history = whoop
+                        .database
+                        .search_history(SearchHistory {
+                            from: Some(last.end - TimeDelta::days(7)),
+                            to: Some(last.end),
+                            limit: None,
+                        })
+                        .await?;
+                    if let Some(rhythm) = HeartRateRhythm::fit(&history) {
+                        println!("{}", rhythm);
+                    }
+                }
+                if let Some(last) = last_week.last() {
+                    match last.score_breakdown {
+                        Some(score) => println!("\nLast night score: {}", score),
+                        None => println!("\nLast night score: {:.0}", last.score),
+                    }
+                    if let Some(rate) = last.rr_artifact_rate {
+                        println!("RR artifacts: {:.1}%", rate);
+                    }
+                }
             }
             OpenWhoopCommand::ExerciseStats => {
                 let whoop = OpenWhoop::new(db_handler, WhoopGeneration::Placeholder);
                 let exercises = whoop
                     .database
-                    .search_activities(
-                        SearchActivityPeriods::default().with_activity(ActivityType::Activity),
-                    )
-                    .await?;
+                    .search_activities(SearchActivityPeriods::default())
+                    .await?
+                    .into_iter()
+                    .filter(|activity| activity.activity != ActivityType::Nap)
+                    .collect::<Vec<_>>();
 
                 if exercises.is_empty() {
                     println!("No activities found, exiting now");
@@ -987,16 +1360,359 @@ impl OpenWhoopCli {
                     .rev()
                     .collect::<Vec<_>>();
 
+                let zones_since = |from: NaiveDateTime| {
+                    let db = &whoop.database;
+                    async move {
+                        anyhow::Ok(
+                            db.get_activities_since(from)
+                                .await?
+                                .iter()
+                                .filter(|activity| {
+                                    activity.activity != ActivityType::Nap.to_string()
+                                })
+                                .filter_map(ZoneBreakdown::from_activity)
+                                .sum::<ZoneBreakdown>(),
+                        )
+                    }
+                };
+                let zones = zones_since(exercises[0].from).await?;
+                let last_week_zones = zones_since(last_week[0].from).await?;
+
                 let metrics = ExerciseMetrics::new(exercises)?;
                 let last_week = ExerciseMetrics::new(last_week)?;
 
                 println!("All time: \n{}", metrics);
+                println!("Zones: {}\n", zones);
                 println!("Last week: \n{}", last_week);
+                println!("Zones: {}", last_week_zones);
             }
             OpenWhoopCommand::CalculateStress => {
                 let whoop = OpenWhoop::new(db_handler, WhoopGeneration::Placeholder);
                 whoop.calculate_stress().await?;
             }
+            OpenWhoopCommand::CalculateHrv => {
+                let whoop = OpenWhoop::new(db_handler, WhoopGeneration::Placeholder);
+                whoop.calculate_hrv_samples().await?;
+            }
+            OpenWhoopCommand::CalculateSpo2 => {
+                let whoop = OpenWhoop::new(db_handler, WhoopGeneration::Placeholder);
+                whoop.calculate_spo2().await?;
+            }
+            OpenWhoopCommand::CalculateSkinTemp => {
+                let whoop = OpenWhoop::new(db_handler, WhoopGeneration::Placeholder);
+                whoop.calculate_skin_temp().await?;
+            }
+            OpenWhoopCommand::CalculateRespRate => {
+                let whoop = OpenWhoop::new(db_handler, WhoopGeneration::Placeholder);
+                whoop.calculate_resp_rate().await?;
+            }
+            OpenWhoopCommand::SleepNeed { wake } => {
+                let whoop = OpenWhoop::new(db_handler, WhoopGeneration::Placeholder);
+                let now = Local::now().naive_local();
+                let mut wake_at = now.date().and_time(wake);
+                if wake_at <= now {
+                    wake_at += TimeDelta::days(1);
+                }
+
+                let need = whoop.sleep_need(now).await?;
+                println!("Sleep need: {}", need);
+                println!(
+                    "Recommended bedtime: {} to wake at {} ({} in bed at {:.0}% efficiency)",
+                    need.bedtime(wake_at).format("%Y-%m-%d %H:%M"),
+                    wake_at.format("%Y-%m-%d %H:%M"),
+                    need.time_in_bed().format_hm(),
+                    need.efficiency * 100.0
+                );
+            }
+            OpenWhoopCommand::Recovery { days } => {
+                let whoop = OpenWhoop::new(db_handler, WhoopGeneration::Placeholder);
+                whoop.calculate_recovery().await?;
+
+                let Some(latest) = whoop.database.get_latest_recovery().await? else {
+                    println!("Not enough sleep data for recovery, exiting now");
+                    return Ok(());
+                };
+
+                let from = latest.date - TimeDelta::days(days.max(1) - 1);
+                for recovery in whoop.database.get_recoveries(Some(from)).await? {
+                    println!("{}", recovery);
+                }
+            }
+            OpenWhoopCommand::HealthAlerts {
+                days,
+                z_score,
+                min_metrics,
+                min_nights,
+            } => {
+                let whoop = OpenWhoop::new(db_handler, WhoopGeneration::Placeholder);
+                whoop
+                    .calculate_health_alerts(AlertThresholds {
+                        z_score,
+                        min_metrics,
+                        min_nights,
+                    })
+                    .await?;
+
+                let from = Local::now().date_naive() - TimeDelta::days(days.max(1) - 1);
+                let alerts = whoop.database.get_health_alerts(Some(from)).await?;
+                if alerts.is_empty() {
+                    println!("No health alerts in the last {} days", days.max(1));
+                }
+                for alert in alerts {
+                    println!("{}", alert);
+                }
+            }
+            OpenWhoopCommand::Steps { days } => {
+                let whoop = OpenWhoop::new(db_handler, WhoopGeneration::Placeholder);
+                whoop.calculate_steps().await?;
+
+                let today = Local::now().date_naive();
+                for offset in (0..days.max(1)).rev() {
+                    let date = today - TimeDelta::days(offset);
+                    match whoop.database.get_steps_for_date(date).await? {
+                        Some(steps) => println!("{}: {} steps", date, steps),
+                        None => println!("{}: -", date),
+                    }
+                }
+            }
+            OpenWhoopCommand::Calories { days } => {
+                let whoop = OpenWhoop::new(db_handler, WhoopGeneration::Placeholder);
+                whoop.calculate_latest_calories().await?;
+
+                let today = Local::now().date_naive();
+                for offset in (0..days.max(1)).rev() {
+                    let date = today - TimeDelta::days(offset);
+                    match whoop.database.get_calories_for_date(date).await? {
+                        Some(calories) => println!(
+                            "{}: {:.0} kcal (active {:.0})",
+                            date, calories.total, calories.active
+                        ),
+                        None => println!("{}: -", date),
+                    }
+                }
+            }
+            OpenWhoopCommand::Fitness { weeks } => {
+                let whoop = OpenWhoop::new(db_handler, WhoopGeneration::Placeholder);
+                whoop.calculate_latest_vo2max().await?;
+
+                let from = Vo2MaxEstimator::week_start(Local::now().date_naive())
+                    - TimeDelta::weeks(weeks.max(1) - 1);
+                let estimates = whoop.database.get_vo2max(Some(from)).await?;
+                if estimates.is_empty() {
+                    println!("No VO2max estimates yet, they need a resting and max heart rate");
+                    return Ok(());
+                }
+
+                let mut previous = None;
+                for estimate in &estimates {
+                    match previous {
+                        Some(previous) => {
+                            println!("{} [{:+.1}]", estimate, estimate.vo2max - previous)
+                        }
+                        None => println!("{}", estimate),
+                    }
+                    previous = Some(estimate.vo2max);
+                }
+
+                match Vo2MaxEstimator::trend(&estimates) {
+                    Some(trend) => println!(
+                        "Trend: {:+.2} ml/kg/min per week over {} weeks",
+                        trend,
+                        estimates.len()
+                    ),
+                    None => println!("Trend: needs at least two weeks"),
+                }
+            }
+            OpenWhoopCommand::Hrv { from, to, json } => {
+                let (from, to) = match (from, to) {
+                    (None, None) => match db_handler.get_latest_sleep().await? {
+                        Some(sleep) => (Some(sleep.start), Some(sleep.end)),
+                        None => {
+                            println!("No sleep found, pass --from/--to");
+                            return Ok(());
+                        }
+                    },
+                    range => range,
+                };
+
+                let metrics = db_handler
+                    .get_hrv(SearchHistory {
+                        from: from.map(|from| from - TimeDelta::milliseconds(1)),
+                        to,
+                        limit: None,
+                    })
+                    .await?;
+
+                if json {
+                    let value = serde_json::json!({
+                        "from": from.map(|from| from.to_string()),
+                        "to": to.map(|to| to.to_string()),
+                        "metrics": metrics.map(|m| serde_json::json!({
+                            "count": m.count,
+                            "artifact_rate": m.artifact_rate,
+                            "mean_nn": m.mean_nn,
+                            "sdnn": m.sdnn,
+                            "rmssd": m.rmssd,
+                            "pnn50": m.pnn50,
+                            "lf": m.lf,
+                            "hf": m.hf,
+                            "lf_hf": m.lf_hf,
+                            "sd1": m.sd1,
+                            "sd2": m.sd2,
+                            "dfa_alpha1": m.dfa_alpha1,
+                        })),
+                    });
+                    println!("{}", serde_json::to_string_pretty(&value)?);
+                } else {
+                    let bound = |time: Option<NaiveDateTime>| {
+                        time.map_or_else(
+                            || "-".to_owned(),
+                            |time| time.format("%Y-%m-%d %H:%M").to_string(),
+                        )
+                    };
+                    println!("HRV {} - {}", bound(from), bound(to));
+                    match metrics {
+                        Some(metrics) => println!("{}", metrics),
+                        None => println!("Not enough RR intervals"),
+                    }
+                }
+            }
+            OpenWhoopCommand::Zones { days } => {
+                let whoop = OpenWhoop::new(db_handler, WhoopGeneration::Placeholder);
+                whoop.calculate_latest_zones().await?;
+
+                let now = Local::now().naive_local();
+                let profile = whoop.database.get_profile().await?;
+                let basis = profile.zone_basis.unwrap_or_default();
+                if let (Some(max_hr), Some(resting_hr)) = (
+                    whoop.database.get_max_hr(now, now).await?,
+                    whoop.database.get_resting_hr_before(now).await?,
+                ) {
+                    let bounds = HeartRateZones::new(max_hr, resting_hr, basis).lower_bounds();
+                    let bounds = bounds
+                        .iter()
+                        .enumerate()
+                        .map(|(zone, bpm)| format!("Z{}: {}+", zone + 1, bpm))
+                        .collect::<Vec<_>>();
+                    println!("Zones ({}, bpm): {}\n", basis, bounds.join("  "));
+                }
+
+                let today = now.date();
+                for offset in (0..days.max(1)).rev() {
+                    let date = today - TimeDelta::days(offset);
+                    match whoop.database.get_zones_for_date(date).await? {
+                        Some(zones) => println!("{}: {}", date, zones),
+                        None => println!("{}: -", date),
+                    }
+                }
+            }
+            OpenWhoopCommand::Activities { days } => {
+                let from = Local::now().naive_local() - TimeDelta::days(days);
+                for activity in db_handler.get_activities_since(from).await? {
+                    let end = activity.end.map_or_else(
+                        || "ongoing".to_owned(),
+                        |end| end.format("%H:%M").to_string(),
+                    );
+                    let label = match (activity.confirmed, activity.confidence) {
+                        (true, _) => "confirmed".to_owned(),
+                        (false, Some(confidence)) => format!("{:.0}%", confidence * 100.0),
+                        (false, None) => "-".to_owned(),
+                    };
+                    println!(
+                        "{} - {}  {} ({})",
+                        activity.start.format("%Y-%m-%d %H:%M:%S"),
+                        end,
+                        activity.activity,
+                        label
+                    );
+                }
+            }
+            OpenWhoopCommand::LabelActivity { start, activity } => {
+                if !db_handler.label_activity(start, activity).await? {
+                    error!(
+                        "No activity starts at {}",
+                        start.format("%Y-%m-%d %H:%M:%S")
+                    );
+                }
+            }
+            OpenWhoopCommand::Profile { action } => {
+                match action {
+                    ProfileCommand::Show => {
+                        let profile = db_handler.get_profile().await?;
+                        let now = Local::now().naive_local();
+                        let field = |value: Option<String>| value.unwrap_or_else(|| "-".to_owned());
+
+                        println!(
+                            "Birth date: {}",
+                            field(profile.birth_date.map(
+                                |birth| match profile.age_on(now.date()) {
+                                    Some(age) => format!("{} (age {})", birth, age),
+                                    None => birth.to_string(),
+                                }
+                            ))
+                        );
+                        println!("Sex: {}", field(profile.sex.map(|sex| sex.to_string())));
+                        println!(
+                            "Weight: {}",
+                            field(profile.weight_kg.map(|kg| format!("{:.1} kg", kg)))
+                        );
+                        println!(
+                            "Height: {}",
+                            field(profile.height_cm.map(|cm| format!("{:.0} cm", cm)))
+                        );
+
+                        let max_hr = match (profile.max_hr, profile.predicted_max_hr(now.date())) {
+                            (Some(max_hr), _) => format!("{} bpm (configured)", max_hr),
+                            (None, Some(max_hr)) => format!("{} bpm (predicted from age)", max_hr),
+                            (None, None) => field(
+                                db_handler
+                                    .get_max_hr_before(now, now)
+                                    .await?
+                                    .map(|max_hr| format!("{} bpm (highest sustained)", max_hr)),
+                            ),
+                        };
+                        println!("Max HR: {}", max_hr);
+                        println!(
+                            "Resting HR: {}",
+                            field(
+                                db_handler
+                                    .get_resting_hr_before(now)
+                                    .await?
+                                    .map(|bpm| format!("{} bpm", bpm))
+                            )
+                        );
+                        println!(
+                            "Zone basis: {}",
+                            profile.zone_basis.map_or_else(
+                                || format!("{} (default)", ZoneBasis::default()),
+                                |basis| basis.to_string()
+                            )
+                        );
+                    }
+                    ProfileCommand::Set {
+                        birth_date,
+                        sex,
+                        weight,
+                        height,
+                        max_hr,
+                        clear_max_hr,
+                        zone_basis,
+                    } => {
+                        let mut profile = db_handler.get_profile().await?;
+                        profile.birth_date = birth_date.or(profile.birth_date);
+                        profile.sex = sex.or(profile.sex);
+                        profile.weight_kg = weight.or(profile.weight_kg);
+                        profile.height_cm = height.or(profile.height_cm);
+                        profile.max_hr = if clear_max_hr {
+                            None
+                        } else {
+                            max_hr.or(profile.max_hr)
+                        };
+                        profile.zone_basis = zone_basis.or(profile.zone_basis);
+                        db_handler.set_profile(profile).await?;
+                    }
+                }
+            }
             OpenWhoopCommand::SetAlarm { whoop, alarm_time } => {
                 let (peripheral, generation) = scan_command(&adapter, Some(whoop)).await?;
                 let mut whoop = WhoopDevice::new(
@@ -1095,13 +1811,158 @@ impl OpenWhoopCli {
                     error!("Unexpected response from device: {:?}", data);
                 }
             }
-            OpenWhoopCommand::GetBattery { whoop } => {
+            OpenWhoopCommand::GetBattery {
+                whoop,
+                extended,
+                log,
+            } => {
+                let device_id = whoop.to_string();
+                let (peripheral, generation) = scan_command(&adapter, Some(whoop)).await?;
+                let mut whoop =
+                    WhoopDevice::new(peripheral, adapter, db_handler, false, generation)
+                        .with_device_id(device_id);
+                whoop.connect().await?;
+
+                if !extended {
+                    let level = whoop.get_battery_level().await?;
+                    println!("Battery level: {}%", level);
+                    return Ok(());
+                }
+
+                whoop.initialize().await?;
+                let battery = whoop.get_extended_battery_info().await?;
+                println!("Battery level: {}%", battery.level);
+                println!(
+                    "Voltage:       {:.3} V",
+                    f64::from(battery.voltage_mv) / 1000.0
+                );
+                println!("Temperature:   {:.1} °C", battery.temperature_c);
+                println!("Charge state:  {}", battery.charge_state);
+                println!("Cycle count:   {}", battery.cycle_count);
+
+                let pack = match whoop.get_battery_pack_info().await {
+                    Ok(pack) => Some(pack),
+                    Err(e) => {
+                        warn!("Battery pack info unavailable: {}", e);
+                        None
+                    }
+                };
+                match pack {
+                    Some(pack) if pack.connected => println!(
+                        "Battery pack:  connected, {}% ({:.3} V, {})",
+                        pack.level,
+                        f64::from(pack.voltage_mv) / 1000.0,
+                        pack.charge_state
+                    ),
+                    Some(_) => println!("Battery pack:  not connected"),
+                    None => println!("Battery pack:  unknown"),
+                }
+
+                if log {
+                    whoop
+                        .record_battery_reading(&battery, pack.as_ref())
+                        .await?;
+                }
+            }
+            OpenWhoopCommand::Logs {
+                from,
+                to,
+                grep,
+                kind,
+                limit,
+            } => {
+                let logs = db_handler
+                    .search_console_logs(SearchConsoleLogs {
+                        from,
+                        to,
+                        grep,
+                        kind,
+                        limit,
+                    })
+                    .await?;
+
+                for log in logs {
+                    let time = log.time.format("%Y-%m-%d %H:%M:%S");
+                    match (log.kind, log.detail) {
+                        (Some(kind), Some(detail)) => {
+                            println!("{time} [{kind}: {detail}] {}", log.message)
+                        }
+                        (Some(kind), None) => println!("{time} [{kind}] {}", log.message),
+                        _ => println!("{time} {}", log.message),
+                    }
+                }
+            }
+            OpenWhoopCommand::ExportDiagnostics { output, days } => {
+                let since = Local::now().naive_local() - TimeDelta::days(days);
+                let path =
+                    diagnostics::export_diagnostics(&db_handler, Path::new(&output), since).await?;
+                println!("Diagnostics written to {}", path.display());
+            }
+            OpenWhoopCommand::BodyLocation { whoop } => {
+                let device_id = whoop.to_string();
+                let (peripheral, generation) = scan_command(&adapter, Some(whoop)).await?;
+                let mut whoop =
+                    WhoopDevice::new(peripheral, adapter, db_handler, false, generation)
+                        .with_device_id(device_id);
+                whoop.connect().await?;
+                whoop.initialize().await?;
+
+                let info = whoop.get_body_location().await?;
+                println!("Location: {}", info.location);
+                if let Some(wrist) = info.wrist {
+                    println!("Wrist:    {}", wrist);
+                }
+                println!(
+                    "Status:   {}",
+                    if info.on_body { "on body" } else { "off body" }
+                );
+            }
+            OpenWhoopCommand::SelectWrist { whoop, wrist } => {
                 let (peripheral, generation) = scan_command(&adapter, Some(whoop)).await?;
                 let mut whoop =
                     WhoopDevice::new(peripheral, adapter, db_handler, false, generation);
                 whoop.connect().await?;
-                let level = whoop.get_battery_level().await?;
-                println!("Battery level: {}%", level);
+                whoop.initialize().await?;
+                whoop.select_wrist(wrist).await?;
+                println!("Wrist set to {}", wrist);
+            }
+            OpenWhoopCommand::Clock { whoop, dry_run } => {
+                let device_id = whoop.to_string();
+                let (peripheral, generation) = scan_command(&adapter, Some(whoop)).await?;
+                let mut whoop =
+                    WhoopDevice::new(peripheral, adapter, db_handler, false, generation)
+                        .with_device_id(device_id);
+                whoop.connect().await?;
+                whoop.initialize().await?;
+
+                let drift = whoop.record_clock_drift().await?;
+                println!(
+                    "Strap time:  {}",
+                    drift
+                        .strap_time
+                        .with_timezone(&Local)
+                        .format("%Y-%m-%d %H:%M:%S")
+                );
+                println!(
+                    "System time: {}",
+                    drift
+                        .system_time
+                        .with_timezone(&Local)
+                        .format("%Y-%m-%d %H:%M:%S")
+                );
+                println!(
+                    "Drift: {:+.3}s",
+                    drift.offset.num_milliseconds() as f64 / 1000.0
+                );
+
+                if !dry_run {
+                    whoop.set_clock().await?;
+                    let drift = whoop.get_clock_drift().await?;
+                    println!(
+                        "Clock corrected, drift now: {:+.3}s",
+                        drift.offset.num_milliseconds() as f64 / 1000.0
+                    );
+                }
             }
             OpenWhoopCommand::Merge { from } => {
                 let from_db = DatabaseHandler::new(from).await;
@@ -1118,11 +1979,19 @@ impl OpenWhoopCli {
                         generation,
                         bytes,
                         id: c_id,
+                        time,
+                        device_id,
                     } in packets
                     {
                         id = c_id;
                         db_handler
-                            .create_packet(uuid, generation.parse()?, bytes)
+                            .create_packet(
+                                uuid,
+                                generation.parse()?,
+                                time,
+                                device_id.as_deref(),
+                                bytes,
+                            )
                             .await?;
                     }
 
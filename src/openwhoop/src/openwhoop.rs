@@ -1,9 +1,10 @@
-use chrono::{DateTime, Local, NaiveDate, TimeDelta};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
 use openwhoop_codec::{
-    HistoryReading, WhoopData, WhoopPacket,
+    BodyLocationInfo, HistoryReading, MemfaultChunkAssembler, ParsedHistoryReading, WhoopData,
+    WhoopPacket,
     constants::{
         CMD_FROM_STRAP_GEN4, CMD_FROM_STRAP_GEN5, DATA_FROM_STRAP_GEN4, DATA_FROM_STRAP_GEN5,
-        MetadataType, WhoopGeneration,
+        MEMFAULT_GEN4, MEMFAULT_GEN5, MetadataType, WhoopGeneration,
     },
 };
 use openwhoop_db::{DailyInfo, DailyStats, DailyStatsAverage, DatabaseHandler, SearchHistory};
@@ -11,7 +12,11 @@ use openwhoop_entities::packets;
 
 use crate::{
     algo::{
-        ActivityPeriod, MAX_SLEEP_PAUSE, SleepCycle, StressCalculator, helpers::format_hm::FormatHM,
+        ActivityClassifier, ActivityPeriod, AlertThresholds, HealthAlertDetector, HrvCalculator,
+        MAX_SLEEP_PAUSE, RecoveryBaseline, RecoveryCalculator, RespiratoryRateCalculator,
+        SkinTempCalibration, SkinTempDeviation, SleepCycle, SleepEpoch, SleepNeed,
+        SleepNeedCalculator, SleepScoreCalculator, SleepStager, SpO2Calculator, StepCounter,
+        StressCalculator, WearTimeline, Workout, WorkoutDetector, helpers::format_hm::FormatHM,
     },
     ble::BleNotification,
     types::activities,
@@ -22,7 +27,13 @@ pub struct OpenWhoop {
     pub packet: Option<WhoopPacket>,
     pub last_history_packet: Option<HistoryReading>,
     pub history_packets: Vec<HistoryReading>,
+    pub memfault: MemfaultChunkAssembler,
     pub generation: WhoopGeneration,
+    /// Strap the packets are captured from, when known
+    pub device_id: Option<String>,
+    /// Where the strap reported being worn before the readings being
+    /// downloaded were requested, stored as their wear window
+    pub wear_location: Option<BodyLocationInfo>,
 }
 
 impl OpenWhoop {
@@ -32,7 +43,10 @@ impl OpenWhoop {
             packet: None,
             last_history_packet: None,
             history_packets: Vec::new(),
+            memfault: MemfaultChunkAssembler::default(),
             generation,
+            device_id: None,
+            wear_location: None,
         }
     }
 
@@ -42,7 +56,13 @@ impl OpenWhoop {
     ) -> anyhow::Result<packets::Model> {
         let packet = self
             .database
-            .create_packet(notification.uuid, self.generation, notification.value)
+            .create_packet(
+                notification.uuid,
+                self.generation,
+                Some(Local::now().naive_local()),
+                self.device_id.as_deref(),
+                notification.value,
+            )
             .await?;
 
         Ok(packet)
@@ -57,9 +77,13 @@ impl OpenWhoop {
                 .generation
                 .parse()
                 .ok()
-                .or_else(|| match packet.uuid {
-                    DATA_FROM_STRAP_GEN4 | CMD_FROM_STRAP_GEN4 => Some(WhoopGeneration::Gen4),
-                    DATA_FROM_STRAP_GEN5 | CMD_FROM_STRAP_GEN5 => Some(WhoopGeneration::Gen5),
+                .or(match packet.uuid {
+                    DATA_FROM_STRAP_GEN4 | CMD_FROM_STRAP_GEN4 | MEMFAULT_GEN4 => {
+                        Some(WhoopGeneration::Gen4)
+                    }
+                    DATA_FROM_STRAP_GEN5 | CMD_FROM_STRAP_GEN5 | MEMFAULT_GEN5 => {
+                        Some(WhoopGeneration::Gen5)
+                    }
                     _ => None,
                 })
                 .ok_or_else(|| {
@@ -72,6 +96,12 @@ impl OpenWhoop {
             self.generation
         };
 
+        if matches!(packet.uuid, MEMFAULT_GEN4 | MEMFAULT_GEN5) {
+            self.handle_memfault_chunk(generation, &packet).await?;
+            return Ok(None);
+        }
+        let device_id = packet.device_id.clone().or_else(|| self.device_id.clone());
+
         let parse_packet = match generation {
             WhoopGeneration::Placeholder => {
                 return Err(anyhow::anyhow!(
@@ -132,10 +162,64 @@ impl OpenWhoop {
             _ => return Ok(None),
         };
 
-        self.handle_data(data).await
+        self.handle_data(data, device_id.as_deref()).await
+    }
+
+    /// Feed a Memfault chunk and store the message it completes, timed by
+    /// when its last packet was captured. Packets stored without a capture
+    /// time are timed now.
+    async fn handle_memfault_chunk(
+        &mut self,
+        generation: WhoopGeneration,
+        packet: &packets::Model,
+    ) -> anyhow::Result<()> {
+        match self.memfault.push(&packet.bytes) {
+            Ok(Some(message)) => {
+                info!(target: "Memfault", "diagnostic message: {} bytes in {} chunks", message.data.len(), message.chunks);
+                let time = packet.time.unwrap_or_else(|| Local::now().naive_local());
+                let device_id = packet.device_id.as_deref().or(self.device_id.as_deref());
+                self.database
+                    .create_diagnostic(generation, device_id, time, message)
+                    .await?;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                warn!(target: "Memfault", "dropping partial diagnostic message: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Store downloaded readings along with the wear window they cover, when
+    /// the strap's placement was queried before downloading them.
+    pub async fn store_readings(&self, readings: Vec<HistoryReading>) -> anyhow::Result<()> {
+        let to_local = |unix: u64| -> anyhow::Result<NaiveDateTime> {
+            DateTime::from_timestamp_millis(i64::try_from(unix)?)
+                .map(|time| time.with_timezone(&Local).naive_local())
+                .ok_or_else(|| anyhow::anyhow!("invalid reading timestamp: {unix}"))
+        };
+        let first = readings.iter().map(|reading| reading.unix).min();
+        let last = readings.iter().map(|reading| reading.unix).max();
+        if let (Some(info), Some(first), Some(last)) = (&self.wear_location, first, last) {
+            self.database
+                .record_wear_location(
+                    self.device_id.as_deref(),
+                    to_local(first)?,
+                    to_local(last)?,
+                    info,
+                )
+                .await?;
+        }
+
+        self.database.create_readings(readings).await
     }
 
-    async fn handle_data(&mut self, data: WhoopData) -> anyhow::Result<Option<WhoopPacket>> {
+    async fn handle_data(
+        &mut self,
+        data: WhoopData,
+        device_id: Option<&str>,
+    ) -> anyhow::Result<Option<WhoopPacket>> {
         match data {
             WhoopData::HistoryReading(hr) if hr.is_valid() => {
                 if let Some(last_packet) = self.last_history_packet.as_mut() {
@@ -166,19 +250,25 @@ impl OpenWhoop {
                 MetadataType::HistoryComplete => {}
                 MetadataType::HistoryStart => {}
                 MetadataType::HistoryEnd => {
-                    self.database
-                        .create_readings(std::mem::take(&mut self.history_packets))
-                        .await?;
+                    let readings = std::mem::take(&mut self.history_packets);
+                    self.store_readings(readings).await?;
 
                     let packet = WhoopPacket::history_end(end_data);
                     return Ok(Some(packet));
                 }
             },
-            WhoopData::ConsoleLog { log, .. } => {
+            WhoopData::ConsoleLog { unix, log } => {
                 trace!(target: "ConsoleLog", "{}", log);
+                self.database
+                    .create_console_log(device_id, unix, &log)
+                    .await?;
             }
             WhoopData::RunAlarm { .. } => {}
             WhoopData::AlarmInfo { .. } => {}
+            WhoopData::ClockInfo { .. } => {}
+            WhoopData::ExtendedBatteryInfo(_) => {}
+            WhoopData::BatteryPackInfo(_) => {}
+            WhoopData::BodyLocation(_) => {}
             WhoopData::Event { .. } => {}
             WhoopData::UnknownEvent { .. } => {}
             WhoopData::CommandResponse(_) => {}
@@ -221,6 +311,26 @@ impl OpenWhoop {
         self.database.get_strain_for_date(date).await
     }
 
+    pub async fn calculate_latest_calories(&self) -> anyhow::Result<()> {
+        self.database.calculate_latest_calories().await?;
+
+        Ok(())
+    }
+
+    /// Weekly VO2max from the resting and max heart rate, refined with the
+    /// week's walks and runs.
+    pub async fn calculate_latest_vo2max(&self) -> anyhow::Result<()> {
+        self.database.calculate_latest_vo2max().await
+    }
+
+    /// Daily heart rate zones, and zones for activities stored without them.
+    pub async fn calculate_latest_zones(&self) -> anyhow::Result<()> {
+        self.database.calculate_latest_zones().await?;
+        self.database.calculate_missing_activity_zones().await?;
+
+        Ok(())
+    }
+
     pub async fn get_daily_info(&self, date: NaiveDate) -> anyhow::Result<DailyInfo> {
         self.database.get_daily_info(date).await
     }
@@ -233,87 +343,379 @@ impl OpenWhoop {
         self.database.get_last_7_day_daily_stats_average().await
     }
 
+    /// Detect workouts in the history after the last detected one and
+    /// store them as activities with their strain.
     pub async fn detect_events(&self) -> anyhow::Result<()> {
-        Ok(())
-    }
+        const BATCH: u64 = 86400;
 
-    /// TODO: add handling for data splits
-    pub async fn detect_sleeps(&self) -> anyhow::Result<()> {
-        'a: loop {
-            let last_sleep = self.get_latest_sleep().await?;
+        let mut from = self.database.get_last_detected_activity_end().await?;
 
+        loop {
             let options = SearchHistory {
-                from: last_sleep.map(|s| s.end),
-                limit: Some(86400 * 2),
-                ..Default::default()
+                from,
+                to: None,
+                limit: Some(BATCH),
             };
 
-            let mut history = self.database.search_history(options).await?;
-            let mut periods = ActivityPeriod::detect_from_gravity(&history);
-
-            while let Some(mut sleep) = ActivityPeriod::find_sleep(&mut periods) {
-                if let Some(last_sleep) = last_sleep {
-                    let diff = sleep.start - last_sleep.end;
-
-                    if diff < MAX_SLEEP_PAUSE {
-                        history = self
-                            .database
-                            .search_history(SearchHistory {
-                                from: Some(last_sleep.start),
-                                to: Some(sleep.end),
-                                ..Default::default()
-                            })
-                            .await?;
-
-                        sleep.start = last_sleep.start;
-                        sleep.duration = sleep.end - sleep.start;
-                    } else {
-                        let this_sleep_id = sleep.end.date();
-                        let last_sleep_id = last_sleep.end.date();
-
-                        if this_sleep_id == last_sleep_id {
-                            if sleep.duration < last_sleep.duration() {
-                                let nap = activities::ActivityPeriod {
-                                    period_id: last_sleep.id,
-                                    from: sleep.start,
-                                    to: Some(sleep.end),
-                                    activity: activities::ActivityType::Nap,
-                                    strain: None,
-                                };
-                                self.database.create_activity(nap).await?;
-                                continue;
-                            } else {
-                                let nap = activities::ActivityPeriod {
-                                    period_id: last_sleep.id - TimeDelta::days(1),
-                                    from: last_sleep.start,
-                                    to: Some(last_sleep.end),
-                                    activity: activities::ActivityType::Nap,
-                                    strain: None,
-                                };
-                                self.database.create_activity(nap).await?;
-                            }
+            let history = self.database.search_history(options).await?;
+            let (Some(first), Some(last)) = (history.first(), history.last()) else {
+                break;
+            };
+
+            let mut next = last.time;
+            let max_hr = self.database.get_max_hr(first.time, last.time).await?;
+            let resting_hr = self.database.get_resting_hr_before(first.time).await?;
+            if let (Some(max_hr), Some(resting_hr)) = (max_hr, resting_hr) {
+                for workout in WorkoutDetector::new(resting_hr, max_hr).detect(&history) {
+                    // A workout running into the end of the batch may go on,
+                    // so it is detected again from the next batch.
+                    if last.time - workout.end < WorkoutDetector::MERGE_GAP {
+                        if workout.start > first.time {
+                            next = workout.start - TimeDelta::milliseconds(1);
                         }
+                        break;
                     }
+
+                    self.create_workout(workout, &history).await?;
                 }
+            }
+
+            if (history.len() as u64) < BATCH {
+                break;
+            }
+            from = Some(next);
+        }
+
+        Ok(())
+    }
+
+    /// Store a workout under the sleep that started its day, labelled from
+    /// its IMU data when there is any. Workouts before the first recorded
+    /// sleep have no period and are skipped.
+    async fn create_workout(
+        &self,
+        workout: Workout,
+        history: &[ParsedHistoryReading],
+    ) -> anyhow::Result<()> {
+        let Some(sleep) = self.database.get_sleep_before(workout.start).await? else {
+            return Ok(());
+        };
 
-                let sleep_cycle = SleepCycle::from_event(sleep, &history)?;
+        let activity = activities::ActivityPeriod {
+            period_id: sleep.id,
+            from: workout.start,
+            to: Some(workout.end),
+            activity: activities::ActivityType::Activity,
+            strain: None,
+            calories: None,
+        };
+        let strain = self
+            .database
+            .calculate_strain_for_activity(activity)
+            .await?;
+        let calories = self
+            .database
+            .calculate_calories_for_activity(activity)
+            .await?;
+        let zones = self.database.calculate_zones_for_activity(activity).await?;
+
+        let readings = history
+            .iter()
+            .filter(|r| r.time >= workout.start && r.time < workout.end)
+            .cloned()
+            .collect::<Vec<_>>();
+        let classification = ActivityClassifier::classify(&readings);
+
+        info!(
+            "Detected workout from {} to {}, duration: {}, avg bpm: {}, type: {}",
+            workout.start,
+            workout.end,
+            (workout.end - workout.start).format_hm(),
+            workout.avg_bpm,
+            classification.map_or_else(
+                || "unknown (no IMU data)".to_owned(),
+                |c| format!("{} ({:.0}%)", c.activity, c.confidence * 100.0)
+            )
+        );
+        self.database
+            .create_activity(activities::ActivityPeriod {
+                strain,
+                calories,
+                ..activity
+            })
+            .await?;
+        if let Some(zones) = zones {
+            self.database
+                .set_activity_zones(workout.start, zones)
+                .await?;
+        }
 
-                info!(
-                    "Detected sleep from {} to {}, duration: {}",
-                    sleep.start,
-                    sleep.end,
-                    sleep.duration.format_hm()
-                );
-                self.database.create_sleep(sleep_cycle).await?;
-                continue 'a;
+        if let Some(classification) = classification {
+            self.database
+                .classify_activity(workout.start, classification)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Sleep need for a night starting at `before`.
+    pub async fn sleep_need(&self, before: NaiveDateTime) -> anyhow::Result<SleepNeed> {
+        let previous = self.previous_sleeps(before).await?;
+        self.sleep_need_after(&previous, before).await
+    }
+
+    /// Sleeps that ended by `before`, oldest first, as far back as the
+    /// sleep need baseline looks.
+    async fn previous_sleeps(&self, before: NaiveDateTime) -> anyhow::Result<Vec<SleepCycle>> {
+        let lookback = TimeDelta::days(SleepNeedCalculator::BASELINE_NIGHTS as i64 + 1);
+        Ok(self
+            .database
+            .get_sleep_cycles(Some(before - lookback))
+            .await?
+            .into_iter()
+            .filter(|s| s.end <= before)
+            .collect())
+    }
+
+    /// Sleep need given the previous nights, the strain of the day since
+    /// the last of them and the naps taken during it.
+    async fn sleep_need_after(
+        &self,
+        previous: &[SleepCycle],
+        before: NaiveDateTime,
+    ) -> anyhow::Result<SleepNeed> {
+        let awake_since = previous.last().map(|s| s.end);
+        let day = awake_since.unwrap_or(before).date();
+
+        let strain = self
+            .database
+            .get_strain_for_date(day)
+            .await?
+            .map(|row| row.strain);
+
+        let naps = self
+            .database
+            .search_activities(activities::SearchActivityPeriods {
+                from: Some(awake_since.unwrap_or_else(|| day.and_time(NaiveTime::MIN))),
+                to: Some(before),
+                activity: Some(activities::ActivityType::Nap),
+            })
+            .await?
+            .into_iter()
+            .filter_map(|nap| nap.to.map(|to| to - nap.from))
+            .sum();
+
+        Ok(SleepNeedCalculator::calculate(previous, strain, naps))
+    }
+
+    /// Score a sleep against the nights before it.
+    async fn score_sleep(
+        &self,
+        sleep: SleepCycle,
+        epochs: &[SleepEpoch],
+    ) -> anyhow::Result<SleepCycle> {
+        let previous = self
+            .previous_sleeps(sleep.start)
+            .await?
+            .into_iter()
+            .filter(|s| s.id != sleep.id)
+            .collect::<Vec<_>>();
+
+        let sleep_need = self.sleep_need_after(&previous, sleep.start).await?;
+        let consistency = SleepScoreCalculator::consistency(&previous, &sleep)?;
+        let score =
+            SleepScoreCalculator::calculate(&sleep, epochs, sleep_need.total(), consistency);
+        Ok(sleep.with_score(score))
+    }
+
+    /// Detect sleeps in history recorded since the latest stored sleep
+    /// began, so a sleep stored from partial data is extended once the rest
+    /// of it is downloaded.
+    pub async fn detect_sleeps(&self) -> anyhow::Result<()> {
+        let from = self
+            .get_latest_sleep()
+            .await?
+            .map(|sleep| sleep.start - TimeDelta::milliseconds(1));
+        self.detect_sleeps_between(from, None).await
+    }
+
+    /// Detect sleeps in history between `from` and `to`. Stored sleeps that
+    /// a detected one overlaps or continues are merged with it and updated
+    /// in place, so re-running over any range gives the same sleeps.
+    pub async fn detect_sleeps_between(
+        &self,
+        from: Option<NaiveDateTime>,
+        to: Option<NaiveDateTime>,
+    ) -> anyhow::Result<()> {
+        const BATCH: u64 = 86400 * 2;
+
+        let mut from = from;
+        loop {
+            let history = self
+                .database
+                .search_history(SearchHistory {
+                    from,
+                    to,
+                    limit: Some(BATCH),
+                })
+                .await?;
+            let (Some(first), Some(last)) = (history.first(), history.last()) else {
+                break;
+            };
+            let full = history.len() as u64 == BATCH;
+
+            // Placement each reading was downloaded with; readings without a
+            // recorded placement are taken as wrist-worn
+            let wear = WearTimeline::new(
+                self.database
+                    .get_wear_locations_before(self.device_id.as_deref(), last.time)
+                    .await?
+                    .into_iter()
+                    .filter_map(|window| Some((window.start, window.location.parse().ok()?)))
+                    .collect(),
+            );
+            let mut sleeps = ActivityPeriod::detect_sleeps(&history, &wear);
+            let mut next = last.time;
+            // A sleep running into the end of the batch may continue in the
+            // next one, so it is detected again from there
+            let open = sleeps
+                .last()
+                .filter(|_| full)
+                .filter(|sleep| last.time - sleep.end < MAX_SLEEP_PAUSE)
+                .filter(|sleep| sleep.start > first.time)
+                .map(|sleep| sleep.start);
+            if let Some(open) = open {
+                next = open - TimeDelta::milliseconds(1);
+                sleeps.pop();
+            }
+
+            for sleep in sleeps {
+                self.store_sleep(sleep).await?;
+            }
+
+            if !full || from.is_some_and(|f| next <= f) {
+                break;
+            }
+            from = Some(next);
+        }
+
+        Ok(())
+    }
+
+    /// Store a detected sleep, merged with the stored sleeps it overlaps or
+    /// continues. Of two separate sleeps ending on the same day the longer
+    /// one is kept as the sleep and the other is stored as a nap.
+    async fn store_sleep(&self, sleep: ActivityPeriod) -> anyhow::Result<()> {
+        let merged = self
+            .database
+            .get_sleeps_between(sleep.start - MAX_SLEEP_PAUSE, sleep.end + MAX_SLEEP_PAUSE)
+            .await?;
+        let start = merged.iter().map(|s| s.start).fold(sleep.start, Ord::min);
+        let end = merged.iter().map(|s| s.end).fold(sleep.end, Ord::max);
+        let id = end.date();
+
+        let same_day = self
+            .database
+            .get_sleep_for_date(id)
+            .await?
+            .filter(|other| merged.iter().all(|s| s.id != other.id));
+
+        if let Some(other) = same_day.filter(|other| other.duration() >= end - start) {
+            for s in &merged {
+                self.database.merge_sleep_into(s.id, other.id).await?;
+            }
+            self.create_nap(other.id, start, end).await?;
+            if merged.is_empty() {
+                // Only a nap was added, the sleep itself is unchanged
+                return Ok(());
             }
+            // The merged sleeps' activities and naps now belong to it
+            return self
+                .calculate_sleep(ActivityPeriod {
+                    start: other.start,
+                    end: other.end,
+                    duration: other.duration(),
+                    ..sleep
+                })
+                .await;
+        }
 
-            break;
+        let mut stored = same_day.is_some() || merged.iter().any(|s| s.id == id);
+        for s in merged.iter().filter(|s| s.id != id) {
+            if stored {
+                self.database.merge_sleep_into(s.id, id).await?;
+            } else {
+                self.database.rename_sleep(s.id, id).await?;
+                stored = true;
+            }
+        }
+
+        self.calculate_sleep(ActivityPeriod {
+            start,
+            end,
+            duration: end - start,
+            ..sleep
+        })
+        .await?;
+
+        if let Some(other) = same_day {
+            self.create_nap(id, other.start, other.end).await?;
         }
 
         Ok(())
     }
 
+    /// Calculate and store the sleep over `sleep`'s bounds with its stages,
+    /// replacing the stored one of the same day.
+    async fn calculate_sleep(&self, sleep: ActivityPeriod) -> anyhow::Result<()> {
+        let (start, end) = (sleep.start, sleep.end);
+        let mut history = self
+            .database
+            .search_history(SearchHistory {
+                from: Some(start - TimeDelta::milliseconds(1)),
+                to: Some(end + TimeDelta::milliseconds(1)),
+                ..Default::default()
+            })
+            .await?;
+        history.retain(ActivityPeriod::is_worn);
+
+        let epochs = SleepStager::stage(&history, start, end);
+        let sleep_cycle = SleepCycle::from_event(sleep, &history)?.with_stages(&epochs);
+        let sleep_cycle = self.score_sleep(sleep_cycle, &epochs).await?;
+
+        info!(
+            "Detected sleep from {} to {}, duration: {}",
+            start,
+            end,
+            sleep.duration.format_hm()
+        );
+        self.database.create_sleep(sleep_cycle).await?;
+        self.database
+            .replace_sleep_stages(sleep_cycle.id, &epochs)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn create_nap(
+        &self,
+        period_id: NaiveDate,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> anyhow::Result<()> {
+        self.database
+            .create_activity(activities::ActivityPeriod {
+                period_id,
+                from,
+                to: Some(to),
+                activity: activities::ActivityType::Nap,
+                strain: None,
+                calories: None,
+            })
+            .await
+    }
+
     pub async fn calculate_stress(&self) -> anyhow::Result<()> {
         loop {
             let last_stress = self.database.last_stress_time().await?;
@@ -343,4 +745,485 @@ impl OpenWhoop {
 
         Ok(())
     }
+
+    /// HRV of every 5-minute window from the last scanned one on. That
+    /// window may have been partial, so it is calculated again.
+    pub async fn calculate_hrv_samples(&self) -> anyhow::Result<()> {
+        const BATCH: u64 = 86400;
+        let last_sample = self.database.last_hrv_sample_start().await?;
+        let processed_until = self.database.hrv_samples_processed_until().await?;
+        let mut from = last_sample
+            .max(processed_until)
+            .map(|start| start - TimeDelta::milliseconds(1));
+
+        loop {
+            let options = SearchHistory {
+                from,
+                to: None,
+                limit: Some(BATCH),
+            };
+
+            let history = self.database.search_history(options).await?;
+            let Some(last) = history.last() else {
+                break;
+            };
+
+            let samples = HrvCalculator::samples(&history);
+            self.database.create_hrv_samples(&samples).await?;
+            // Remember the scan even when it stored nothing, so history
+            // without usable RR intervals isn't scanned again next run
+            let last_window = HrvCalculator::window_start(last.time);
+            self.database
+                .set_hrv_samples_processed_until(last_window)
+                .await?;
+
+            if (history.len() as u64) < BATCH {
+                break;
+            }
+            // Calculate the last, possibly cut off, window again with the next batch
+            let next = last_window - TimeDelta::milliseconds(1);
+            if from.is_some_and(|from| next <= from) {
+                break;
+            }
+            from = Some(next);
+        }
+
+        Ok(())
+    }
+
+    /// Backfill `spo2` on readings after the last estimated one. Unlike
+    /// stress, many windows yield no estimate (off wrist, poor signal), so
+    /// the cursor advances by batch instead of by the last stored value.
+    pub async fn calculate_spo2(&self) -> anyhow::Result<()> {
+        const BATCH: u64 = 86400;
+        let overlap = TimeDelta::seconds(i64::try_from(SpO2Calculator::WINDOW).unwrap_or(0));
+        let mut from = self.database.last_spo2_time().await?.map(|t| t - overlap);
+
+        loop {
+            let options = SearchHistory {
+                from,
+                to: None,
+                limit: Some(BATCH),
+            };
+
+            let history = self.database.search_history(options).await?;
+            if history.len() < SpO2Calculator::WINDOW {
+                break;
+            }
+
+            let scores = history
+                .windows(SpO2Calculator::WINDOW)
+                .filter_map(SpO2Calculator::calculate_spo2);
+
+            for spo2 in scores {
+                self.database.update_spo2_on_reading(spo2).await?;
+            }
+
+            if (history.len() as u64) < BATCH {
+                break;
+            }
+            from = Some(history[history.len() - SpO2Calculator::WINDOW].time);
+        }
+
+        Ok(())
+    }
+
+    /// Count steps per minute from the last stored minute on. That minute
+    /// may have been partial, so it is counted again.
+    pub async fn calculate_steps(&self) -> anyhow::Result<()> {
+        const BATCH: u64 = 86400;
+        let mut from = self
+            .database
+            .last_step_minute()
+            .await?
+            .map(|minute| minute - TimeDelta::milliseconds(1));
+
+        loop {
+            let options = SearchHistory {
+                from,
+                to: None,
+                limit: Some(BATCH),
+            };
+
+            let history = self.database.search_history(options).await?;
+            let Some(last) = history.last() else {
+                break;
+            };
+
+            let minutes = StepCounter::count(&history);
+            self.database.create_step_minutes(&minutes).await?;
+
+            if (history.len() as u64) < BATCH {
+                break;
+            }
+            // Count the last, possibly cut off, minute again with the next batch
+            let next = minutes.last().map_or(last.time, |minute| {
+                minute.minute - TimeDelta::milliseconds(1)
+            });
+            if from.is_some_and(|from| next <= from) {
+                break;
+            }
+            from = Some(next);
+        }
+
+        Ok(())
+    }
+
+    /// Convert raw thermistor values into `skin_temp` for readings after the
+    /// last converted one, then compute nightly deviations for new sleeps.
+    /// Generations without a calibration are skipped.
+    pub async fn calculate_skin_temp(&self) -> anyhow::Result<()> {
+        const BATCH: u64 = 86400;
+        let generation = self.skin_temp_generation().await?;
+        let Some(calibration) = SkinTempCalibration::for_generation(generation) else {
+            warn!("No skin temperature calibration for {generation}, skipping");
+            return Ok(());
+        };
+
+        let mut from = self.database.last_skin_temp_time().await?;
+        loop {
+            let options = SearchHistory {
+                from,
+                to: None,
+                limit: Some(BATCH),
+            };
+
+            let history = self.database.search_history(options).await?;
+            let Some(last) = history.last() else {
+                break;
+            };
+
+            for reading in history.iter().filter_map(|r| calibration.convert(r)) {
+                self.database.update_skin_temp_on_reading(reading).await?;
+            }
+
+            if (history.len() as u64) < BATCH {
+                break;
+            }
+            from = Some(last.time);
+        }
+
+        for sleep in self.database.get_sleeps_without_skin_temp().await? {
+            let temps = self.database.get_skin_temps(sleep.start, sleep.end).await?;
+            let Some(nightly) = SkinTempDeviation::nightly(&temps) else {
+                continue;
+            };
+
+            let baseline = self.database.get_skin_temp_baseline(sleep.sleep_id).await?;
+            let deviation = SkinTempDeviation::deviation(nightly, &baseline);
+            self.database
+                .update_sleep_skin_temp(sleep.sleep_id, nightly, deviation)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Estimate the respiratory rate of stored sleeps that have none, e.g.
+    /// ones detected before it was calculated. Sleeps without enough usable
+    /// data stay empty.
+    pub async fn calculate_resp_rate(&self) -> anyhow::Result<()> {
+        for sleep in self.database.get_sleeps_without_resp_rate().await? {
+            let mut history = self
+                .database
+                .search_history(SearchHistory {
+                    from: Some(sleep.start - TimeDelta::milliseconds(1)),
+                    to: Some(sleep.end + TimeDelta::milliseconds(1)),
+                    ..Default::default()
+                })
+                .await?;
+            history.retain(ActivityPeriod::is_worn);
+
+            if let Some(resp_rate) = RespiratoryRateCalculator::calculate(&history) {
+                self.database
+                    .update_sleep_resp_rate(sleep.sleep_id, resp_rate)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Score recovery for every sleep since the latest stored recovery,
+    /// which is recalculated in case its night changed, against the 7-day
+    /// daily stats average of the nights before it.
+    pub async fn calculate_recovery(&self) -> anyhow::Result<()> {
+        let sleeps = self.database.get_sleep_cycles(None).await?;
+        let recalc_from = self.database.get_latest_recovery().await?.map(|r| r.date);
+
+        for sleep in sleeps {
+            if recalc_from.is_some_and(|date| sleep.id < date) {
+                continue;
+            }
+
+            let DailyStatsAverage::Average(DailyStats {
+                hrv: Some(hrv),
+                rhr: Some(rhr),
+                resp_rate,
+            }) = self
+                .database
+                .get_7_day_daily_stats_average(sleep.id - TimeDelta::days(1))
+                .await?
+            else {
+                continue;
+            };
+
+            let deviation = self
+                .database
+                .get_sleep_skin_temp_deviation(sleep.id)
+                .await?;
+            let baseline = RecoveryBaseline {
+                hrv,
+                rhr,
+                resp_rate,
+            };
+            let recovery = RecoveryCalculator::calculate(&sleep, deviation, &baseline);
+            self.database.create_or_update_recovery(recovery).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Detect health alerts over all nights with `thresholds`, replacing the
+    /// stored ones.
+    pub async fn calculate_health_alerts(&self, thresholds: AlertThresholds) -> anyhow::Result<()> {
+        let nights = self.database.get_nightly_metrics().await?;
+        let alerts = HealthAlertDetector::new(thresholds).detect(&nights);
+        self.database.replace_health_alerts(&alerts).await
+    }
+
+    /// Generation whose calibration applies to stored readings: the one this
+    /// instance was created for, else the most recently seen strap, else Gen4
+    /// (the only generation whose history carries the thermistor value).
+    async fn skin_temp_generation(&self) -> anyhow::Result<WhoopGeneration> {
+        if !matches!(self.generation, WhoopGeneration::Placeholder) {
+            return Ok(self.generation);
+        }
+
+        Ok(self
+            .database
+            .get_devices()
+            .await?
+            .into_iter()
+            .find_map(|device| device.generation.parse().ok())
+            .filter(|generation| !matches!(generation, WhoopGeneration::Placeholder))
+            .unwrap_or(WhoopGeneration::Gen4))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use openwhoop_codec::{Activity, SensorData};
+
+    fn stale_sleep(start: NaiveDateTime, end: NaiveDateTime) -> SleepCycle {
+        SleepCycle {
+            id: end.date(),
+            start,
+            end,
+            min_bpm: 1,
+            max_bpm: 1,
+            avg_bpm: 1,
+            min_hrv: 1,
+            max_hrv: 1,
+            avg_hrv: 1,
+            score: 0.0,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn longer_same_day_sleep_absorbing_segments_is_recalculated() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let day = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+        let at = |date: NaiveDate, h, m| date.and_hms_opt(h, m, 0).unwrap();
+        let evening = day.pred_opt().unwrap();
+
+        // The night's sleep, and an evening segment the detected sleep continues
+        let night = stale_sleep(at(day, 2, 0), at(day, 10, 0));
+        let segment = stale_sleep(at(evening, 21, 0), at(evening, 23, 30));
+        db.create_sleep(night).await.unwrap();
+        db.create_sleep(segment).await.unwrap();
+
+        let readings = (0..8 * 60)
+            .map(|minute| HistoryReading {
+                unix: u64::try_from(
+                    Local
+                        .from_local_datetime(&(night.start + TimeDelta::minutes(minute)))
+                        .unwrap()
+                        .timestamp_millis(),
+                )
+                .unwrap(),
+                bpm: 55,
+                rr: vec![1090],
+                imu_data: vec![],
+                sensor_data: None,
+            })
+            .collect();
+        db.create_readings(readings).await.unwrap();
+
+        let whoop = OpenWhoop::new(db, WhoopGeneration::Gen4);
+        let start = at(evening, 23, 45);
+        let end = at(day, 0, 30);
+        whoop
+            .store_sleep(ActivityPeriod {
+                activity: Activity::Sleep,
+                start,
+                end,
+                duration: end - start,
+            })
+            .await
+            .unwrap();
+
+        let sleeps = whoop.database.get_sleep_cycles(None).await.unwrap();
+        assert_eq!(sleeps.len(), 1);
+        assert_eq!((sleeps[0].id, sleeps[0].start), (day, night.start));
+        assert_eq!(sleeps[0].avg_bpm, 55);
+
+        let naps = whoop
+            .database
+            .search_activities(
+                activities::SearchActivityPeriods::default()
+                    .with_activity(activities::ActivityType::Nap),
+            )
+            .await
+            .unwrap();
+        assert_eq!(naps.len(), 1);
+        assert_eq!((naps[0].period_id, naps[0].from), (day, segment.start));
+    }
+
+    #[tokio::test]
+    async fn nap_leaves_the_longer_same_day_sleep_as_stored() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let day = NaiveDate::from_ymd_opt(2025, 1, 2).unwrap();
+        let at = |h, m| day.and_hms_opt(h, m, 0).unwrap();
+
+        let night = stale_sleep(at(2, 0), at(10, 0));
+        db.create_sleep(night).await.unwrap();
+
+        let whoop = OpenWhoop::new(db, WhoopGeneration::Gen4);
+        let (start, end) = (at(15, 0), at(16, 0));
+        whoop
+            .store_sleep(ActivityPeriod {
+                activity: Activity::Sleep,
+                start,
+                end,
+                duration: end - start,
+            })
+            .await
+            .unwrap();
+
+        // Not calculated again over readings it has none of
+        let sleeps = whoop.database.get_sleep_cycles(None).await.unwrap();
+        assert_eq!(sleeps, vec![night]);
+
+        let naps = whoop
+            .database
+            .search_activities(
+                activities::SearchActivityPeriods::default()
+                    .with_activity(activities::ActivityType::Nap),
+            )
+            .await
+            .unwrap();
+        assert_eq!(naps.len(), 1);
+        assert_eq!((naps[0].period_id, naps[0].from), (day, start));
+    }
+
+    #[tokio::test]
+    async fn memfault_message_is_timed_by_capture_and_stored_once() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let captured = NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let packet = db
+            .create_packet(
+                MEMFAULT_GEN5,
+                WhoopGeneration::Gen5,
+                Some(captured),
+                Some("strap"),
+                vec![0x00, 1, 2, 3],
+            )
+            .await
+            .unwrap();
+
+        // Re-running the stored packets doesn't store the message again
+        let mut whoop = OpenWhoop::new(db.clone(), WhoopGeneration::Placeholder);
+        for _ in 0..2 {
+            whoop.handle_packet(packet.clone()).await.unwrap();
+        }
+
+        let diagnostics = db.get_diagnostics(None).await.unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].time, captured);
+        assert_eq!(diagnostics[0].device_id, "strap");
+        assert_eq!(diagnostics[0].data, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn skin_temp_is_skipped_without_a_calibration() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let whoop = OpenWhoop::new(db, WhoopGeneration::Gen5);
+
+        whoop
+            .calculate_skin_temp()
+            .await
+            .expect("an uncalibrated generation is skipped, not an error");
+    }
+
+    #[tokio::test]
+    async fn resp_rate_is_backfilled_for_sleeps_without_one() {
+        let db = DatabaseHandler::new("sqlite::memory:").await;
+        let end = NaiveDate::from_ymd_opt(2025, 1, 2)
+            .unwrap()
+            .and_hms_opt(6, 0, 0)
+            .unwrap();
+        let sleep = stale_sleep(end - TimeDelta::minutes(10), end);
+        db.create_sleep(sleep).await.unwrap();
+
+        // 15 breaths per minute reported by the strap
+        let readings = (0..10 * 60)
+            .map(|second| HistoryReading {
+                unix: u64::try_from(
+                    Local
+                        .from_local_datetime(&(sleep.start + TimeDelta::seconds(second)))
+                        .unwrap()
+                        .timestamp_millis(),
+                )
+                .unwrap(),
+                bpm: 55,
+                rr: vec![],
+                imu_data: vec![],
+                sensor_data: Some(SensorData {
+                    ppg_green: 0,
+                    ppg_red_ir: 0,
+                    spo2_red: 0,
+                    spo2_ir: 0,
+                    skin_temp_raw: 0,
+                    ambient_light: 0,
+                    led_drive_1: 0,
+                    led_drive_2: 0,
+                    resp_rate_raw: 1500,
+                    signal_quality: 0,
+                    skin_contact: 70,
+                    accel_gravity: [0.0, 0.0, 1.0],
+                    spo2_pct: None,
+                }),
+            })
+            .collect();
+        db.create_readings(readings).await.unwrap();
+
+        let whoop = OpenWhoop::new(db.clone(), WhoopGeneration::Gen4);
+        whoop.calculate_resp_rate().await.unwrap();
+
+        let resp_rate = db
+            .get_sleep_for_date(sleep.id)
+            .await
+            .unwrap()
+            .and_then(|sleep| sleep.resp_rate)
+            .expect("resp rate should be backfilled");
+        assert!((resp_rate.avg - 15.0).abs() < 1e-9);
+        assert!(db.get_sleeps_without_resp_rate().await.unwrap().is_empty());
+    }
 }
@@ -4,7 +4,10 @@ use futures::{Stream, StreamExt};
 use openwhoop_codec::{
     DataRangeInfo, GetDataRangeResponse, HistoryReading, WhoopCommandResponse, WhoopData,
     WhoopPacket,
-    constants::{CMD_FROM_STRAP_GEN5, DATA_FROM_STRAP_GEN5, EVENTS_FROM_STRAP_GEN5, MetadataType},
+    constants::{
+        CMD_FROM_STRAP_GEN5, DATA_FROM_STRAP_GEN5, EVENTS_FROM_STRAP_GEN5, MEMFAULT_GEN5,
+        MetadataType,
+    },
 };
 use std::{
     collections::VecDeque,
@@ -330,7 +333,11 @@ where
                 | WhoopData::UnknownEvent { .. }
                 | WhoopData::VersionInfo { .. }
                 | WhoopData::RealtimeHr { .. }
-                | WhoopData::AlarmInfo { .. } => {
+                | WhoopData::AlarmInfo { .. }
+                | WhoopData::ClockInfo { .. }
+                | WhoopData::ExtendedBatteryInfo(_)
+                | WhoopData::BatteryPackInfo(_)
+                | WhoopData::BodyLocation(_) => {
                     self.saw_non_history_packets = true;
                 }
             }
@@ -420,8 +427,7 @@ where
 
         self.device
             .whoop
-            .database
-            .create_readings(std::mem::take(&mut self.pending_readings))
+            .store_readings(std::mem::take(&mut self.pending_readings))
             .await
             .context(context)?;
 
@@ -507,7 +513,7 @@ where
     }
 
     async fn decode_notification(
-        &self,
+        &mut self,
         notification: BleNotification,
     ) -> anyhow::Result<Option<WhoopPacket>> {
         let packet = self.device.notification_to_model(notification).await?;
@@ -524,6 +530,10 @@ where
                     }
                 }
             }
+            MEMFAULT_GEN5 => {
+                self.device.whoop.handle_packet(packet).await?;
+                Ok(None)
+            }
             _ => Ok(None),
         }
     }